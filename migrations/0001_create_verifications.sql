@@ -0,0 +1,10 @@
+-- Successful verifications, keyed by chain and contract address.
+CREATE TABLE IF NOT EXISTS verifications (
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    repo_url TEXT NOT NULL,
+    repo_commit TEXT NOT NULL,
+    verified_at INTEGER NOT NULL,
+    verification TEXT NOT NULL,
+    PRIMARY KEY (chain_id, address)
+);
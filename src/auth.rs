@@ -0,0 +1,153 @@
+use crate::routes::VerifyError;
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Environment variable listing the API keys that may call protected routes, separated by commas.
+/// Each key may be followed by `:` and the name of the rate limit bucket it's counted against,
+/// such as `key1:partner,key2:partner,key3`. Keys without a bucket each get their own.
+const API_KEYS_ENV_VAR: &str = "COVE_API_KEYS";
+
+/// Header that API keys can be sent in, as an alternative to `Authorization: Bearer <key>`.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// The API keys that may call protected routes. When there are none, authentication is disabled
+/// and every request is allowed, which is the default for local development.
+#[derive(Debug, Default)]
+pub struct ApiKeys {
+    /// The rate limit bucket of each key, keyed by the key.
+    buckets: HashMap<String, String>,
+}
+
+/// The caller of a request that passed authentication, added to the request's extensions so later
+/// handlers and middleware, such as rate limiting, know who made it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyIdentity {
+    /// The rate limit bucket the caller's key is counted against.
+    pub bucket: String,
+}
+
+impl ApiKeys {
+    /// Reads the API keys from `COVE_API_KEYS`. If it's unset or empty, authentication is
+    /// disabled.
+    pub fn from_env() -> Self {
+        std::env::var(API_KEYS_ENV_VAR).map(|keys| Self::parse(&keys)).unwrap_or_default()
+    }
+
+    /// Parses a comma-separated list of keys, each optionally followed by `:<bucket>`.
+    fn parse(keys: &str) -> Self {
+        let buckets = keys
+            .split(',')
+            .filter_map(|entry| {
+                let (key, bucket) = entry.split_once(':').unwrap_or((entry, ""));
+                let (key, bucket) = (key.trim(), bucket.trim());
+                if key.is_empty() {
+                    return None
+                }
+                let bucket =
+                    if bucket.is_empty() { default_bucket(key) } else { bucket.to_string() };
+                Some((key.to_string(), bucket))
+            })
+            .collect();
+        Self { buckets }
+    }
+
+    /// Returns true if requests must include a valid API key.
+    pub fn is_enabled(&self) -> bool {
+        !self.buckets.is_empty()
+    }
+
+    /// Returns the identity of the caller with the API key in `headers`, or an `Unauthorized`
+    /// error if there's no key or it isn't valid. The key is read from `x-api-key`, or from the
+    /// `Authorization` header as a bearer token.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<ApiKeyIdentity, VerifyError> {
+        let api_key = headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok());
+        let bearer_token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let Some(key) = api_key.or(bearer_token).map(str::trim) else {
+            let msg =
+                format!("An API key is required in the {API_KEY_HEADER} or Authorization header");
+            return Err(VerifyError::Unauthorized(msg))
+        };
+        match self.buckets.get(key) {
+            Some(bucket) => Ok(ApiKeyIdentity { bucket: bucket.clone() }),
+            None => Err(VerifyError::Unauthorized("The API key is not valid".to_string())),
+        }
+    }
+}
+
+/// Middleware that rejects requests without a valid API key with a `401`, if API keys are
+/// configured. Requests that pass have their `ApiKeyIdentity` added to their extensions.
+pub async fn require_api_key<B>(
+    State(api_keys): State<Arc<ApiKeys>>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if !api_keys.is_enabled() {
+        return next.run(request).await
+    }
+    match api_keys.authenticate(request.headers()) {
+        Ok(identity) => {
+            request.extensions_mut().insert(identity);
+            next.run(request).await
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Returns the name of the bucket for a key configured without one. This is derived from a hash of
+/// the key, so the key itself doesn't end up in logs.
+fn default_bucket(key: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    format!("key-{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderName, HeaderValue};
+
+    #[test]
+    fn test_authenticate() {
+        let api_keys = ApiKeys::parse(" first-key:partner, second-key:partner,third-key, ");
+        assert!(api_keys.is_enabled());
+        assert!(!ApiKeys::parse("").is_enabled());
+
+        let api_key_header = HeaderName::from_static(API_KEY_HEADER);
+        let authenticate = |name: &HeaderName, value: &str| {
+            let mut headers = HeaderMap::new();
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.insert(name.clone(), value);
+            }
+            api_keys.authenticate(&headers)
+        };
+        let bucket = |result: Result<ApiKeyIdentity, VerifyError>| {
+            result.map(|identity| identity.bucket).map_err(|e| e.code())
+        };
+
+        // Keys can be sent in either header, and keys sharing a bucket are counted together.
+        assert_eq!(bucket(authenticate(&api_key_header, "first-key")), Ok("partner".to_string()));
+        let bearer = authenticate(&header::AUTHORIZATION, "Bearer second-key");
+        assert_eq!(bucket(bearer), Ok("partner".to_string()));
+        // Keys without a bucket get their own, which doesn't contain the key.
+        let own_bucket = bucket(authenticate(&api_key_header, "third-key")).unwrap_or_default();
+        assert!(own_bucket.starts_with("key-") && !own_bucket.contains("third"));
+
+        // Missing and unknown keys are rejected.
+        assert_eq!(bucket(api_keys.authenticate(&HeaderMap::new())), Err("UNAUTHORIZED"));
+        assert_eq!(bucket(authenticate(&api_key_header, "wrong-key")), Err("UNAUTHORIZED"));
+        let basic = authenticate(&header::AUTHORIZATION, "Basic Zmlyc3Qta2V5");
+        assert_eq!(bucket(basic), Err("UNAUTHORIZED"));
+    }
+}
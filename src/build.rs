@@ -0,0 +1,649 @@
+use semver::Version;
+use std::{
+    env,
+    error::Error,
+    io::{self, Read},
+    os::unix::process::CommandExt,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{Mutex, Semaphore},
+    task::JoinSet,
+};
+
+/// Environment variable used to override the maximum time a single build command may run for, in
+/// seconds.
+const BUILD_TIMEOUT_ENV_VAR: &str = "BUILD_TIMEOUT_SECS";
+
+/// Default maximum time a single build command may run for.
+const DEFAULT_BUILD_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Default maximum number of bytes captured from each of a build command's stdout and stderr.
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Environment variable used to override the maximum number of build commands run concurrently
+/// for a single verification.
+const BUILD_CONCURRENCY_ENV_VAR: &str = "BUILD_CONCURRENCY";
+
+/// Default maximum number of build commands run concurrently for a single verification.
+const DEFAULT_BUILD_CONCURRENCY: usize = 4;
+
+/// Environment variable that enables installing the dependencies a project declares but doesn't
+/// contain before building it.
+const INSTALL_DEPENDENCIES_ENV_VAR: &str = "INSTALL_DEPENDENCIES";
+
+/// Maximum number of compiler errors included in the message returned when no build succeeds.
+const MAX_REPORTED_ERRORS: usize = 10;
+
+/// Maximum number of characters of compiler diagnostics included in the message returned when no
+/// build succeeds.
+const MAX_DIAGNOSTICS_CHARS: usize = 4000;
+
+/// How often a running build command is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Held while installing compilers, so concurrent verifications that need the same solc version
+/// don't download it twice.
+static SOLC_INSTALL_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Limits applied to every build command, so a broken or malicious repository can't hang the
+/// server or exhaust its memory.
+#[derive(Debug, Clone)]
+pub struct BuildLimits {
+    /// The maximum time a build command may run for before it's killed.
+    pub timeout: Duration,
+    /// The maximum number of bytes captured from each of stdout and stderr. Output past this is
+    /// discarded.
+    pub max_output_bytes: usize,
+    /// The maximum number of build commands run at once.
+    pub concurrency: usize,
+}
+
+impl Default for BuildLimits {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_BUILD_TIMEOUT,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            concurrency: DEFAULT_BUILD_CONCURRENCY,
+        }
+    }
+}
+
+impl BuildLimits {
+    /// Returns the default limits, with the timeout and concurrency overridden by the
+    /// `BUILD_TIMEOUT_SECS` and `BUILD_CONCURRENCY` environment variables if they're set to valid
+    /// numbers.
+    pub fn from_env() -> Self {
+        let timeout = env::var(BUILD_TIMEOUT_ENV_VAR)
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map_or(DEFAULT_BUILD_TIMEOUT, Duration::from_secs);
+        let concurrency = env::var(BUILD_CONCURRENCY_ENV_VAR)
+            .ok()
+            .and_then(|concurrency| concurrency.parse().ok())
+            .filter(|concurrency| *concurrency > 0)
+            .unwrap_or(DEFAULT_BUILD_CONCURRENCY);
+        Self { timeout, concurrency, ..Self::default() }
+    }
+}
+
+/// How a build command finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildStatus {
+    /// The command exited on its own with the given status.
+    Exited(ExitStatus),
+    /// The command ran longer than the timeout and was killed.
+    TimedOut,
+    /// The command was killed because the verification it was part of was cancelled, such as when
+    /// the verification exceeded its total timeout.
+    Cancelled,
+}
+
+/// The result of running a build command.
+#[derive(Debug)]
+pub struct BuildOutput {
+    /// How the command finished.
+    pub status: BuildStatus,
+    /// The captured stdout, truncated to `BuildLimits::max_output_bytes`. Empty if the command
+    /// timed out.
+    pub stdout: Vec<u8>,
+    /// The captured stderr, truncated to `BuildLimits::max_output_bytes`. Empty if the command
+    /// timed out.
+    pub stderr: Vec<u8>,
+    /// How long the command ran for, until it exited or was killed.
+    pub duration: Duration,
+}
+
+impl BuildOutput {
+    /// Returns true if the command exited successfully.
+    pub fn success(&self) -> bool {
+        matches!(self.status, BuildStatus::Exited(status) if status.success())
+    }
+
+    /// Returns the compiler errors in the command's output, such as
+    /// `Error (2314): Expected ';' but got '}' --> src/Counter.sol:7:5`. Compilers print each error
+    /// followed by the location it refers to, so the two are joined. If no errors can be found,
+    /// the last lines of stderr are returned instead, since they usually explain the failure.
+    pub fn diagnostics(&self) -> Vec<String> {
+        let output = strip_ansi_codes(&format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&self.stdout),
+            String::from_utf8_lossy(&self.stderr)
+        ));
+        let lines: Vec<&str> = output.lines().map(str::trim).collect();
+
+        let mut errors = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if !is_compiler_error(line) {
+                continue
+            }
+            // The location is on one of the next couple of lines, after the error message.
+            let location =
+                lines.iter().skip(index + 1).take(2).find(|line| line.starts_with("-->"));
+            errors.push(match location {
+                Some(location) => format!("{line} {}", location.trim_end_matches(':')),
+                None => line.to_string(),
+            });
+        }
+        if !errors.is_empty() {
+            return errors
+        }
+
+        let stderr = strip_ansi_codes(&String::from_utf8_lossy(&self.stderr));
+        let last_lines: Vec<String> = stderr
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+        last_lines[last_lines.len().saturating_sub(5)..].to_vec()
+    }
+}
+
+/// Returns true if the line is an error reported by the compiler, such as
+/// `Error (2314): Expected ';' but got '}'` or `TypeError: Undeclared identifier.`, rather than the
+/// summary line forge prints after them.
+fn is_compiler_error(line: &str) -> bool {
+    let Some((kind, message)) = line.split_once(": ") else { return false };
+    let kind = kind.split(" (").next().unwrap_or(kind);
+    kind.ends_with("Error")
+        && kind.chars().all(|c| c.is_ascii_alphabetic())
+        && !message.starts_with("Compiler run failed")
+}
+
+/// Removes the ANSI escape codes that compilers use to color their output.
+fn strip_ansi_codes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            stripped.push(c);
+            continue
+        }
+        // Escape codes are `ESC [`, then parameters, then a final letter.
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break
+                }
+            }
+        }
+    }
+    stripped
+}
+
+/// Returns the error message to report when none of the builds succeeded, including the compiler
+/// errors from their output so the caller can see why the project didn't compile. The errors are
+/// deduplicated, since every profile usually fails the same way, and truncated.
+pub fn build_failure_message<'a>(outputs: impl IntoIterator<Item = &'a BuildOutput>) -> String {
+    let msg = "No build of the project succeeded".to_string();
+    let mut diagnostics: Vec<String> = Vec::new();
+    for diagnostic in outputs.into_iter().flat_map(BuildOutput::diagnostics) {
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    if diagnostics.is_empty() {
+        return msg
+    }
+
+    let omitted = diagnostics.len().saturating_sub(MAX_REPORTED_ERRORS);
+    diagnostics.truncate(MAX_REPORTED_ERRORS);
+    let mut diagnostics = diagnostics.join("\n");
+    if let Some((index, _)) = diagnostics.char_indices().nth(MAX_DIAGNOSTICS_CHARS) {
+        diagnostics.truncate(index);
+        diagnostics.push_str("...");
+    }
+    if omitted > 0 {
+        diagnostics.push_str(&format!("\n...and {omitted} more"));
+    }
+    format!("{msg}:\n{diagnostics}")
+}
+
+/// Runs the build command, killing it if it exceeds the timeout and capturing at most
+/// `max_output_bytes` of each of stdout and stderr.
+///
+/// The command runs in its own process group so that on timeout any processes it spawned, such as
+/// the `node` processes started by Hardhat's `npx` build command, are killed along with it.
+pub fn run_build_command(command: &mut Command, limits: &BuildLimits) -> io::Result<BuildOutput> {
+    run_cancellable_build_command(command, limits, &AtomicBool::new(false))
+}
+
+/// Runs the build command like `run_build_command`, but also kills it as soon as `cancelled` is
+/// set.
+fn run_cancellable_build_command(
+    command: &mut Command,
+    limits: &BuildLimits,
+    cancelled: &AtomicBool,
+) -> io::Result<BuildOutput> {
+    let started_at = Instant::now();
+    let mut child =
+        command.stdout(Stdio::piped()).stderr(Stdio::piped()).process_group(0).spawn()?;
+    let stdout = capture_output(child.stdout.take(), limits.max_output_bytes);
+    let stderr = capture_output(child.stderr.take(), limits.max_output_bytes);
+
+    let deadline = started_at + limits.timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break BuildStatus::Exited(status)
+        }
+        if Instant::now() >= deadline {
+            kill_process_group(&mut child)?;
+            break BuildStatus::TimedOut
+        }
+        if cancelled.load(Ordering::Relaxed) {
+            kill_process_group(&mut child)?;
+            break BuildStatus::Cancelled
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    // After the command is killed, processes that escaped the process group may still hold the
+    // pipes open, so we don't wait for the readers to finish.
+    let (stdout, stderr) = match status {
+        BuildStatus::Exited(_) => (join_output(stdout), join_output(stderr)),
+        BuildStatus::TimedOut | BuildStatus::Cancelled => (Vec::new(), Vec::new()),
+    };
+    let duration = started_at.elapsed();
+    Ok(BuildOutput { status, stdout, stderr, duration })
+}
+
+/// Runs the build commands concurrently, at most `limits.concurrency` at a time, and returns their
+/// results in the same order as the commands. Each command is subject to the same limits as
+/// `run_build_command`. If the returned future is dropped before it completes, such as when the
+/// verification times out, any commands still running are killed.
+pub async fn run_build_commands(
+    commands: Vec<Command>,
+    limits: &BuildLimits,
+) -> Vec<io::Result<BuildOutput>> {
+    let semaphore = Arc::new(Semaphore::new(limits.concurrency.max(1)));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let _cancel_on_drop = CancelOnDrop(cancelled.clone());
+    let mut builds = JoinSet::new();
+    for (index, mut command) in commands.into_iter().enumerate() {
+        let semaphore = semaphore.clone();
+        let limits = limits.clone();
+        let cancelled = cancelled.clone();
+        builds.spawn(async move {
+            // The semaphore is never closed, so acquiring a permit can't fail.
+            let _permit = semaphore.acquire_owned().await;
+            let output = tokio::task::spawn_blocking(move || {
+                run_cancellable_build_command(&mut command, &limits, &cancelled)
+            })
+            .await
+            .unwrap_or_else(|e| Err(io::Error::other(e)));
+            (index, output)
+        });
+    }
+
+    let mut outputs: Vec<io::Result<BuildOutput>> = Vec::new();
+    outputs.resize_with(builds.len(), || Err(io::Error::other("Build task panicked")));
+    while let Some(result) = builds.join_next().await {
+        if let Ok((index, output)) = result {
+            outputs[index] = output;
+        }
+    }
+    outputs
+}
+
+/// Sets the flag when dropped. Dropping the `JoinSet` aborts builds that haven't started, but
+/// builds already running on blocking threads can't be aborted, so they watch this flag instead.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Installs each of the given solc versions with svm, unless it's already installed. Compilers are
+/// installed to svm's home directory, which is where forge looks for them, so each version is only
+/// downloaded once and is reused by later verifications.
+pub async fn install_solc_versions(
+    versions: &[String],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if versions.is_empty() {
+        return Ok(())
+    }
+
+    let _guard = SOLC_INSTALL_LOCK.lock().await;
+    let installed = svm::installed_versions().unwrap_or_default();
+    for version in versions {
+        let version = Version::parse(version)
+            .map_err(|e| format!("Invalid solc version '{version}': {e}"))?;
+        if installed.contains(&version) {
+            continue
+        }
+        tracing::info!(%version, "Installing solc");
+        svm::install(&version)
+            .await
+            .map_err(|e| format!("Failed to install solc {version}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Returns true if `INSTALL_DEPENDENCIES` is set to `true` or `1`. Installing dependencies is
+/// opt-in, since it fetches code from outside the repository being verified.
+pub fn dependency_install_enabled() -> bool {
+    env::var(INSTALL_DEPENDENCIES_ENV_VAR)
+        .map_or(false, |value| matches!(value.trim(), "true" | "1"))
+}
+
+/// Runs the commands that install a project's dependencies one at a time, in order, since they
+/// write to the same project. Each command is subject to the same limits as a build command.
+/// Returns an error describing the first command that doesn't succeed.
+pub async fn install_dependencies(
+    commands: Vec<Command>,
+    limits: &BuildLimits,
+) -> Result<(), String> {
+    for command in commands {
+        let outputs = run_build_commands(vec![command], limits).await;
+        let output = outputs
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| Err(io::Error::other("Install command didn't run")))
+            .map_err(|e| format!("Failed to install dependencies: {e}"))?;
+        match output.status {
+            BuildStatus::TimedOut => {
+                let secs = limits.timeout.as_secs();
+                return Err(format!("Installing dependencies timed out after {secs}s"))
+            }
+            BuildStatus::Cancelled => return Err("Installing dependencies was cancelled".into()),
+            BuildStatus::Exited(_) if !output.success() => {
+                let diagnostics = output.diagnostics().join("\n");
+                return Err(format!("Failed to install dependencies:\n{diagnostics}"))
+            }
+            BuildStatus::Exited(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Reads up to `max_bytes` from the pipe on a background thread. Anything past that is read and
+/// discarded, so the child never blocks on a full pipe.
+fn capture_output<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    max_bytes: usize,
+) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.by_ref().take(max_bytes as u64).read_to_end(&mut output);
+            let _ = io::copy(&mut pipe, &mut io::sink());
+        }
+        output
+    })
+}
+
+/// Returns the output read by a `capture_output` thread, or nothing if the thread panicked.
+fn join_output(handle: JoinHandle<Vec<u8>>) -> Vec<u8> {
+    handle.join().unwrap_or_default()
+}
+
+/// Kills the child and every process in its process group, then reaps the child.
+fn kill_process_group(child: &mut Child) -> io::Result<()> {
+    // The child is the leader of its process group, so the group ID is its PID.
+    let killed_group = Command::new("kill")
+        .arg("-KILL")
+        .arg("--")
+        .arg(format!("-{}", child.id()))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !killed_group {
+        child.kill()?;
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frameworks::{foundry::Foundry, framework::Framework};
+    use std::fs;
+
+    #[test]
+    fn test_build_timeout() -> Result<(), Box<dyn std::error::Error>> {
+        let limits = BuildLimits { timeout: Duration::from_millis(200), ..Default::default() };
+
+        let start = Instant::now();
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("sleep 30");
+        let output = run_build_command(&mut command, &limits)?;
+        assert_eq!(output.status, BuildStatus::TimedOut);
+        assert!(!output.success());
+        assert!(start.elapsed() < Duration::from_secs(10), "build was not killed");
+
+        // Commands that finish in time report their exit status and output.
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("echo built");
+        let output = run_build_command(&mut command, &BuildLimits::default())?;
+        assert!(output.success());
+        assert_eq!(output.stdout, b"built\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_build_commands_run_concurrently() -> Result<(), Box<dyn std::error::Error>> {
+        let limits = BuildLimits { concurrency: 4, ..Default::default() };
+        let sleep = |secs: &str| {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(format!("sleep {secs}; echo {secs}"));
+            command
+        };
+
+        // Four builds take about as long as the slowest, rather than the sum of all of them.
+        let start = Instant::now();
+        let outputs =
+            run_build_commands(vec![sleep("1"), sleep("0.5"), sleep("1"), sleep("0.5")], &limits)
+                .await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(1));
+        assert!(elapsed < Duration::from_secs(2), "builds ran sequentially: {elapsed:?}");
+
+        // Outputs are returned in the order of the commands, not the order they finished in.
+        let stdout = outputs
+            .into_iter()
+            .map(|output| Ok(String::from_utf8(output?.stdout)?))
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+        assert_eq!(stdout, vec!["1\n", "0.5\n", "1\n", "0.5\n"]);
+
+        // With a concurrency of one, builds run sequentially.
+        let limits = BuildLimits { concurrency: 1, ..Default::default() };
+        let start = Instant::now();
+        run_build_commands(vec![sleep("0.5"), sleep("0.5")], &limits).await;
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_install_pinned_solc() -> Result<(), Box<dyn Error + Send + Sync>> {
+        // A project pinned to an old solc version, which the build environment doesn't ship with.
+        let project_dir = tempfile::tempdir()?;
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "[profile.default]\nsrc = 'src'\nsolc_version = '0.7.6'\noffline = true\n",
+        )?;
+        fs::create_dir(project_dir.path().join("src"))?;
+        fs::write(
+            project_dir.path().join("src").join("Counter.sol"),
+            "pragma solidity 0.7.6;\ncontract Counter { uint256 public count; }\n",
+        )?;
+
+        // Since the config sets `offline`, forge can't install the compiler itself, so the build
+        // only succeeds if it was installed beforehand.
+        let foundry = Foundry::new(project_dir.path())?;
+        let versions = foundry.pinned_solc_versions().map_err(|e| e.to_string())?;
+        assert_eq!(versions, vec!["0.7.6"]);
+        install_solc_versions(&versions).await?;
+        assert!(svm::installed_versions()?.contains(&Version::new(0, 7, 6)));
+
+        // Installing again is a no-op.
+        install_solc_versions(&versions).await?;
+
+        let (commands, _): (Vec<_>, Vec<_>) =
+            foundry.build_commands(None).map_err(|e| e.to_string())?.into_iter().unzip();
+        let outputs = run_build_commands(commands, &BuildLimits::default()).await;
+        for output in outputs {
+            let output = output?;
+            assert!(output.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_install_soldeer_dependencies() -> Result<(), Box<dyn Error + Send + Sync>> {
+        // A project that imports forge-std through Soldeer, so nothing it imports is in the
+        // repository until the dependencies are fetched.
+        let project_dir = tempfile::tempdir()?;
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "[profile.default]\nsrc = 'src'\nsolc_version = '0.8.19'\n\n[dependencies]\n\
+             forge-std = '1.9.2'\n\n[soldeer]\nremappings_generate = false\n",
+        )?;
+        fs::write(
+            project_dir.path().join("remappings.txt"),
+            "forge-std/=dependencies/forge-std-1.9.2/src/\n",
+        )?;
+        fs::create_dir(project_dir.path().join("src"))?;
+        fs::write(
+            project_dir.path().join("src").join("Token.sol"),
+            "pragma solidity 0.8.19;\nimport {IERC20} from 'forge-std/interfaces/IERC20.sol';\n\
+             abstract contract Token is IERC20 {}\n",
+        )?;
+
+        let foundry = Foundry::new(project_dir.path())?;
+        install_solc_versions(&foundry.pinned_solc_versions().map_err(|e| e.to_string())?).await?;
+        let build = |foundry: &Foundry| -> Result<_, Box<dyn Error + Send + Sync>> {
+            let (commands, _): (Vec<_>, Vec<_>) =
+                foundry.build_commands(None).map_err(|e| e.to_string())?.into_iter().unzip();
+            Ok(commands)
+        };
+
+        // Without the fetch step, the import can't be resolved.
+        let outputs = run_build_commands(build(&foundry)?, &BuildLimits::default()).await;
+        assert!(!outputs.into_iter().next().ok_or("No build")??.success());
+
+        // After installing, nothing is missing and the project builds.
+        let commands = foundry.dependency_install_commands();
+        assert_eq!(commands.len(), 1);
+        install_dependencies(commands, &BuildLimits::default()).await?;
+        assert!(foundry.dependency_install_commands().is_empty());
+        let outputs = run_build_commands(build(&foundry)?, &BuildLimits::default()).await;
+        for output in outputs {
+            let output = output?;
+            assert!(output.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_build_diagnostics() -> Result<(), Box<dyn Error + Send + Sync>> {
+        // A project with a syntax error, which forge reports along with its location.
+        let project_dir = tempfile::tempdir()?;
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "[profile.default]\nsrc = 'src'\nsolc_version = '0.7.6'\n",
+        )?;
+        fs::create_dir(project_dir.path().join("src"))?;
+        fs::write(
+            project_dir.path().join("src").join("Counter.sol"),
+            "pragma solidity 0.7.6;\ncontract Counter { uint256 public count }\n",
+        )?;
+
+        let foundry = Foundry::new(project_dir.path())?;
+        install_solc_versions(&foundry.pinned_solc_versions().map_err(|e| e.to_string())?).await?;
+        let (commands, _): (Vec<_>, Vec<_>) =
+            foundry.build_commands(None).map_err(|e| e.to_string())?.into_iter().unzip();
+        let outputs = run_build_commands(commands, &BuildLimits::default()).await;
+        let outputs = outputs.into_iter().collect::<Result<Vec<_>, _>>()?;
+        assert!(!outputs[0].success());
+
+        let msg = build_failure_message(&outputs);
+        assert!(msg.starts_with("No build of the project succeeded:\n"), "{msg}");
+        assert!(msg.contains("Expected ';' but got '}'"), "{msg}");
+        assert!(msg.contains("src/Counter.sol:2"), "{msg}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_diagnostics() -> Result<(), Box<dyn std::error::Error>> {
+        let status = Command::new("false").status()?;
+        let output = |stdout: &str, stderr: &str| BuildOutput {
+            status: BuildStatus::Exited(status),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+            duration: Duration::ZERO,
+        };
+
+        // Errors are joined with their locations, and colors and forge's summary are dropped.
+        let forge = output(
+            "Compiling 1 files with 0.8.19\n",
+            "Error: \nCompiler run failed:\n\u{1b}[31mError (2314)\u{1b}[0m: Expected ';' but \
+             got '}'\n --> src/Counter.sol:7:5:\n  |\n7 |     }\n  |     ^\n\nTypeError: \
+             Undeclared identifier.\n",
+        );
+        assert_eq!(
+            forge.diagnostics(),
+            vec![
+                "Error (2314): Expected ';' but got '}' --> src/Counter.sol:7:5",
+                "TypeError: Undeclared identifier.",
+            ]
+        );
+
+        // Without recognizable errors, the end of stderr is used.
+        let npm = output("", "npm ERR! code ENOENT\nnpm ERR! missing package.json\n");
+        assert_eq!(
+            npm.diagnostics(),
+            vec!["npm ERR! code ENOENT", "npm ERR! missing package.json"]
+        );
+
+        // Errors shared by several builds are only reported once.
+        let msg = build_failure_message([&forge, &forge]);
+        assert_eq!(msg.matches("Expected ';'").count(), 1);
+        assert_eq!(build_failure_message([]), "No build of the project succeeded");
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_output_cap() -> Result<(), Box<dyn std::error::Error>> {
+        let limits = BuildLimits { max_output_bytes: 1000, ..Default::default() };
+
+        // The command still runs to completion, even though most of its output is discarded.
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("head -c 1000000 /dev/zero; head -c 1000000 /dev/zero >&2");
+        let output = run_build_command(&mut command, &limits)?;
+        assert!(output.success());
+        assert_eq!(output.stdout.len(), 1000);
+        assert_eq!(output.stderr.len(), 1000);
+        Ok(())
+    }
+}
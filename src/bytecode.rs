@@ -1,7 +1,25 @@
-use ethers::types::Bytes;
-use ethers_solc::artifacts::Offsets;
+use crate::comparator::ComparatorChain;
+use ethers::{
+    abi::{Abi, ParamType, Token},
+    types::{Address, Bytes},
+};
+use ethers_solc::artifacts::{BytecodeObject, Offsets};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, error::Error, fmt, str::FromStr};
+
+/// Length of an address in bytes, which is also the length of each library placeholder in
+/// unlinked bytecode.
+const ADDRESS_LENGTH: usize = 20;
+
+/// The `solc` key of the CBOR metadata, encoded as a CBOR text string of length 4.
+const SOLC_METADATA_KEY: [u8; 5] = [0x64, b's', b'o', b'l', b'c'];
+
+/// The `PUSH0` opcode, introduced in the Shanghai upgrade.
+const PUSH0: u8 = 0x5f;
+
+/// The `PUSH1` and `PUSH32` opcodes, which bound the opcodes followed by immediate data.
+const PUSH1: u8 = 0x60;
+const PUSH32: u8 = 0x7f;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 
@@ -17,7 +35,7 @@ pub enum MatchType {
 }
 
 /// Contains info about the the bytecode's metadata hash.
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct MetadataInfo {
     /// The metadata hash if present.
     pub hash: Option<Bytes>,
@@ -37,6 +55,8 @@ pub struct FoundCreationBytecode {
     pub leading_code: Bytes,
     /// Information about the metadata hash.
     pub metadata: MetadataInfo,
+    /// Library references and their offsets within bytecode.
+    pub link_references: LinkReferences,
 }
 
 /// Data about expected creation bytecode, where "expected" bytecode is the bytecode that exists
@@ -58,6 +78,19 @@ pub struct ExpectedCreationBytecode {
 /// bytecode. This contains data from the `immutableReferences` field of an artifact.
 pub type ImmutableReferences = BTreeMap<String, Vec<Offsets>>;
 
+/// Type alias for a mapping between immutable reference identifiers and the values they were set
+/// to, as read from deployed bytecode.
+pub type ImmutableValues = BTreeMap<String, Bytes>;
+
+/// Type alias for a mapping from source file to library name to the offsets of that library's
+/// address placeholders within bytecode. This contains data from the `linkReferences` field of an
+/// artifact.
+pub type LinkReferences = BTreeMap<String, BTreeMap<String, Vec<Offsets>>>;
+
+/// Type alias for a mapping between fully qualified library names, i.e. `path:Name`, and the
+/// addresses they were linked against.
+pub type Libraries = BTreeMap<String, Address>;
+
 /// Data about found deployed bytecode, where "found" bytecode is bytecode from an artifact that was
 /// output when compiling the repo.
 #[derive(Debug, PartialEq, Eq)]
@@ -70,6 +103,8 @@ pub struct FoundDeployedBytecode {
     pub metadata: MetadataInfo,
     /// Immutable references and their offsets within bytecode.
     pub immutable_references: ImmutableReferences,
+    /// Library references and their offsets within bytecode.
+    pub link_references: LinkReferences,
 }
 
 /// Data about expected deployed bytecode, where "expected" bytecode is the bytecode that exists
@@ -86,91 +121,445 @@ pub struct ExpectedDeployedBytecode {
     pub immutable_references: ImmutableReferences,
 }
 
-/// Checks for equality between found and expected creation bytecode and returns the type of match.
-pub fn creation_code_equality_check(
-    found: &FoundCreationBytecode,
-    expected: &ExpectedCreationBytecode,
-) -> MatchType {
-    // If bytecode is empty, we have an interface, and we can't match with an interface.
-    if found.raw_code.is_empty() {
-        return MatchType::None
+impl FoundCreationBytecode {
+    /// Returns a copy of this bytecode with its library placeholders filled in from the expected
+    /// bytecode, along with the addresses of the linked libraries.
+    pub fn link(&self, expected: &Bytes) -> (Self, Libraries) {
+        let (raw_code, libraries) = link_libraries(&self.raw_code, &self.link_references, expected);
+        let leading_code = raw_code[..self.leading_code.len()].to_vec().into();
+        let linked = Self {
+            raw_code,
+            leading_code,
+            metadata: self.metadata.clone(),
+            link_references: self.link_references.clone(),
+        };
+        (linked, libraries)
     }
+}
 
-    // Expected code might contain appended constructor arguments, so if code matches then expected
-    // can only be equal to or longer than found code.
-    if found.raw_code.len() > expected.raw_code.len() {
-        return MatchType::None
+impl FoundDeployedBytecode {
+    /// Returns a copy of this bytecode with its library placeholders filled in from the expected
+    /// bytecode, along with the addresses of the linked libraries.
+    pub fn link(&self, expected: &Bytes) -> (Self, Libraries) {
+        let (raw_code, libraries) = link_libraries(&self.raw_code, &self.link_references, expected);
+        let leading_code = raw_code[..self.leading_code.len()].to_vec().into();
+        let linked = Self {
+            raw_code,
+            leading_code,
+            metadata: self.metadata.clone(),
+            immutable_references: self.immutable_references.clone(),
+            link_references: self.link_references.clone(),
+        };
+        (linked, libraries)
     }
-    if found.raw_code == expected.raw_code {
-        return MatchType::Full
+}
+
+/// Converts a bytecode object from an artifact into bytes. Unlinked bytecode contains library
+/// placeholders that aren't valid hex, so those are replaced with zeroes. The real addresses are
+/// filled in later by `link_libraries`, once we know the expected bytecode.
+pub fn bytecode_object_to_bytes(
+    object: &BytecodeObject,
+    link_references: &LinkReferences,
+) -> Result<Bytes, Box<dyn Error>> {
+    let unlinked = match object {
+        BytecodeObject::Bytecode(bytes) => return Ok(bytes.clone()),
+        BytecodeObject::Unlinked(unlinked) => unlinked,
+    };
+
+    let mut hex = unlinked.strip_prefix("0x").unwrap_or(unlinked).to_string();
+    for offset in link_references.values().flat_map(|libraries| libraries.values()).flatten() {
+        // Each byte is two hex characters.
+        let start = offset.start as usize * 2;
+        let end = start + ADDRESS_LENGTH * 2;
+        if end > hex.len() || !hex.is_char_boundary(start) || !hex.is_char_boundary(end) {
+            return Err("Link reference is out of bounds of the bytecode.".into())
+        }
+        hex.replace_range(start..end, &"0".repeat(ADDRESS_LENGTH * 2));
     }
-    if found.leading_code == expected.leading_code {
-        return MatchType::Partial
+    Ok(Bytes::from_str(&hex)?)
+}
+
+/// Fills in the library addresses in `code` with the bytes at the same offsets in `expected`, and
+/// returns the linked code along with the address of each library. Offsets that are out of bounds
+/// of either bytecode are skipped, in which case the bytecode simply won't match.
+pub fn link_libraries(
+    code: &Bytes,
+    link_references: &LinkReferences,
+    expected: &Bytes,
+) -> (Bytes, Libraries) {
+    let mut linked = code.to_vec();
+    let mut libraries = Libraries::new();
+    for (path, references) in link_references {
+        for (name, offsets) in references {
+            for offset in offsets {
+                let start = offset.start as usize;
+                let end = start + ADDRESS_LENGTH;
+                if end > linked.len() || end > expected.len() {
+                    continue
+                }
+                linked[start..end].copy_from_slice(&expected[start..end]);
+                libraries
+                    .insert(format!("{path}:{name}"), Address::from_slice(&expected[start..end]));
+            }
+        }
     }
+    (linked.into(), libraries)
+}
 
-    MatchType::None
+/// Whether the trailing metadata should be included when comparing bytecode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataComparison {
+    /// Compare the full bytecode, including the metadata.
+    Include,
+    /// Strip the metadata from both sides before comparing.
+    Strip,
 }
 
-/// Checks for equality between found and expected deployed bytecode and returns the type of match.
-pub fn deployed_code_equality_check(
-    found: &FoundDeployedBytecode,
-    expected: &ExpectedDeployedBytecode,
-) -> MatchType {
-    // If bytecode is empty, we have an interface, and we can't match with an interface.
-    if found.raw_code.is_empty() {
-        return MatchType::None
+/// Returns the bytecode with its trailing CBOR-encoded metadata removed. If no metadata is present,
+/// the bytecode is returned unchanged.
+pub fn strip_metadata(code: &Bytes) -> Bytes {
+    let (leading_code, metadata) = split_at_metadata_hash(code);
+    match metadata {
+        // Solidity always encodes the metadata as a CBOR map, so if the section doesn't start with
+        // a map header it wasn't actually metadata.
+        Some(metadata) if is_cbor_map_header(metadata[0]) => leading_code,
+        _ => code.clone(),
     }
+}
 
-    // Expected and found code must have the same length.
-    if found.raw_code.len() != expected.raw_code.len() {
-        return MatchType::None
+/// Returns true if the two pieces of bytecode are equal. If `comparison` is
+/// `MetadataComparison::Strip`, the trailing metadata is removed from both before comparing, which
+/// allows matching identical sources that were compiled on different machines or paths.
+pub fn bytecode_eq(a: &Bytes, b: &Bytes, comparison: MetadataComparison) -> bool {
+    match comparison {
+        MetadataComparison::Include => a == b,
+        MetadataComparison::Strip => strip_metadata(a) == strip_metadata(b),
     }
+}
 
-    // Simple check for exact match.
-    if found.raw_code == expected.raw_code {
-        return MatchType::Full
+/// Where two pieces of bytecode diverge, used to explain why an artifact doesn't match on-chain
+/// code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BytecodeDiff {
+    /// The length of the found bytecode in bytes.
+    pub found_length: usize,
+    /// The length of the expected bytecode in bytes.
+    pub expected_length: usize,
+    /// The length of the longest common prefix in bytes.
+    pub common_prefix_length: usize,
+    /// The offset of the first byte that differs, or `None` if the bytecode is identical. If one
+    /// is a prefix of the other, this is the length of the shorter one.
+    pub first_difference: Option<usize>,
+    /// Whether the bytecode is equal once the trailing metadata is stripped from both.
+    pub matches_without_metadata: bool,
+    /// Whether the first difference is within the trailing metadata of either side, meaning the
+    /// code itself matches and only the metadata differs.
+    pub difference_in_metadata: bool,
+    /// The offset that both windows start at.
+    pub window_start: usize,
+    /// The found bytecode around the first difference. Empty if the bytecode is identical.
+    pub found_window: Bytes,
+    /// The expected bytecode around the first difference. Empty if the bytecode is identical.
+    pub expected_window: Bytes,
+}
+
+/// The number of bytes shown on each side of the first difference in a `BytecodeDiff`.
+pub const DIFF_WINDOW_BYTES: usize = 16;
+
+impl BytecodeDiff {
+    /// Compares the found bytecode, such as an artifact's, against the expected bytecode, such as
+    /// the contract's on-chain code, and locates the first byte where they differ.
+    pub fn new(found: &Bytes, expected: &Bytes) -> Self {
+        let common_prefix_length = common_prefix_length(found, expected);
+        let first_difference = (found != expected).then_some(common_prefix_length);
+
+        let found_code_length = strip_metadata(found).len();
+        let expected_code_length = strip_metadata(expected).len();
+        let has_metadata = found_code_length < found.len() || expected_code_length < expected.len();
+        let difference_in_metadata = first_difference.map_or(false, |offset| {
+            has_metadata && offset >= found_code_length.min(expected_code_length)
+        });
+
+        let (window_start, found_window, expected_window) = match first_difference {
+            Some(offset) => {
+                let start = offset.saturating_sub(DIFF_WINDOW_BYTES);
+                let end = offset + DIFF_WINDOW_BYTES;
+                // The offset is at most the length of the shorter side, so `start` is in bounds.
+                let window = |code: &Bytes| Bytes::from(code[start..end.min(code.len())].to_vec());
+                (start, window(found), window(expected))
+            }
+            None => (0, Bytes::new(), Bytes::new()),
+        };
+
+        BytecodeDiff {
+            found_length: found.len(),
+            expected_length: expected.len(),
+            common_prefix_length,
+            first_difference,
+            matches_without_metadata: bytecode_eq(found, expected, MetadataComparison::Strip),
+            difference_in_metadata,
+            window_start,
+            found_window,
+            expected_window,
+        }
+    }
+}
+
+/// Returns the number of leading bytes that are equal in both pieces of bytecode.
+fn common_prefix_length(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+/// Why an artifact's creation code did or didn't match the expected creation code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchOutcome {
+    /// The code matched, either fully or partially.
+    Matched,
+    /// The expected code is too short to contain the artifact's code, so they can't match.
+    LengthMismatch {
+        /// The length of the found creation code in bytes.
+        found: usize,
+        /// The length of the expected creation code in bytes.
+        expected: usize,
+    },
+    /// The code before the metadata matches, but the metadata differs in a way none of the
+    /// comparators accept.
+    MetadataDiffers,
+    /// The code before the metadata first differs at this byte offset.
+    PrefixMismatchAt(usize),
+}
+
+impl MatchOutcome {
+    /// Classifies the result of comparing found against expected creation code, where
+    /// `match_type` is the result of the comparison.
+    pub fn classify(
+        found: &FoundCreationBytecode,
+        expected: &ExpectedCreationBytecode,
+        match_type: MatchType,
+    ) -> Self {
+        if match_type != MatchType::None {
+            return MatchOutcome::Matched
+        }
+        let offset = common_prefix_length(&found.leading_code, &expected.leading_code);
+        if offset == found.leading_code.len() && offset == expected.leading_code.len() {
+            MatchOutcome::MetadataDiffers
+        } else {
+            MatchOutcome::PrefixMismatchAt(offset)
+        }
     }
 
-    // Compare the leading code, but skip all chunks that contain immutables.
-    if found.immutable_references == expected.immutable_references {
-        // Flatten the map to just a vec of the references. Since we know found and expected have
-        // equal immutable references due to how the structs were constructed, we can just use the
-        // found ones.
-        let mut offsets: Vec<Offsets> = Vec::new();
-        for new_offsets in found.immutable_references.values() {
-            offsets.extend(new_offsets.iter().cloned());
+    /// Returns how close the outcome is to a match, where greater is closer. Differing metadata is
+    /// closer than differing code, and code that differs later is closer than code that differs
+    /// earlier.
+    pub fn closeness(&self) -> (u8, usize) {
+        match self {
+            MatchOutcome::Matched => (3, 0),
+            MatchOutcome::MetadataDiffers => (2, 0),
+            MatchOutcome::PrefixMismatchAt(offset) => (1, *offset),
+            MatchOutcome::LengthMismatch { .. } => (0, 0),
         }
+    }
+}
 
-        // The expected bytecode is deployed and therefore has real values for the immutables. The
-        // found bytecode uses zeroes as placeholders for the immutables (this is how solc works).
-        // Therefore for each immutable reference in the expected bytecode, we can replace the
-        // bytecode with zeroes, then compare the found bytecode with the expected bytecode.
-        // It's likely the metadata hashes won't match, so we adjust both the raw and leading code
-        // so we only have to loop through the offsets once.
-        let mut adjusted_expected_raw_code = expected.raw_code.to_vec();
-        let mut adjusted_expected_leading_code = expected.leading_code.to_vec();
-        for offset in offsets {
-            let immutable_start: usize = offset.start.try_into().unwrap();
-            let immutable_length: usize = offset.length.try_into().unwrap();
-            let immutable_end = immutable_start + immutable_length;
-            for i in immutable_start..immutable_end {
-                adjusted_expected_raw_code[i] = 0;
-                adjusted_expected_leading_code[i] = 0;
+impl fmt::Display for MatchOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchOutcome::Matched => write!(f, "the code matches"),
+            MatchOutcome::LengthMismatch { found, expected } => write!(
+                f,
+                "the expected code is {expected} bytes, too short for the artifact's {found} bytes"
+            ),
+            MatchOutcome::MetadataDiffers => write!(f, "the code matches but the metadata differs"),
+            MatchOutcome::PrefixMismatchAt(offset) => {
+                write!(f, "the code differs at byte {offset}")
             }
         }
+    }
+}
 
-        // This matched with the metadata hash, so it's a full match.
-        if adjusted_expected_raw_code == found.raw_code {
-            return MatchType::Full
+/// Returns true if the code uses the `PUSH0` opcode, which solc only emits when compiling for the
+/// Shanghai EVM version or later. The trailing metadata and the immediate data of `PUSH` opcodes
+/// aren't opcodes, so they're skipped.
+pub fn uses_push0(code: &Bytes) -> bool {
+    let code = strip_metadata(code);
+    let mut index = 0;
+    while let Some(&opcode) = code.get(index) {
+        if opcode == PUSH0 {
+            return true
+        }
+        index += 1;
+        if (PUSH1..=PUSH32).contains(&opcode) {
+            index += usize::from(opcode - PUSH1) + 1;
         }
+    }
+    false
+}
 
-        // Had to remove the metadata hash, so it's a partial match.
-        if adjusted_expected_leading_code == found.leading_code {
-            return MatchType::Partial
+/// Returns the version of solc that compiled the code, as encoded in its trailing CBOR metadata.
+/// Release builds encode the version as three bytes, which are returned as `major.minor.patch`,
+/// and prerelease builds encode the full version string. Returns `None` if the code has no
+/// metadata or the metadata doesn't include a solc version, e.g. because it was compiled with
+/// Vyper.
+pub fn metadata_solc_version(code: &Bytes) -> Option<String> {
+    let (_, metadata) = split_at_metadata_hash(code);
+    let metadata = metadata.filter(|metadata| is_cbor_map_header(metadata[0]))?;
+    let key_index =
+        metadata.windows(SOLC_METADATA_KEY.len()).position(|window| window == SOLC_METADATA_KEY)?;
+    let value = &metadata[key_index + SOLC_METADATA_KEY.len()..];
+    match *value.first()? {
+        // A byte string of length 3.
+        0x43 => value.get(1..4).map(|v| format!("{}.{}.{}", v[0], v[1], v[2])),
+        // A text string whose length is encoded in the header.
+        header @ 0x60..=0x77 => {
+            let len = (header - 0x60) as usize;
+            String::from_utf8(value.get(1..1 + len)?.to_vec()).ok()
+        }
+        // A text string whose length is encoded in the following byte.
+        0x78 => {
+            let len = *value.get(1)? as usize;
+            String::from_utf8(value.get(2..2 + len)?.to_vec()).ok()
         }
+        _ => None,
+    }
+}
+
+/// Returns true if the byte is the header of a CBOR map, i.e. CBOR major type 5.
+fn is_cbor_map_header(byte: u8) -> bool {
+    byte >> 5 == 5
+}
+
+/// Maximum nesting of CBOR arrays and maps parsed by `cbor_item_length`. Metadata is a map of
+/// scalars, or of short arrays for Vyper, so this only guards against recursing on garbage.
+const MAX_CBOR_DEPTH: usize = 4;
+
+/// Returns the total length of the metadata at the start of `code`, i.e. the CBOR map plus the two
+/// bytes after it that encode its length, or `None` if `code` doesn't start with a CBOR map whose
+/// length matches those two bytes. Unlike `get_metadata_hash_length`, which trusts the last two
+/// bytes of the code, this parses the CBOR itself, so it finds the exact end of the metadata even
+/// when constructor arguments follow it or its length differs from another build's metadata.
+pub fn metadata_length_at(code: &[u8]) -> Option<usize> {
+    if !is_cbor_map_header(*code.first()?) {
+        return None
+    }
+    let cbor_length = cbor_item_length(code, 0)?;
+    let encoded_length = code.get(cbor_length..cbor_length + 2)?;
+    let encoded_length = usize::from(u16::from_be_bytes([encoded_length[0], encoded_length[1]]));
+    (encoded_length == cbor_length).then_some(cbor_length + 2)
+}
+
+/// Returns the length of the CBOR data item at the start of `data`, or `None` if it's truncated,
+/// nested deeper than `MAX_CBOR_DEPTH`, or of a type compilers don't use in metadata. Supported
+/// types are unsigned integers, byte and text strings, arrays, maps, and simple values.
+fn cbor_item_length(data: &[u8], depth: usize) -> Option<usize> {
+    let header = *data.first()?;
+    let (major_type, info) = (header >> 5, header & 0x1f);
+    // The low five bits hold the argument, i.e. the value, length, or number of items, directly
+    // if it's below 24, and otherwise say how many of the following bytes hold it.
+    let (argument, header_length) = match info {
+        0..=23 => (usize::from(info), 1),
+        24 => (usize::from(*data.get(1)?), 2),
+        25 => (usize::from(u16::from_be_bytes(data.get(1..3)?.try_into().ok()?)), 3),
+        26 => (usize::try_from(u32::from_be_bytes(data.get(1..5)?.try_into().ok()?)).ok()?, 5),
+        _ => return None,
+    };
+    match major_type {
+        // Unsigned integers and simple values are just the header and argument.
+        0 | 7 => (header_length <= data.len()).then_some(header_length),
+        // Byte and text strings are followed by `argument` bytes.
+        2 | 3 => header_length.checked_add(argument).filter(|length| *length <= data.len()),
+        // Arrays are followed by `argument` items, and maps by `argument` key-value pairs.
+        4 | 5 if depth < MAX_CBOR_DEPTH => {
+            let items = if major_type == 5 { argument.checked_mul(2)? } else { argument };
+            let mut length = header_length;
+            for _ in 0..items {
+                length += cbor_item_length(data.get(length..)?, depth + 1)?;
+            }
+            Some(length)
+        }
+        _ => None,
+    }
+}
+
+/// Checks for equality between found and expected creation bytecode and returns the type of match,
+/// using the comparators for EVM bytecode.
+pub fn creation_code_equality_check(
+    found: &FoundCreationBytecode,
+    expected: &ExpectedCreationBytecode,
+) -> MatchType {
+    ComparatorChain::evm().compare_creation(found, expected).match_type
+}
+
+/// A constructor argument decoded from the ABI-encoded arguments appended to the creation code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstructorArg {
+    /// The name of the parameter in the constructor signature.
+    pub name: String,
+    /// The Solidity type of the parameter.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The decoded value of the argument.
+    pub value: String,
+}
+
+/// Decodes the ABI-encoded constructor arguments against the constructor in the given ABI. Returns
+/// `None` if the ABI has no constructor or the arguments don't decode to exactly its parameters.
+pub fn decode_constructor_args(abi: &Abi, encoded: &Bytes) -> Option<Vec<ConstructorArg>> {
+    let constructor = abi.constructor()?;
+    let types: Vec<ParamType> = constructor.inputs.iter().map(|param| param.kind.clone()).collect();
+    let tokens = ethers::abi::decode_whole(&types, encoded).ok()?;
+
+    let args = constructor
+        .inputs
+        .iter()
+        .zip(tokens)
+        .map(|(param, token)| ConstructorArg {
+            name: param.name.clone(),
+            kind: param.kind.to_string(),
+            value: format_token(&token),
+        })
+        .collect();
+    Some(args)
+}
+
+/// Formats a decoded token the way it would be written in Solidity, e.g. numbers in decimal and
+/// addresses and bytes as `0x`-prefixed hex.
+fn format_token(token: &Token) -> String {
+    let join = |tokens: &[Token]| tokens.iter().map(format_token).collect::<Vec<_>>().join(", ");
+    match token {
+        Token::Address(address) => format!("{address:?}"),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => Bytes::from(bytes.clone()).to_string(),
+        Token::Int(value) => ethers::types::I256::from_raw(*value).to_string(),
+        Token::Uint(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => value.clone(),
+        Token::FixedArray(tokens) | Token::Array(tokens) => format!("[{}]", join(tokens)),
+        Token::Tuple(tokens) => format!("({})", join(tokens)),
     }
+}
 
-    MatchType::None
+/// Checks for equality between found and expected deployed bytecode and returns the type of match,
+/// using the comparators for EVM bytecode.
+pub fn deployed_code_equality_check(
+    found: &FoundDeployedBytecode,
+    expected: &ExpectedDeployedBytecode,
+) -> MatchType {
+    ComparatorChain::evm().compare_deployed(found, expected).match_type
+}
+
+/// Reads the value of each immutable from deployed code at the offsets solc reserved for it. Every
+/// offset of an immutable holds the same value, so the first is used. Immutables that are never
+/// read have no offsets and are omitted, as are any whose offsets lie outside the code.
+pub fn immutable_values(references: &ImmutableReferences, code: &Bytes) -> ImmutableValues {
+    references
+        .iter()
+        .filter_map(|(id, offsets)| {
+            let offset = offsets.first()?;
+            let start = offset.start as usize;
+            let value = code.get(start..start + offset.length as usize)?;
+            Some((id.clone(), value.to_vec().into()))
+        })
+        .collect()
 }
 
 /// Given code, infers and returns the metadata details.
@@ -246,6 +635,7 @@ mod tests {
             raw_code: found_code.clone(),
             leading_code: found_code.clone(),
             metadata: MetadataInfo::default(),
+            link_references: LinkReferences::new(),
         };
 
         let expected_full = ExpectedCreationBytecode {
@@ -277,8 +667,345 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "TODO"]
+    fn test_creation_code_with_constructor_args() -> Result<(), Box<dyn std::error::Error>> {
+        let found_code = Bytes::from_str("60606040525b6102c05b60")?;
+        let constructor_args = ethers::abi::encode(&[Token::Uint(42.into())]);
+        let expected_code = Bytes::from([found_code.to_vec(), constructor_args.clone()].concat());
+
+        let found = FoundCreationBytecode {
+            raw_code: found_code.clone(),
+            leading_code: found_code.clone(),
+            metadata: MetadataInfo::default(),
+            link_references: LinkReferences::new(),
+        };
+        let expected = ExpectedCreationBytecode {
+            raw_code: expected_code,
+            leading_code: found_code,
+            metadata: MetadataInfo::default(),
+            constructor_args: Some(constructor_args.into()),
+        };
+
+        assert_eq!(creation_code_equality_check(&found, &expected), MatchType::Full);
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_libraries() -> Result<(), Box<dyn std::error::Error>> {
+        // A contract that calls `MathLib.add`, with the library address placeholder at offset 2.
+        let placeholder = "__$1d0a7da4a6f5e8a3b9eb3e3b7ef9e18bf1$__";
+        let object = BytecodeObject::Unlinked(format!("0x6073{placeholder}3014"));
+        let offsets = vec![Offsets { start: 2, length: 20 }];
+        let link_references: LinkReferences = BTreeMap::from([(
+            "src/MathLib.sol".to_string(),
+            BTreeMap::from([("MathLib".to_string(), offsets)]),
+        )]);
+
+        // Placeholders are zeroed out so the bytecode can be parsed.
+        let unlinked = bytecode_object_to_bytes(&object, &link_references)?;
+        let zero_address = "0".repeat(40);
+        assert_eq!(unlinked, Bytes::from_str(&format!("0x6073{zero_address}3014"))?);
+
+        // The on-chain code has the deployed library's address at the placeholder's offset.
+        let library = Address::repeat_byte(0xab);
+        let expected = Bytes::from_str(&format!("0x6073{library:x}3014"))?;
+        let found = FoundCreationBytecode {
+            raw_code: unlinked.clone(),
+            leading_code: unlinked,
+            metadata: MetadataInfo::default(),
+            link_references,
+        };
+        let (linked, libraries) = found.link(&expected);
+        assert_eq!(linked.raw_code, expected);
+        assert_eq!(linked.leading_code, expected);
+        assert_eq!(libraries, Libraries::from([("src/MathLib.sol:MathLib".to_string(), library)]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_constructor_args() -> Result<(), Box<dyn std::error::Error>> {
+        let abi: Abi = serde_json::from_str(
+            r#"[{
+                "type": "constructor",
+                "stateMutability": "nonpayable",
+                "inputs": [
+                    { "name": "owner", "type": "address", "internalType": "address" },
+                    { "name": "supply", "type": "uint256", "internalType": "uint256" },
+                    { "name": "name", "type": "string", "internalType": "string" }
+                ]
+            }]"#,
+        )?;
+        let owner = ethers::types::Address::repeat_byte(0x11);
+        let encoded: Bytes = ethers::abi::encode(&[
+            Token::Address(owner),
+            Token::Uint(1_000_000.into()),
+            Token::String("Cove".to_string()),
+        ])
+        .into();
+
+        let args = decode_constructor_args(&abi, &encoded).ok_or("failed to decode")?;
+        let values: Vec<(&str, &str, &str)> =
+            args.iter().map(|a| (a.name.as_str(), a.kind.as_str(), a.value.as_str())).collect();
+        assert_eq!(
+            values,
+            vec![
+                ("owner", "address", "0x1111111111111111111111111111111111111111"),
+                ("supply", "uint256", "1000000"),
+                ("name", "string", "Cove"),
+            ]
+        );
+
+        // Trailing bytes mean the suffix wasn't just the constructor arguments.
+        let mut too_long = encoded.to_vec();
+        too_long.extend_from_slice(&[0; 32]);
+        assert_eq!(decode_constructor_args(&abi, &too_long.into()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_uses_push0() -> Result<(), Box<dyn std::error::Error>> {
+        // `PUSH0 PUSH0 REVERT`, as compiled for Shanghai.
+        assert!(uses_push0(&Bytes::from_str("5f5ffd")?));
+        // `PUSH1 0x5f PUSH1 0x00 REVERT`, where 0x5f is data rather than an opcode.
+        assert!(!uses_push0(&Bytes::from_str("605f6000fd")?));
+        // A `PUSH32` whose data is all 0x5f.
+        assert!(!uses_push0(&Bytes::from_str(&format!("7f{}00", "5f".repeat(32)))?));
+        // 0x5f in the metadata isn't an opcode.
+        let metadata = format!("a2646970667358221220{}64736f6c63430008130033", "5f".repeat(32));
+        let code = Bytes::from_str(&format!("6080604052600080fdfe{metadata}"))?;
+        assert!(!uses_push0(&code));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytecode_diff() -> Result<(), Box<dyn std::error::Error>> {
+        let metadata = "a2646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b6164736f6c634300060b0033";
+        let code = "6080604052348015600f57600080fd5b50603f80601d6000396000f3fe6080604052600080fdfe";
+        let found = Bytes::from_str(&format!("{code}{metadata}"))?;
+
+        // A one-byte difference in the code is reported at its offset.
+        let mut changed = found.to_vec();
+        changed[20] ^= 0xff;
+        let diff = BytecodeDiff::new(&found, &changed.into());
+        assert_eq!(diff.common_prefix_length, 20);
+        assert_eq!(diff.first_difference, Some(20));
+        assert!(!diff.matches_without_metadata);
+        assert!(!diff.difference_in_metadata);
+        assert_eq!(diff.window_start, 4);
+        assert_eq!(diff.found_window[..], found[4..36]);
+        assert_eq!(diff.expected_window[16], found[20] ^ 0xff);
+
+        // A difference in the metadata hash is flagged as such.
+        let mut changed = found.to_vec();
+        let offset = code.len() / 2 + 20;
+        changed[offset] ^= 0xff;
+        let diff = BytecodeDiff::new(&found, &changed.into());
+        assert_eq!(diff.first_difference, Some(offset));
+        assert!(diff.matches_without_metadata);
+        assert!(diff.difference_in_metadata);
+
+        // Identical bytecode has no difference.
+        let diff = BytecodeDiff::new(&found, &found);
+        assert_eq!(diff.first_difference, None);
+        assert_eq!(diff.common_prefix_length, found.len());
+        assert!(diff.found_window.is_empty());
+
+        // When one side is a prefix of the other, they differ where the shorter one ends.
+        let diff = BytecodeDiff::new(&found, &Bytes::from(found[..10].to_vec()));
+        assert_eq!(diff.first_difference, Some(10));
+        assert_eq!(diff.expected_window.len(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_outcome() -> Result<(), Box<dyn std::error::Error>> {
+        let metadata = format!("a2646970667358221220{}64736f6c63430008130033", "00".repeat(32));
+        let found_code = Bytes::from_str(&format!("6080604052348015600f57600080fdfe{metadata}"))?;
+        let found = FoundCreationBytecode {
+            leading_code: strip_metadata(&found_code),
+            metadata: parse_metadata(&found_code),
+            raw_code: found_code,
+            link_references: LinkReferences::new(),
+        };
+        let expected = |leading_code: &str| -> Result<ExpectedCreationBytecode, Box<dyn Error>> {
+            let raw_code = Bytes::from_str(&format!("{leading_code}{metadata}"))?;
+            Ok(ExpectedCreationBytecode {
+                leading_code: Bytes::from_str(leading_code)?,
+                metadata: parse_metadata(&raw_code),
+                raw_code,
+                constructor_args: None,
+            })
+        };
+
+        let same = expected("6080604052348015600f57600080fdfe")?;
+        let outcome = MatchOutcome::classify(&found, &same, MatchType::Full);
+        assert_eq!(outcome, MatchOutcome::Matched);
+        let outcome = MatchOutcome::classify(&found, &same, MatchType::Partial);
+        assert_eq!(outcome, MatchOutcome::Matched);
+
+        // Equal code that didn't match can only differ in its metadata.
+        let outcome = MatchOutcome::classify(&found, &same, MatchType::None);
+        assert_eq!(outcome, MatchOutcome::MetadataDiffers);
+
+        let different = expected("6080604052348015601057600080fdfe")?;
+        let outcome = MatchOutcome::classify(&found, &different, MatchType::None);
+        assert_eq!(outcome, MatchOutcome::PrefixMismatchAt(9));
+
+        let shorter = expected("6080604052")?;
+        let outcome = MatchOutcome::classify(&found, &shorter, MatchType::None);
+        assert_eq!(outcome, MatchOutcome::PrefixMismatchAt(5));
+
+        // Closer outcomes rank higher, so the best near-miss can be reported.
+        let length_mismatch = MatchOutcome::LengthMismatch { found: 16, expected: 4 };
+        assert!(length_mismatch.closeness() < MatchOutcome::PrefixMismatchAt(0).closeness());
+        assert!(
+            MatchOutcome::PrefixMismatchAt(4).closeness()
+                < MatchOutcome::PrefixMismatchAt(8).closeness()
+        );
+        assert!(
+            MatchOutcome::PrefixMismatchAt(8).closeness()
+                < MatchOutcome::MetadataDiffers.closeness()
+        );
+        assert!(MatchOutcome::MetadataDiffers.closeness() < MatchOutcome::Matched.closeness());
+        assert_eq!(
+            length_mismatch.to_string(),
+            "the expected code is 4 bytes, too short for the artifact's 16 bytes"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_metadata() -> Result<(), Box<dyn std::error::Error>> {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // IPFS metadata.
+            ("6080604052600080fdfea2646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b6164736f6c634300060b0033", "6080604052600080fdfe"),
+            // CBOR with only the solc version.
+            ("57600080fd5b5056fea164736f6c6343000706000a", "57600080fd5b5056fe"),
+            // Trailing bytes that look like a length but aren't preceded by a CBOR map are kept.
+            ("ffffffffffffffffffffff000a", "ffffffffffffffffffffff000a"),
+            // No metadata.
+            ("6080", "6080"),
+        ];
+
+        for (code, expected) in test_cases {
+            assert_eq!(strip_metadata(&Bytes::from_str(code)?), Bytes::from_str(expected)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_solc_version() -> Result<(), Box<dyn std::error::Error>> {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // IPFS metadata from solc 0.6.11.
+            ("6080604052600080fdfea2646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b6164736f6c634300060b0033", Some("0.6.11")),
+            // CBOR with only the solc version, from solc 0.7.6.
+            ("57600080fd5b5056fea164736f6c6343000706000a", Some("0.7.6")),
+            // A prerelease version is encoded as a string.
+            ("57600080fd5b5056fea164736f6c6366302e382e3230000d", Some("0.8.20")),
+            // Metadata without a solc version.
+            ("6080fea1646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b61002a", None),
+            // No metadata.
+            ("6080", None),
+        ];
+
+        for (code, expected) in test_cases {
+            let version = metadata_solc_version(&Bytes::from_str(code)?);
+            assert_eq!(version.as_deref(), expected, "code: {code}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_length_at() -> Result<(), Box<dyn std::error::Error>> {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // IPFS metadata from solc 0.6.11.
+            ("a2646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b6164736f6c634300060b0033", Some(53)),
+            // The same metadata followed by a constructor argument.
+            ("a2646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b6164736f6c634300060b00330000000000000000000000000000000000000000000000000000000000000001", Some(53)),
+            // CBOR with only the solc version, from solc 0.7.6.
+            ("a164736f6c6343000706000a", Some(12)),
+            // A prerelease version is encoded as a string.
+            ("a164736f6c6366302e382e3230000d", Some(15)),
+            // Vyper encodes its version as an array.
+            ("a165767970657283000307000b", Some(13)),
+            // The length suffix doesn't match the CBOR length.
+            ("a164736f6c6343000706000b", None),
+            // The CBOR is truncated.
+            ("a164736f6c63430007", None),
+            // Not CBOR metadata.
+            ("6080604052600080fd000a", None),
+        ];
+
+        for (code, expected) in test_cases {
+            assert_eq!(metadata_length_at(&Bytes::from_str(code)?), expected, "code: {code}");
+        }
+
+        // Deeply nested arrays are rejected rather than recursed into.
+        let nested = [vec![0xa1, 0x01], vec![0x81; 10_000], vec![0x00]].concat();
+        assert_eq!(metadata_length_at(&nested), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytecode_eq() -> Result<(), Box<dyn std::error::Error>> {
+        // The same source compiled on two different setups, which results in different IPFS
+        // hashes in the metadata but otherwise identical bytecode.
+        #[rustfmt::skip]
+        let build_a = Bytes::from_str("6080604052348015600f57600080fd5b50603f80601d6000396000f3fe6080604052600080fdfea2646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b6164736f6c63430008130033")?;
+        #[rustfmt::skip]
+        let build_b = Bytes::from_str("6080604052348015600f57600080fd5b50603f80601d6000396000f3fe6080604052600080fdfea2646970667358221220111111111111111111111111111111111111111111111111111111111111111164736f6c63430008130033")?;
+        #[rustfmt::skip]
+        let different_code = Bytes::from_str("6080604052348015600f57600080fd5b50603f80601d6000396000f3fe6080604052600180fdfea2646970667358221220111111111111111111111111111111111111111111111111111111111111111164736f6c63430008130033")?;
+
+        assert!(bytecode_eq(&build_a, &build_a, MetadataComparison::Include));
+        assert!(!bytecode_eq(&build_a, &build_b, MetadataComparison::Include));
+        assert!(bytecode_eq(&build_a, &build_b, MetadataComparison::Strip));
+        assert!(!bytecode_eq(&build_a, &different_code, MetadataComparison::Strip));
+        Ok(())
+    }
+
+    #[test]
     fn test_deployed_code_equality_check() -> Result<(), Box<dyn std::error::Error>> {
+        // A contract whose constructor sets an immutable, read with a `PUSH32` at offset 1. solc
+        // leaves zeroes where the value goes, and the deployed code contains the real value.
+        let references =
+            ImmutableReferences::from([("5".to_string(), vec![Offsets { start: 1, length: 32 }])]);
+        let found_code = Bytes::from_str(&format!("7f{}50a1ffee0003", "00".repeat(32)))?;
+        let value = format!("{:064x}", 42);
+        let deployed_code = Bytes::from_str(&format!("7f{value}50a1ffee0003"))?;
+
+        let (leading_code, _) = split_at_metadata_hash(&found_code);
+        let found = FoundDeployedBytecode {
+            raw_code: found_code.clone(),
+            leading_code,
+            metadata: parse_metadata(&found_code),
+            immutable_references: references.clone(),
+            link_references: LinkReferences::new(),
+        };
+        let expected = |code: &str| -> Result<ExpectedDeployedBytecode, Box<dyn Error>> {
+            let raw_code = Bytes::from_str(code)?;
+            let (leading_code, _) = split_at_metadata_hash(&raw_code);
+            Ok(ExpectedDeployedBytecode {
+                metadata: parse_metadata(&raw_code),
+                raw_code,
+                leading_code,
+                immutable_references: references.clone(),
+            })
+        };
+
+        // The immutable's value is ignored when comparing.
+        let full_match = expected(&deployed_code.to_string())?;
+        assert_eq!(deployed_code_equality_check(&found, &full_match), MatchType::Full);
+        let partial_match = expected(&format!("7f{value}50a1eeee0003"))?;
+        assert_eq!(deployed_code_equality_check(&found, &partial_match), MatchType::Partial);
+        let no_match = expected(&format!("7f{value}51a1ffee0003"))?;
+        assert_eq!(deployed_code_equality_check(&found, &no_match), MatchType::None);
+
+        // But it can be read back out of the deployed code.
+        let values = immutable_values(&references, &deployed_code);
+        assert_eq!(values, ImmutableValues::from([("5".to_string(), Bytes::from_str(&value)?)]));
         Ok(())
     }
 
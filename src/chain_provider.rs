@@ -0,0 +1,285 @@
+use ethers::{
+    providers::{Http, Middleware, Provider, ProviderError},
+    types::{
+        Address, Block, BlockId, Bytes, Trace, Transaction, TransactionReceipt, TxHash, H256, U256,
+        U64,
+    },
+};
+use futures::future::BoxFuture;
+#[cfg(test)]
+use std::collections::HashMap;
+
+/// The RPC calls made to a single chain. `MultiChainProvider` holds one per chain and builds its
+/// queries, such as finding a contract's creation code, on top of these calls, so chains can be
+/// served by backends other than an ethers HTTP provider, and tests can drive those queries without
+/// a network. Calls aren't retried here, since `MultiChainProvider` retries transient errors.
+pub trait ChainProvider: Send + Sync {
+    /// Returns the code at the address, as of `block` if given or the latest block otherwise.
+    /// Addresses without code return empty bytes.
+    fn get_code(
+        &self,
+        address: Address,
+        block: Option<BlockId>,
+    ) -> BoxFuture<'_, Result<Bytes, ProviderError>>;
+
+    /// Returns the runtime code currently deployed at the address.
+    fn get_runtime_code(&self, address: Address) -> BoxFuture<'_, Result<Bytes, ProviderError>> {
+        self.get_code(address, None)
+    }
+
+    /// Returns the block, without its transactions, or `None` if it doesn't exist.
+    fn get_block(
+        &self,
+        block: BlockId,
+    ) -> BoxFuture<'_, Result<Option<Block<TxHash>>, ProviderError>>;
+
+    /// Returns the block with its transactions, or `None` if it doesn't exist.
+    fn get_block_with_txs(
+        &self,
+        block: BlockId,
+    ) -> BoxFuture<'_, Result<Option<Block<Transaction>>, ProviderError>>;
+
+    /// Returns the number of the latest block.
+    fn get_block_number(&self) -> BoxFuture<'_, Result<U64, ProviderError>>;
+
+    /// Returns the value in the storage slot of the address, as of the latest block.
+    fn get_storage_at(
+        &self,
+        address: Address,
+        slot: H256,
+    ) -> BoxFuture<'_, Result<H256, ProviderError>>;
+
+    /// Returns the transaction, or `None` if it doesn't exist.
+    fn get_transaction(
+        &self,
+        tx_hash: TxHash,
+    ) -> BoxFuture<'_, Result<Option<Transaction>, ProviderError>>;
+
+    /// Returns the receipt of the transaction, or `None` if it doesn't exist or is pending.
+    fn get_transaction_receipt(
+        &self,
+        tx_hash: TxHash,
+    ) -> BoxFuture<'_, Result<Option<TransactionReceipt>, ProviderError>>;
+
+    /// Returns the number of transactions the account has sent, as of `block` if given or the
+    /// latest block otherwise.
+    fn get_transaction_count(
+        &self,
+        address: Address,
+        block: Option<BlockId>,
+    ) -> BoxFuture<'_, Result<U256, ProviderError>>;
+
+    /// Returns the traces of the calls the transaction made. Not all RPC providers support this.
+    fn trace_transaction(
+        &self,
+        tx_hash: TxHash,
+    ) -> BoxFuture<'_, Result<Vec<Trace>, ProviderError>>;
+}
+
+impl ChainProvider for Provider<Http> {
+    fn get_code(
+        &self,
+        address: Address,
+        block: Option<BlockId>,
+    ) -> BoxFuture<'_, Result<Bytes, ProviderError>> {
+        Box::pin(Middleware::get_code(self, address, block))
+    }
+
+    fn get_block(
+        &self,
+        block: BlockId,
+    ) -> BoxFuture<'_, Result<Option<Block<TxHash>>, ProviderError>> {
+        Box::pin(Middleware::get_block(self, block))
+    }
+
+    fn get_block_with_txs(
+        &self,
+        block: BlockId,
+    ) -> BoxFuture<'_, Result<Option<Block<Transaction>>, ProviderError>> {
+        Box::pin(Middleware::get_block_with_txs(self, block))
+    }
+
+    fn get_block_number(&self) -> BoxFuture<'_, Result<U64, ProviderError>> {
+        Box::pin(Middleware::get_block_number(self))
+    }
+
+    fn get_storage_at(
+        &self,
+        address: Address,
+        slot: H256,
+    ) -> BoxFuture<'_, Result<H256, ProviderError>> {
+        Box::pin(Middleware::get_storage_at(self, address, slot, None))
+    }
+
+    fn get_transaction(
+        &self,
+        tx_hash: TxHash,
+    ) -> BoxFuture<'_, Result<Option<Transaction>, ProviderError>> {
+        Box::pin(Middleware::get_transaction(self, tx_hash))
+    }
+
+    fn get_transaction_receipt(
+        &self,
+        tx_hash: TxHash,
+    ) -> BoxFuture<'_, Result<Option<TransactionReceipt>, ProviderError>> {
+        Box::pin(Middleware::get_transaction_receipt(self, tx_hash))
+    }
+
+    fn get_transaction_count(
+        &self,
+        address: Address,
+        block: Option<BlockId>,
+    ) -> BoxFuture<'_, Result<U256, ProviderError>> {
+        Box::pin(Middleware::get_transaction_count(self, address, block))
+    }
+
+    fn trace_transaction(
+        &self,
+        tx_hash: TxHash,
+    ) -> BoxFuture<'_, Result<Vec<Trace>, ProviderError>> {
+        Box::pin(Middleware::trace_transaction(self, tx_hash))
+    }
+}
+
+/// An in-memory chain for tests, which answers each call from its fields rather than an RPC
+/// provider. Blocks that aren't given are empty, and `block_number` is the latest block.
+#[cfg(test)]
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MockChainProvider {
+    /// The number of the latest block.
+    pub block_number: u64,
+    /// The code deployed at each address, as of every block.
+    pub code: HashMap<Address, Bytes>,
+    /// The value of each storage slot, keyed by address and slot.
+    pub storage: HashMap<(Address, H256), H256>,
+    /// The transactions in each block, keyed by block number.
+    pub blocks: HashMap<u64, Vec<Transaction>>,
+    /// The receipt of each transaction, keyed by transaction hash.
+    pub receipts: HashMap<TxHash, TransactionReceipt>,
+    /// The traces of each transaction, keyed by transaction hash.
+    pub traces: HashMap<TxHash, Vec<Trace>>,
+    /// If set, every call fails with this error.
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+impl MockChainProvider {
+    /// Returns `value`, or the mock's error if it has one.
+    fn respond<T: Send + 'static>(&self, value: T) -> BoxFuture<'_, Result<T, ProviderError>> {
+        let result = match &self.error {
+            Some(error) => Err(ProviderError::CustomError(error.clone())),
+            None => Ok(value),
+        };
+        Box::pin(futures::future::ready(result))
+    }
+
+    /// Returns the number of the block `block` refers to, treating tags as the latest block.
+    fn block_number(&self, block: Option<BlockId>) -> Option<u64> {
+        use ethers::types::BlockNumber;
+        match block {
+            Some(BlockId::Number(BlockNumber::Number(number))) => Some(number.as_u64()),
+            Some(BlockId::Hash(hash)) => {
+                self.blocks.keys().copied().find(|n| block_hash(*n) == hash)
+            }
+            _ => Some(self.block_number),
+        }
+    }
+
+    /// Returns the block with its transactions, if it's no later than the latest block.
+    fn block(&self, block: BlockId) -> Option<Block<Transaction>> {
+        let number = self.block_number(Some(block)).filter(|n| *n <= self.block_number)?;
+        Some(Block {
+            hash: Some(block_hash(number)),
+            number: Some(number.into()),
+            transactions: self.blocks.get(&number).cloned().unwrap_or_default(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Returns the hash of the mock block with the given number.
+#[cfg(test)]
+fn block_hash(number: u64) -> H256 {
+    H256::from_low_u64_be(number + 1)
+}
+
+#[cfg(test)]
+impl ChainProvider for MockChainProvider {
+    fn get_code(
+        &self,
+        address: Address,
+        _block: Option<BlockId>,
+    ) -> BoxFuture<'_, Result<Bytes, ProviderError>> {
+        self.respond(self.code.get(&address).cloned().unwrap_or_default())
+    }
+
+    fn get_block(
+        &self,
+        block: BlockId,
+    ) -> BoxFuture<'_, Result<Option<Block<TxHash>>, ProviderError>> {
+        let block = self.block(block).map(|block| Block {
+            hash: block.hash,
+            number: block.number,
+            transactions: block.transactions.iter().map(|tx| tx.hash).collect(),
+            ..Default::default()
+        });
+        self.respond(block)
+    }
+
+    fn get_block_with_txs(
+        &self,
+        block: BlockId,
+    ) -> BoxFuture<'_, Result<Option<Block<Transaction>>, ProviderError>> {
+        self.respond(self.block(block))
+    }
+
+    fn get_block_number(&self) -> BoxFuture<'_, Result<U64, ProviderError>> {
+        self.respond(self.block_number.into())
+    }
+
+    fn get_storage_at(
+        &self,
+        address: Address,
+        slot: H256,
+    ) -> BoxFuture<'_, Result<H256, ProviderError>> {
+        self.respond(self.storage.get(&(address, slot)).copied().unwrap_or_default())
+    }
+
+    fn get_transaction(
+        &self,
+        tx_hash: TxHash,
+    ) -> BoxFuture<'_, Result<Option<Transaction>, ProviderError>> {
+        let tx = self.blocks.values().flatten().find(|tx| tx.hash == tx_hash).cloned();
+        self.respond(tx)
+    }
+
+    fn get_transaction_receipt(
+        &self,
+        tx_hash: TxHash,
+    ) -> BoxFuture<'_, Result<Option<TransactionReceipt>, ProviderError>> {
+        self.respond(self.receipts.get(&tx_hash).cloned())
+    }
+
+    fn get_transaction_count(
+        &self,
+        address: Address,
+        block: Option<BlockId>,
+    ) -> BoxFuture<'_, Result<U256, ProviderError>> {
+        let until = self.block_number(block).unwrap_or_default();
+        let count = self
+            .blocks
+            .iter()
+            .filter(|(number, _)| **number <= until)
+            .flat_map(|(_, txs)| txs)
+            .filter(|tx| tx.from == address)
+            .count();
+        self.respond(count.into())
+    }
+
+    fn trace_transaction(
+        &self,
+        tx_hash: TxHash,
+    ) -> BoxFuture<'_, Result<Vec<Trace>, ProviderError>> {
+        self.respond(self.traces.get(&tx_hash).cloned().unwrap_or_default())
+    }
+}
@@ -0,0 +1,49 @@
+use crate::routes::{clone_repo, VerifyError};
+use futures::future::BoxFuture;
+use std::path::Path;
+
+/// A repository and commit to clone, as given by the caller.
+#[derive(Debug, Clone, Copy)]
+pub struct CloneRequest<'a> {
+    /// The URL of the repository, which may contain credentials.
+    pub repo_url: &'a str,
+    /// The commit, branch, or tag to check out.
+    pub repo_commit: &'a str,
+    /// The empty directory to clone the repository into.
+    pub dest: &'a Path,
+    /// Whether to clone the repository's submodules too.
+    pub update_submodules: bool,
+    /// Optional token used to authenticate the clone, for private repositories.
+    pub auth_token: Option<&'a str>,
+}
+
+/// Clones repositories to verify. The clone must be a git repository, since other commits are
+/// checked out in it when the requested commit doesn't match. This lets tests verify fixture
+/// repositories without cloning from a git host.
+pub trait RepoCloner: Send + Sync {
+    /// Clones the repository into `request.dest` and checks out the commit, returning the full
+    /// hash of the commit that was checked out.
+    fn clone_repo<'a>(
+        &'a self,
+        request: CloneRequest<'a>,
+    ) -> BoxFuture<'a, Result<String, VerifyError>>;
+}
+
+/// Clones repositories from their git host, as `clone_repo` does.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GitCloner;
+
+impl RepoCloner for GitCloner {
+    fn clone_repo<'a>(
+        &'a self,
+        request: CloneRequest<'a>,
+    ) -> BoxFuture<'a, Result<String, VerifyError>> {
+        Box::pin(clone_repo(
+            request.repo_url,
+            request.repo_commit,
+            request.dest,
+            request.update_submodules,
+            request.auth_token,
+        ))
+    }
+}
@@ -0,0 +1,761 @@
+use crate::bytecode::{
+    bytecode_eq, metadata_length_at, ExpectedCreationBytecode, ExpectedDeployedBytecode,
+    FoundCreationBytecode, FoundDeployedBytecode, MatchType, MetadataComparison,
+};
+use ethers::types::{Bytes, Chain, H256};
+use ethers_solc::artifacts::Offsets;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Length of a word of zkSync Era bytecode in bytes. Bytecode must be a whole number of words.
+const ZKSYNC_WORD_LENGTH: usize = 32;
+
+/// Version byte of the zkSync Era bytecode hash format.
+const ZKSYNC_BYTECODE_HASH_VERSION: u8 = 1;
+
+/// How strong a match is, based on the comparator that found it. Explorers can use this to show
+/// whether a contract is a full or partial match.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchQuality {
+    /// The code is identical to the artifact's, other than the immutables set at deployment.
+    Exact,
+    /// The code matched once the trailing metadata was removed, so the sources may differ in
+    /// comments or file paths. This is the default since it's the weakest quality.
+    #[default]
+    MetadataInsensitive,
+    /// The creation code matched once the constructor arguments appended to it were removed.
+    PartialWithArgs,
+}
+
+/// A strategy for comparing the bytecode from a project's artifacts against the bytecode found on
+/// a chain. Each comparator handles one way the code can differ, and comparators are run in order
+/// by a `ComparatorChain`. A comparator returns `MatchType::None` for code it doesn't handle, so
+/// the next comparator can try it.
+pub trait BytecodeComparator: Send + Sync {
+    /// A short name for the comparator, reported with matches so callers know why code matched.
+    fn name(&self) -> &'static str;
+
+    /// The quality of the matches this comparator finds.
+    fn quality(&self) -> MatchQuality;
+
+    /// Returns how well the artifact's creation code matches the code used to create the contract.
+    fn compare_creation(
+        &self,
+        found: &FoundCreationBytecode,
+        expected: &ExpectedCreationBytecode,
+    ) -> MatchType;
+
+    /// Returns how well the artifact's deployed code matches the code deployed on-chain.
+    fn compare_deployed(
+        &self,
+        found: &FoundDeployedBytecode,
+        expected: &ExpectedDeployedBytecode,
+    ) -> MatchType;
+}
+
+/// Matches code that is byte-for-byte identical, including its metadata. Creation code only
+/// matches if it has no constructor arguments.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExactComparator;
+
+impl BytecodeComparator for ExactComparator {
+    fn name(&self) -> &'static str {
+        "exact"
+    }
+
+    fn quality(&self) -> MatchQuality {
+        MatchQuality::Exact
+    }
+
+    fn compare_creation(
+        &self,
+        found: &FoundCreationBytecode,
+        expected: &ExpectedCreationBytecode,
+    ) -> MatchType {
+        let has_constructor_args =
+            expected.constructor_args.as_ref().map_or(false, |args| !args.is_empty());
+        if !found.raw_code.is_empty()
+            && found.raw_code == expected.raw_code
+            && !has_constructor_args
+        {
+            return MatchType::Full
+        }
+        MatchType::None
+    }
+
+    fn compare_deployed(
+        &self,
+        found: &FoundDeployedBytecode,
+        expected: &ExpectedDeployedBytecode,
+    ) -> MatchType {
+        if !found.raw_code.is_empty() && found.raw_code == expected.raw_code {
+            return MatchType::Full
+        }
+        MatchType::None
+    }
+}
+
+/// Matches creation code that is the artifact's creation code followed by exactly the constructor
+/// arguments. Deployed code has no constructor arguments, so it never matches.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PrefixWithConstructorArgsComparator;
+
+impl BytecodeComparator for PrefixWithConstructorArgsComparator {
+    fn name(&self) -> &'static str {
+        "constructor-args"
+    }
+
+    fn quality(&self) -> MatchQuality {
+        MatchQuality::PartialWithArgs
+    }
+
+    fn compare_creation(
+        &self,
+        found: &FoundCreationBytecode,
+        expected: &ExpectedCreationBytecode,
+    ) -> MatchType {
+        let Some(constructor_args) = expected.constructor_args.as_ref() else {
+            return MatchType::None
+        };
+        if found.raw_code.is_empty() || constructor_args.is_empty() {
+            return MatchType::None
+        }
+        let (code, args) = expected
+            .raw_code
+            .split_at(expected.raw_code.len().saturating_sub(constructor_args.len()));
+        if code == found.raw_code.as_ref() && args == constructor_args.as_ref() {
+            return MatchType::Full
+        }
+        MatchType::None
+    }
+
+    fn compare_deployed(
+        &self,
+        _found: &FoundDeployedBytecode,
+        _expected: &ExpectedDeployedBytecode,
+    ) -> MatchType {
+        MatchType::None
+    }
+}
+
+/// Matches deployed code that is identical once the immutables in the on-chain code are replaced
+/// with the zeroes solc leaves as placeholders in the artifact. Creation code has no immutable
+/// values, so it never matches.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ImmutableMaskedComparator;
+
+impl BytecodeComparator for ImmutableMaskedComparator {
+    fn name(&self) -> &'static str {
+        "immutables-masked"
+    }
+
+    fn quality(&self) -> MatchQuality {
+        MatchQuality::Exact
+    }
+
+    fn compare_creation(
+        &self,
+        _found: &FoundCreationBytecode,
+        _expected: &ExpectedCreationBytecode,
+    ) -> MatchType {
+        MatchType::None
+    }
+
+    fn compare_deployed(
+        &self,
+        found: &FoundDeployedBytecode,
+        expected: &ExpectedDeployedBytecode,
+    ) -> MatchType {
+        if found.raw_code.is_empty() || found.raw_code.len() != expected.raw_code.len() {
+            return MatchType::None
+        }
+        match mask_immutables(found, expected) {
+            Some((raw_code, _)) if raw_code == found.raw_code.as_ref() => MatchType::Full,
+            _ => MatchType::None,
+        }
+    }
+}
+
+/// Matches code that is identical once the trailing metadata is removed, e.g. because the sources
+/// were compiled at different paths or with a different metadata hash type. Immutables in deployed
+/// code are masked before comparing. These are partial matches, since the metadata commits to the
+/// exact sources.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MetadataStrippedComparator;
+
+impl BytecodeComparator for MetadataStrippedComparator {
+    fn name(&self) -> &'static str {
+        "metadata-stripped"
+    }
+
+    fn quality(&self) -> MatchQuality {
+        MatchQuality::MetadataInsensitive
+    }
+
+    fn compare_creation(
+        &self,
+        found: &FoundCreationBytecode,
+        expected: &ExpectedCreationBytecode,
+    ) -> MatchType {
+        if found.raw_code.is_empty() || found.leading_code != expected.leading_code {
+            return MatchType::None
+        }
+        // Expected code might contain appended constructor arguments, so if code matches then
+        // expected is usually equal to or longer than found code. It can be shorter when the
+        // on-chain metadata is shorter than the artifact's, e.g. CBOR only instead of an IPFS hash,
+        // which is only accepted if that metadata parses exactly rather than by a guessed length.
+        let expected_metadata = &expected.raw_code[expected.leading_code.len()..];
+        if found.raw_code.len() <= expected.raw_code.len()
+            || metadata_length_at(expected_metadata).is_some()
+        {
+            return MatchType::Partial
+        }
+        MatchType::None
+    }
+
+    fn compare_deployed(
+        &self,
+        found: &FoundDeployedBytecode,
+        expected: &ExpectedDeployedBytecode,
+    ) -> MatchType {
+        if found.raw_code.is_empty() {
+            return MatchType::None
+        }
+
+        // Code with metadata of a different length can only be compared by stripping each side's
+        // own metadata. Immutables can't be masked then, since their offsets are from the artifact.
+        if found.raw_code.len() != expected.raw_code.len() {
+            let matches_without_metadata = found.immutable_references.is_empty()
+                && bytecode_eq(&found.raw_code, &expected.raw_code, MetadataComparison::Strip);
+            return if matches_without_metadata { MatchType::Partial } else { MatchType::None }
+        }
+
+        match mask_immutables(found, expected) {
+            Some((_, leading_code)) if leading_code == found.leading_code.as_ref() => {
+                MatchType::Partial
+            }
+            _ => MatchType::None,
+        }
+    }
+}
+
+/// Compares zkSync Era bytecode compiled with zksolc. zkSync identifies contracts by the hash of
+/// their bytecode rather than the bytecode itself, so the hash of the artifact's bytecode is
+/// compared against the hash of the code deployed on-chain. Contracts are created by calling the
+/// `ContractDeployer` system contract with the bytecode hash, so there is no creation code to
+/// compare.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ZkSyncComparator;
+
+impl BytecodeComparator for ZkSyncComparator {
+    fn name(&self) -> &'static str {
+        "zksync-code-hash"
+    }
+
+    fn quality(&self) -> MatchQuality {
+        MatchQuality::Exact
+    }
+
+    fn compare_creation(
+        &self,
+        _found: &FoundCreationBytecode,
+        _expected: &ExpectedCreationBytecode,
+    ) -> MatchType {
+        MatchType::None
+    }
+
+    fn compare_deployed(
+        &self,
+        found: &FoundDeployedBytecode,
+        expected: &ExpectedDeployedBytecode,
+    ) -> MatchType {
+        // Immutables are stored by the `ImmutableSimulator` system contract rather than in the
+        // code, so the hashes are equal only if the code is identical, including its metadata.
+        match (zksync_bytecode_hash(&found.raw_code), zksync_bytecode_hash(&expected.raw_code)) {
+            (Some(found_hash), Some(expected_hash)) if found_hash == expected_hash => {
+                MatchType::Full
+            }
+            _ => MatchType::None,
+        }
+    }
+}
+
+/// The result of running a `ComparatorChain`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    /// The best match found by any comparator.
+    pub match_type: MatchType,
+    /// The name of the comparator that found the match, or `None` if nothing matched.
+    pub comparator: Option<&'static str>,
+    /// The quality of the match, or `None` if nothing matched.
+    pub quality: Option<MatchQuality>,
+}
+
+/// An ordered list of comparators. Code is compared with each of them, and the first full match
+/// is returned, or the first partial match if no comparator found a full match.
+pub struct ComparatorChain {
+    /// The comparators to run, in order.
+    comparators: Vec<Box<dyn BytecodeComparator>>,
+}
+
+impl ComparatorChain {
+    /// Returns a chain that runs the given comparators in order.
+    pub fn new(comparators: Vec<Box<dyn BytecodeComparator>>) -> Self {
+        Self { comparators }
+    }
+
+    /// Returns the comparators for EVM bytecode compiled with solc. Comparators that find full
+    /// matches run before the metadata is stripped.
+    pub fn evm() -> Self {
+        Self::new(vec![
+            Box::new(ExactComparator),
+            Box::new(PrefixWithConstructorArgsComparator),
+            Box::new(ImmutableMaskedComparator),
+            Box::new(MetadataStrippedComparator),
+        ])
+    }
+
+    /// Returns the comparators for bytecode deployed on the given chain. zkSync Era contracts
+    /// aren't EVM bytecode, so they're compared by their code hash.
+    pub fn for_chain(chain: Chain) -> Self {
+        match chain {
+            Chain::ZkSync | Chain::ZkSyncTestnet => Self::new(vec![Box::new(ZkSyncComparator)]),
+            _ => Self::evm(),
+        }
+    }
+
+    /// Returns the names of the comparators, in the order they're run.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.comparators.iter().map(|comparator| comparator.name()).collect()
+    }
+
+    /// Compares the artifact's creation code against the code used to create the contract.
+    pub fn compare_creation(
+        &self,
+        found: &FoundCreationBytecode,
+        expected: &ExpectedCreationBytecode,
+    ) -> Comparison {
+        self.best(|comparator| comparator.compare_creation(found, expected))
+    }
+
+    /// Compares the artifact's deployed code against the code deployed on-chain.
+    pub fn compare_deployed(
+        &self,
+        found: &FoundDeployedBytecode,
+        expected: &ExpectedDeployedBytecode,
+    ) -> Comparison {
+        self.best(|comparator| comparator.compare_deployed(found, expected))
+    }
+
+    /// Runs `compare` with each comparator and returns the best match, stopping at the first full
+    /// match.
+    fn best(&self, compare: impl Fn(&dyn BytecodeComparator) -> MatchType) -> Comparison {
+        let mut best = Comparison::default();
+        for comparator in &self.comparators {
+            match compare(comparator.as_ref()) {
+                MatchType::Full => {
+                    return Comparison {
+                        match_type: MatchType::Full,
+                        comparator: Some(comparator.name()),
+                        quality: Some(comparator.quality()),
+                    }
+                }
+                MatchType::Partial if best.match_type == MatchType::None => {
+                    best = Comparison {
+                        match_type: MatchType::Partial,
+                        comparator: Some(comparator.name()),
+                        quality: Some(comparator.quality()),
+                    };
+                }
+                _ => {}
+            }
+        }
+        best
+    }
+}
+
+impl Default for ComparatorChain {
+    fn default() -> Self {
+        Self::evm()
+    }
+}
+
+/// Returns the expected raw and leading code with each of the found code's immutable references
+/// replaced by zeroes, which is how solc leaves them in artifacts. Returns `None` if the found and
+/// expected code don't have the same immutable references, or a reference lies past the end of
+/// the leading code, since then the references can't be from the contract that was deployed.
+fn mask_immutables(
+    found: &FoundDeployedBytecode,
+    expected: &ExpectedDeployedBytecode,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    if found.immutable_references != expected.immutable_references {
+        return None
+    }
+    let mut raw_code = expected.raw_code.to_vec();
+    let mut leading_code = expected.leading_code.to_vec();
+    let offsets: Vec<&Offsets> = found.immutable_references.values().flatten().collect();
+    for offset in offsets {
+        let immutable_start = offset.start as usize;
+        let immutable_end = immutable_start + offset.length as usize;
+        if immutable_end > leading_code.len() || immutable_end > raw_code.len() {
+            return None
+        }
+        raw_code[immutable_start..immutable_end].fill(0);
+        leading_code[immutable_start..immutable_end].fill(0);
+    }
+    Some((raw_code, leading_code))
+}
+
+/// Returns the zkSync Era hash of the bytecode, which is the hash zksolc outputs in an artifact's
+/// `hash` field and the hash the chain stores as the account's code hash. The first byte is the
+/// version, the second is zero for deployed code, the next two are the length of the code in
+/// words, and the rest are the last 28 bytes of the SHA-256 hash of the code. Returns `None` if
+/// the code isn't valid zkSync bytecode, i.e. it isn't an odd number of words that fits in the
+/// length field.
+pub fn zksync_bytecode_hash(code: &Bytes) -> Option<H256> {
+    if code.len() % ZKSYNC_WORD_LENGTH != 0 {
+        return None
+    }
+    let words = u16::try_from(code.len() / ZKSYNC_WORD_LENGTH).ok()?;
+    if words % 2 == 0 {
+        return None
+    }
+
+    let mut hash: [u8; 32] = Sha256::digest(code).into();
+    hash[0] = ZKSYNC_BYTECODE_HASH_VERSION;
+    hash[1] = 0;
+    hash[2..4].copy_from_slice(&words.to_be_bytes());
+    Some(H256(hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::{strip_metadata, ImmutableReferences, LinkReferences, MetadataInfo};
+    use proptest::{collection::vec, prelude::*};
+    use std::str::FromStr;
+
+    /// Returns found creation code with the given raw code, followed by `metadata`.
+    fn found_creation(code: &[u8], metadata: &[u8]) -> FoundCreationBytecode {
+        FoundCreationBytecode {
+            raw_code: [code, metadata].concat().into(),
+            leading_code: code.to_vec().into(),
+            metadata: MetadataInfo::default(),
+            link_references: LinkReferences::new(),
+        }
+    }
+
+    /// Returns expected creation code with the given code, metadata, and constructor arguments.
+    fn expected_creation(code: &[u8], metadata: &[u8], args: &[u8]) -> ExpectedCreationBytecode {
+        ExpectedCreationBytecode {
+            raw_code: [code, metadata, args].concat().into(),
+            leading_code: code.to_vec().into(),
+            metadata: MetadataInfo::default(),
+            constructor_args: Some(args.to_vec().into()).filter(|args: &Bytes| !args.is_empty()),
+        }
+    }
+
+    /// Returns found deployed code with the given code, followed by `metadata`, and immutables.
+    fn found_deployed(
+        code: &[u8],
+        metadata: &[u8],
+        immutable_references: &ImmutableReferences,
+    ) -> FoundDeployedBytecode {
+        FoundDeployedBytecode {
+            raw_code: [code, metadata].concat().into(),
+            leading_code: code.to_vec().into(),
+            metadata: MetadataInfo::default(),
+            immutable_references: immutable_references.clone(),
+            link_references: LinkReferences::new(),
+        }
+    }
+
+    /// Returns expected deployed code with the given code, followed by `metadata`, and immutables.
+    fn expected_deployed(
+        code: &[u8],
+        metadata: &[u8],
+        immutable_references: &ImmutableReferences,
+    ) -> ExpectedDeployedBytecode {
+        ExpectedDeployedBytecode {
+            raw_code: [code, metadata].concat().into(),
+            leading_code: code.to_vec().into(),
+            metadata: MetadataInfo::default(),
+            immutable_references: immutable_references.clone(),
+        }
+    }
+
+    /// Immutable references with a single 2-byte immutable at offset 1.
+    fn immutable_references() -> ImmutableReferences {
+        ImmutableReferences::from([("7".to_string(), vec![Offsets { start: 1, length: 2 }])])
+    }
+
+    #[test]
+    fn test_exact_comparator() {
+        let found = found_creation(&[0x60, 0x80], &[0xa1]);
+        let comparator = ExactComparator;
+        let exact = expected_creation(&[0x60, 0x80], &[0xa1], &[]);
+        assert_eq!(comparator.compare_creation(&found, &exact), MatchType::Full);
+        // Constructor arguments or different metadata aren't exact matches.
+        let with_args = expected_creation(&[0x60, 0x80], &[0xa1], &[0x2a]);
+        assert_eq!(comparator.compare_creation(&found, &with_args), MatchType::None);
+        let other_metadata = expected_creation(&[0x60, 0x80], &[0xa2], &[]);
+        assert_eq!(comparator.compare_creation(&found, &other_metadata), MatchType::None);
+
+        let none = ImmutableReferences::new();
+        let found = found_deployed(&[0x60, 0x80], &[0xa1], &none);
+        let exact = expected_deployed(&[0x60, 0x80], &[0xa1], &none);
+        assert_eq!(comparator.compare_deployed(&found, &exact), MatchType::Full);
+        // Empty code is an interface, which can't match anything.
+        let empty = found_deployed(&[], &[], &none);
+        assert_eq!(
+            comparator.compare_deployed(&empty, &expected_deployed(&[], &[], &none)),
+            MatchType::None
+        );
+    }
+
+    #[test]
+    fn test_prefix_with_constructor_args_comparator() {
+        let found = found_creation(&[0x60, 0x80], &[0xa1]);
+        let comparator = PrefixWithConstructorArgsComparator;
+        let with_args = expected_creation(&[0x60, 0x80], &[0xa1], &[0x00, 0x2a]);
+        assert_eq!(comparator.compare_creation(&found, &with_args), MatchType::Full);
+        // The code before the arguments must match exactly, and there must be arguments.
+        let other_code = expected_creation(&[0x60, 0x81], &[0xa1], &[0x00, 0x2a]);
+        assert_eq!(comparator.compare_creation(&found, &other_code), MatchType::None);
+        let without_args = expected_creation(&[0x60, 0x80], &[0xa1], &[]);
+        assert_eq!(comparator.compare_creation(&found, &without_args), MatchType::None);
+    }
+
+    #[test]
+    fn test_immutable_masked_comparator() {
+        let references = immutable_references();
+        let found = found_deployed(&[0x60, 0x00, 0x00, 0x80], &[0xa1], &references);
+        let comparator = ImmutableMaskedComparator;
+        let with_immutable = expected_deployed(&[0x60, 0x12, 0x34, 0x80], &[0xa1], &references);
+        assert_eq!(comparator.compare_deployed(&found, &with_immutable), MatchType::Full);
+        // Bytes outside the immutables must still match.
+        let other_code = expected_deployed(&[0x61, 0x12, 0x34, 0x80], &[0xa1], &references);
+        assert_eq!(comparator.compare_deployed(&found, &other_code), MatchType::None);
+        // Different metadata is left to `MetadataStrippedComparator`.
+        let other_metadata = expected_deployed(&[0x60, 0x12, 0x34, 0x80], &[0xa2], &references);
+        assert_eq!(comparator.compare_deployed(&found, &other_metadata), MatchType::None);
+    }
+
+    #[test]
+    fn test_metadata_stripped_comparator() {
+        let found = found_creation(&[0x60, 0x80], &[0xa1]);
+        let comparator = MetadataStrippedComparator;
+        let other_metadata = expected_creation(&[0x60, 0x80], &[0xa2], &[]);
+        assert_eq!(comparator.compare_creation(&found, &other_metadata), MatchType::Partial);
+        let other_code = expected_creation(&[0x60, 0x81], &[0xa1], &[]);
+        assert_eq!(comparator.compare_creation(&found, &other_code), MatchType::None);
+
+        // On-chain metadata shorter than the artifact's still matches if it parses exactly, even
+        // though the whole code, with constructor arguments, is shorter than the artifact's.
+        let ipfs_hash = [&[0x58, 0x22, 0x12, 0x20][..], &[0xab; 32]].concat();
+        let solc_version = [0x43, 0x00, 0x06, 0x0b];
+        let ipfs_metadata = [
+            &[0xa2, 0x64],
+            &b"ipfs"[..],
+            &ipfs_hash,
+            &[0x64],
+            b"solc",
+            &solc_version,
+            &[0x00, 0x33],
+        ]
+        .concat();
+        let cbor_metadata = [&[0xa1, 0x64], &b"solc"[..], &solc_version, &[0x00, 0x0a]].concat();
+        let found = found_creation(&[0x60, 0x80], &ipfs_metadata);
+        let shorter_metadata = expected_creation(&[0x60, 0x80], &cbor_metadata, &[0x2a; 32]);
+        assert_eq!(comparator.compare_creation(&found, &shorter_metadata), MatchType::Partial);
+        let unparsed_metadata = expected_creation(&[0x60, 0x80], &[0xa1], &[0x2a; 32]);
+        assert_eq!(comparator.compare_creation(&found, &unparsed_metadata), MatchType::None);
+
+        // Immutables are masked before the leading code is compared.
+        let references = immutable_references();
+        let found = found_deployed(&[0x60, 0x00, 0x00, 0x80], &[0xa1], &references);
+        let with_immutable = expected_deployed(&[0x60, 0x12, 0x34, 0x80], &[0xa2], &references);
+        assert_eq!(comparator.compare_deployed(&found, &with_immutable), MatchType::Partial);
+    }
+
+    #[test]
+    fn test_comparator_chain() {
+        // The first full match is reported, even if an earlier comparator found a partial match.
+        let chain = ComparatorChain::new(vec![
+            Box::new(MetadataStrippedComparator),
+            Box::new(PrefixWithConstructorArgsComparator),
+        ]);
+        let found = found_creation(&[0x60, 0x80], &[0xa1]);
+        let with_args = expected_creation(&[0x60, 0x80], &[0xa1], &[0x2a]);
+        let comparison = chain.compare_creation(&found, &with_args);
+        assert_eq!(comparison.match_type, MatchType::Full);
+        assert_eq!(comparison.comparator, Some("constructor-args"));
+
+        let other_metadata = expected_creation(&[0x60, 0x80], &[0xa2], &[]);
+        let comparison = ComparatorChain::evm().compare_creation(&found, &other_metadata);
+        assert_eq!(comparison.match_type, MatchType::Partial);
+        assert_eq!(comparison.comparator, Some("metadata-stripped"));
+
+        let other_code = expected_creation(&[0x61], &[], &[]);
+        assert_eq!(
+            ComparatorChain::evm().compare_creation(&found, &other_code),
+            Comparison::default()
+        );
+    }
+
+    #[test]
+    fn test_match_quality() {
+        let chain = ComparatorChain::evm();
+        let quality = |comparison: Comparison| comparison.quality;
+
+        let found = found_creation(&[0x60, 0x80], &[0xa1]);
+        let exact = expected_creation(&[0x60, 0x80], &[0xa1], &[]);
+        assert_eq!(quality(chain.compare_creation(&found, &exact)), Some(MatchQuality::Exact));
+        let with_args = expected_creation(&[0x60, 0x80], &[0xa1], &[0x2a]);
+        let with_args = quality(chain.compare_creation(&found, &with_args));
+        assert_eq!(with_args, Some(MatchQuality::PartialWithArgs));
+        let other_metadata = expected_creation(&[0x60, 0x80], &[0xa2], &[]);
+        let other_metadata = quality(chain.compare_creation(&found, &other_metadata));
+        assert_eq!(other_metadata, Some(MatchQuality::MetadataInsensitive));
+        let other_code = expected_creation(&[0x61], &[], &[]);
+        assert_eq!(quality(chain.compare_creation(&found, &other_code)), None);
+
+        // Immutables are set at deployment, so masking them still gives an exact match.
+        let references = immutable_references();
+        let found = found_deployed(&[0x60, 0x00, 0x00, 0x80], &[0xa1], &references);
+        let with_immutable = expected_deployed(&[0x60, 0x12, 0x34, 0x80], &[0xa1], &references);
+        let with_immutable = quality(chain.compare_deployed(&found, &with_immutable));
+        assert_eq!(with_immutable, Some(MatchQuality::Exact));
+    }
+
+    #[test]
+    fn test_zksync_bytecode_hash() -> Result<(), Box<dyn std::error::Error>> {
+        // One word of zeroes, whose SHA-256 hash is `0x66687aad...0d5f2925`.
+        let code = Bytes::from(vec![0; 32]);
+        let expected =
+            H256::from_str("0x01000001f862bd776c8fc18b8e9f8e20089714856ee233b3902a591d0d5f2925")?;
+        assert_eq!(zksync_bytecode_hash(&code), Some(expected));
+
+        // Code that isn't an odd number of whole words isn't valid.
+        assert_eq!(zksync_bytecode_hash(&Bytes::from(vec![0; 31])), None);
+        assert_eq!(zksync_bytecode_hash(&Bytes::from(vec![0; 64])), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zksync_comparator() {
+        let none = ImmutableReferences::new();
+        let found = found_deployed(&[1; 96], &[], &none);
+
+        // zkSync code matches only if its hash does.
+        let zksync = ComparatorChain::for_chain(Chain::ZkSync);
+        assert_eq!(zksync.names(), vec!["zksync-code-hash"]);
+        let comparison = zksync.compare_deployed(&found, &expected_deployed(&[1; 96], &[], &none));
+        assert_eq!(comparison.match_type, MatchType::Full);
+        let comparison = zksync.compare_deployed(&found, &expected_deployed(&[2; 96], &[], &none));
+        assert_eq!(comparison.match_type, MatchType::None);
+
+        // Other chains compare EVM bytecode.
+        let evm = ComparatorChain::for_chain(Chain::Mainnet);
+        let comparison = evm.compare_deployed(&found, &expected_deployed(&[1; 96], &[], &none));
+        assert_eq!(comparison.comparator, Some("exact"));
+    }
+
+    /// Returns valid trailing metadata: a CBOR map header, then `payload`, then the length of both
+    /// as two big-endian bytes, like the metadata solc appends.
+    fn metadata(header: u8, payload: &[u8]) -> Vec<u8> {
+        let length = u16::try_from(payload.len() + 1).unwrap_or(u16::MAX);
+        [&[header][..], payload, &length.to_be_bytes()].concat()
+    }
+
+    /// Returns found deployed code without immutables whose leading code has the metadata stripped,
+    /// as the framework structures it.
+    fn structured_found(raw_code: &[u8]) -> FoundDeployedBytecode {
+        let raw_code = Bytes::from(raw_code.to_vec());
+        FoundDeployedBytecode {
+            leading_code: strip_metadata(&raw_code),
+            raw_code,
+            metadata: MetadataInfo::default(),
+            immutable_references: ImmutableReferences::new(),
+            link_references: LinkReferences::new(),
+        }
+    }
+
+    /// Returns expected deployed code without immutables, structured like `structured_found`.
+    fn structured_expected(raw_code: &[u8]) -> ExpectedDeployedBytecode {
+        let raw_code = Bytes::from(raw_code.to_vec());
+        ExpectedDeployedBytecode {
+            leading_code: strip_metadata(&raw_code),
+            raw_code,
+            metadata: MetadataInfo::default(),
+            immutable_references: ImmutableReferences::new(),
+        }
+    }
+
+    proptest! {
+        // Each case is cheap, so this bounds the runtime to well under a second.
+        #![proptest_config(ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn prop_bytecode_eq_is_reflexive_and_symmetric(
+            a in vec(any::<u8>(), 0..256),
+            b in vec(any::<u8>(), 0..256),
+        ) {
+            let (a, b) = (Bytes::from(a), Bytes::from(b));
+            for comparison in [MetadataComparison::Include, MetadataComparison::Strip] {
+                prop_assert!(bytecode_eq(&a, &a, comparison));
+                prop_assert_eq!(bytecode_eq(&a, &b, comparison), bytecode_eq(&b, &a, comparison));
+            }
+        }
+
+        #[test]
+        fn prop_strip_metadata_removes_appended_metadata(
+            code in vec(any::<u8>(), 0..256),
+            header in 0xa0u8..=0xbf,
+            payload in vec(any::<u8>(), 0..128),
+        ) {
+            let with_metadata = [code.clone(), metadata(header, &payload)].concat();
+            prop_assert_eq!(strip_metadata(&with_metadata.into()), Bytes::from(code));
+        }
+
+        #[test]
+        fn prop_metadata_stripped_comparator_ignores_metadata(
+            code in vec(any::<u8>(), 1..256),
+            first in (0xa0u8..=0xbf, vec(any::<u8>(), 0..128)),
+            second in (0xa0u8..=0xbf, vec(any::<u8>(), 0..128)),
+        ) {
+            let comparator = MetadataStrippedComparator;
+            let found = [code.clone(), metadata(first.0, &first.1)].concat();
+            let expected = [code.clone(), metadata(second.0, &second.1)].concat();
+
+            // The same code always matches itself, with or without metadata.
+            for raw_code in [&found, &code] {
+                let result = comparator
+                    .compare_deployed(&structured_found(raw_code), &structured_expected(raw_code));
+                prop_assert_ne!(result, MatchType::None);
+            }
+
+            // Metadata of any content or length doesn't affect the match.
+            let result = comparator
+                .compare_deployed(&structured_found(&found), &structured_expected(&expected));
+            prop_assert_ne!(result, MatchType::None);
+        }
+
+        #[test]
+        fn prop_metadata_stripped_comparator_detects_code_changes(
+            code in vec(any::<u8>(), 1..256),
+            index in any::<prop::sample::Index>(),
+            payload in vec(any::<u8>(), 0..128),
+        ) {
+            // Changing a byte of the code, rather than the metadata, is never ignored.
+            let mut changed = code.clone();
+            let index = index.index(code.len());
+            changed[index] = changed[index].wrapping_add(1);
+            let found = [code, metadata(0xa2, &payload)].concat();
+            let expected = [changed, metadata(0xa2, &payload)].concat();
+            let result = MetadataStrippedComparator
+                .compare_deployed(&structured_found(&found), &structured_expected(&expected));
+            prop_assert_eq!(result, MatchType::None);
+        }
+    }
+}
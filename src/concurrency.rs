@@ -0,0 +1,154 @@
+use crate::routes::VerifyError;
+use axum::{
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Environment variable used to override how many verifications may run at once. Setting it to `0`
+/// disables the limit.
+const MAX_CONCURRENT_ENV_VAR: &str = "MAX_CONCURRENT_VERIFICATIONS";
+
+/// Environment variable used to override how many verifications may wait for a running one to
+/// finish before new ones are rejected.
+const MAX_QUEUED_ENV_VAR: &str = "MAX_QUEUED_VERIFICATIONS";
+
+/// Default number of verifications that may run at once.
+const DEFAULT_MAX_CONCURRENT: usize = 4;
+
+/// Default number of verifications that may wait for a running one to finish.
+const DEFAULT_MAX_QUEUED: usize = 16;
+
+/// How long rejected callers are told to wait before trying again. Verifications usually take tens
+/// of seconds, so a slot is likely to have freed up by then.
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Limits how many verifications run at once, since each clones a repository and runs the
+/// compiler, which can exhaust CPU, memory, and file descriptors when unbounded. Verifications over
+/// the limit wait in a bounded queue, and new ones are rejected once the queue is full.
+pub struct VerificationLimiter {
+    /// Permits for verifications that are running or queued. `None` if there is no limit.
+    admitted: Option<Arc<Semaphore>>,
+    /// Permits for verifications that are running.
+    running: Arc<Semaphore>,
+}
+
+/// A verification's place in the queue, which is released when dropped. Call `start` to wait for
+/// it to be allowed to run.
+pub struct VerificationTicket {
+    /// The permit admitting the verification, or `None` if there is no limit.
+    admitted: Option<OwnedSemaphorePermit>,
+    /// Permits for verifications that are running.
+    running: Arc<Semaphore>,
+}
+
+/// Permission for a verification to run, which is released when dropped.
+pub struct VerificationPermit {
+    /// The permits admitting and running the verification, or `None` if there is no limit.
+    _permits: Option<(OwnedSemaphorePermit, OwnedSemaphorePermit)>,
+}
+
+impl VerificationLimiter {
+    /// Returns a limiter allowing `max_concurrent` verifications to run at once, with up to
+    /// `max_queued` more waiting. A `max_concurrent` of `0` disables the limit.
+    pub fn new(max_concurrent: usize, max_queued: usize) -> Self {
+        let admitted = (max_concurrent > 0)
+            .then(|| Arc::new(Semaphore::new(max_concurrent.saturating_add(max_queued))));
+        Self { admitted, running: Arc::new(Semaphore::new(max_concurrent)) }
+    }
+
+    /// Returns a limiter configured by `MAX_CONCURRENT_VERIFICATIONS` and
+    /// `MAX_QUEUED_VERIFICATIONS`, which default to 4 and 16 if not set to valid numbers.
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: usize| {
+            std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+        };
+        Self::new(
+            read(MAX_CONCURRENT_ENV_VAR, DEFAULT_MAX_CONCURRENT),
+            read(MAX_QUEUED_ENV_VAR, DEFAULT_MAX_QUEUED),
+        )
+    }
+
+    /// Returns true if the number of verifications is limited.
+    pub fn is_enabled(&self) -> bool {
+        self.admitted.is_some()
+    }
+
+    /// Admits a new verification, or rejects it if as many as allowed are already running and
+    /// queued.
+    pub fn admit(&self) -> Result<VerificationTicket, VerificationRejected> {
+        let admitted = match &self.admitted {
+            Some(admitted) => {
+                Some(Arc::clone(admitted).try_acquire_owned().map_err(|_| VerificationRejected)?)
+            }
+            None => None,
+        };
+        Ok(VerificationTicket { admitted, running: Arc::clone(&self.running) })
+    }
+}
+
+impl Default for VerificationLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT, DEFAULT_MAX_QUEUED)
+    }
+}
+
+impl VerificationTicket {
+    /// Waits until fewer than the maximum number of verifications are running, then returns the
+    /// permit to run this one.
+    pub async fn start(self) -> VerificationPermit {
+        let Some(admitted) = self.admitted else { return VerificationPermit { _permits: None } };
+        // The semaphore is never closed, so acquiring a permit can't fail.
+        let running = self.running.acquire_owned().await.expect("Semaphore closed");
+        VerificationPermit { _permits: Some((admitted, running)) }
+    }
+}
+
+/// Returned when a verification is rejected because the server is at capacity. It's converted into
+/// a `503` with a `Retry-After` header.
+#[derive(Debug, PartialEq, Eq)]
+pub struct VerificationRejected;
+
+impl IntoResponse for VerificationRejected {
+    fn into_response(self) -> Response {
+        let retry_after_secs = RETRY_AFTER.as_secs();
+        tracing::warn!("Rejecting verification since too many are running and queued");
+        let msg = format!("Too many verifications are running, try again in {retry_after_secs}s");
+        let mut response = VerifyError::ServiceUnavailable(msg).into_response();
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_admit() -> Result<(), Box<dyn std::error::Error>> {
+        let limiter = VerificationLimiter::new(1, 1);
+
+        // One verification runs while another waits in the queue.
+        let first = limiter.admit().map_err(|_| "First verification rejected")?.start().await;
+        let second = limiter.admit().map_err(|_| "Second verification rejected")?;
+        let waiting = tokio::spawn(second.start());
+
+        // Once the queue is full, new verifications are rejected.
+        assert!(matches!(limiter.admit(), Err(VerificationRejected)));
+
+        // The queued verification starts once the running one finishes, which frees up a place.
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_secs(5), waiting).await??;
+        let third = limiter.admit().map_err(|_| "Third verification rejected")?;
+        assert!(matches!(limiter.admit(), Err(VerificationRejected)));
+        drop((second, third));
+
+        // A limit of zero disables the limit.
+        let unlimited = VerificationLimiter::new(0, 0);
+        assert!(!unlimited.is_enabled());
+        let permits: Vec<_> = (0..100).filter_map(|_| unlimited.admit().ok()).collect();
+        assert_eq!(permits.len(), 100);
+        Ok(())
+    }
+}
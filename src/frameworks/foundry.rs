@@ -1,67 +1,223 @@
-use super::framework::Framework;
+use super::framework::{
+    read_artifact_json, ArtifactExclusions, ArtifactTooLarge, BuildConfigError, Framework,
+};
 
 use crate::bytecode::{
-    parse_metadata, ExpectedCreationBytecode, ExpectedDeployedBytecode, FoundCreationBytecode,
-    FoundDeployedBytecode, ImmutableReferences, MetadataInfo,
+    bytecode_object_to_bytes, metadata_length_at, parse_metadata, ExpectedCreationBytecode,
+    ExpectedDeployedBytecode, FoundCreationBytecode, FoundDeployedBytecode, ImmutableReferences,
+    LinkReferences, MetadataInfo,
 };
 use ethers::types::Bytes;
-use ethers_solc::{
-    artifacts::{BytecodeHash, BytecodeObject, LosslessAbi, SettingsMetadata},
-    ConfigurableContractArtifact,
+use ethers_solc::artifacts::{
+    BytecodeHash, CompactBytecode, CompactDeployedBytecode, LosslessAbi, SettingsMetadata,
 };
 use std::{
     error::Error,
     fs,
-    path::{Path, PathBuf},
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
     process::Command,
     result::Result,
 };
 use walkdir::WalkDir;
 
+/// Name of the directory, relative to the project root, that Foundry writes artifacts to unless
+/// `foundry.toml` sets `out`.
+const DEFAULT_OUT_DIR: &str = "out";
+
+/// Name of the directory, relative to the project root, that each build command writes its
+/// artifacts to a numbered subdirectory of.
+const BUILD_OUT_DIR: &str = "cove_out";
+
+/// Name of the directory, relative to the project root, that each build command writes its build
+/// info files to a numbered subdirectory of.
+const BUILD_INFO_DIR: &str = "cove_build_info";
+
+/// Name of the directory, relative to the project root, that each build command writes its
+/// compilation cache to a numbered subdirectory of.
+const BUILD_CACHE_DIR: &str = "cove_cache";
+
+/// Name of the directory, relative to the project root, that Soldeer installs dependencies to.
+const SOLDEER_DEPENDENCIES_DIR: &str = "dependencies";
+
 /// Implementation of the `Framework` trait for Foundry projects.
 pub struct Foundry {
     /// Path to the project.
     path: PathBuf,
+    /// The directory to read artifacts from. If `None`, the output directories configured in
+    /// `foundry.toml` are searched.
+    out_dir: Option<PathBuf>,
+    /// The directory containing the build info files.
+    build_info_dir: PathBuf,
+    /// The profile this view of the project is built with. If `None`, the project hasn't been
+    /// assigned a build.
+    profile: Option<String>,
 }
 
 impl Foundry {
-    /// Given all artifacts that were generated by compilation, filter out contracts that cannot be
-    /// the most-derived contract the user is interested in. For now this just filters out contracts
-    /// where all sources are in the `lib/` directory.
-    fn filter_artifacts(artifacts: Vec<PathBuf>) -> Vec<PathBuf> {
-        artifacts
+    /// Returns a `Foundry` instance rooted at `path` without checking for a `foundry.toml`. This is
+    /// used by other frameworks that normalize their artifacts into Foundry's artifact format, so
+    /// they can reuse Foundry's bytecode structuring.
+    pub(super) fn from_artifacts_root(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            out_dir: None,
+            build_info_dir: path.join("build_info"),
+            profile: None,
+        }
+    }
+
+    /// Returns the link references of the creation and deployed bytecode in the artifact. These are
+    /// empty if the contract doesn't use any external libraries.
+    fn get_artifact_link_references(
+        artifact: &Path,
+    ) -> Result<(LinkReferences, LinkReferences), Box<dyn Error>> {
+        // Only the bytecode fields are parsed, so this works with partial artifacts.
+        let json_content: serde_json::Value = read_artifact_json(artifact)?;
+        let creation = match json_content.get("bytecode") {
+            Some(value) => {
+                serde_json::from_value::<CompactBytecode>(value.clone())?.link_references
+            }
+            None => LinkReferences::new(),
+        };
+        let deployed = match json_content.get("deployedBytecode") {
+            Some(value) => serde_json::from_value::<CompactDeployedBytecode>(value.clone())?
+                .bytecode
+                .map(|code| code.link_references)
+                .unwrap_or_default(),
+            None => LinkReferences::new(),
+        };
+        Ok((creation, deployed))
+    }
+
+    /// Returns the output directories configured by the profiles in `foundry.toml`, relative to the
+    /// project root. Profiles that don't set `out` use Foundry's default of `out`. Directories
+    /// outside the project are ignored, since the config comes from the repository.
+    fn configured_out_dirs(&self) -> Vec<PathBuf> {
+        let config = fs::read_to_string(self.path.join("foundry.toml"))
+            .ok()
+            .and_then(|contents| contents.parse::<toml::Value>().ok());
+        let profiles = config
+            .as_ref()
+            .and_then(|config| config.get("profile"))
+            .and_then(|profiles| profiles.as_table());
+
+        let mut out_dirs = Vec::new();
+        let configured = profiles.into_iter().flat_map(|profiles| profiles.values());
+        for out_dir in configured.map(|profile| profile.get("out").and_then(|out| out.as_str())) {
+            let out_dir = PathBuf::from(out_dir.unwrap_or(DEFAULT_OUT_DIR));
+            let is_inside_project =
+                out_dir.components().all(|component| matches!(component, Component::Normal(_)));
+            if is_inside_project && !out_dirs.contains(&out_dir) {
+                out_dirs.push(out_dir);
+            }
+        }
+        if out_dirs.is_empty() {
+            out_dirs.push(PathBuf::from(DEFAULT_OUT_DIR));
+        }
+        out_dirs
+    }
+
+    /// Appends the path of every JSON file under `out_dir` to `artifacts`, at any depth. Forge
+    /// writes each contract to `<out>/<File>.sol/<Contract>.json`, so the path identifies both the
+    /// source file and the contract, even when a file defines several contracts. Build info
+    /// directories are skipped, since build info files aren't artifacts and can be very large.
+    /// Paths are sorted, so artifacts are always returned in the same order.
+    fn collect_artifacts(out_dir: &Path, artifacts: &mut Vec<PathBuf>) {
+        let entries = WalkDir::new(out_dir)
+            .sort_by_file_name()
             .into_iter()
-            .filter(|a| {
-                let content = fs::read_to_string(a).unwrap();
-                let json: serde_json::Value = serde_json::from_str(&content).unwrap();
-                if let Some(metadata) = json.get("metadata") {
-                    if let Some(sources) = metadata.get("sources") {
-                        let sources_obj = sources.as_object().unwrap();
-                        let all_sources_are_libs =
-                            sources_obj.keys().all(|key| key.starts_with("lib/"));
-                        return !all_sources_are_libs
-                    }
-                }
-                false // If metadata and sources are missing, this can't be the right contract.
+            .filter_entry(|entry| {
+                !(entry.file_type().is_dir() && entry.file_name() == "build-info")
             })
-            .collect::<Vec<_>>()
+            .filter_map(Result::ok);
+        for entry in entries {
+            if entry.file_type().is_file()
+                && entry.path().extension().map_or(false, |ext| ext == "json")
+            {
+                artifacts.push(entry.into_path());
+            }
+        }
+    }
+
+    /// Given all artifacts that were generated by compilation, filter out contracts that cannot be
+    /// the most-derived contract the user is interested in. This filters out contracts where all
+    /// sources are in the `lib/` directory, and contracts compiled from sources that `exclusions`
+    /// excludes, such as tests and scripts. Returns an `ArtifactTooLarge` error if any artifact is
+    /// larger than the maximum size, since it could be the contract the user is interested in.
+    fn filter_artifacts(
+        artifacts: Vec<PathBuf>,
+        exclusions: &ArtifactExclusions,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut filtered = Vec::new();
+        for a in artifacts {
+            let json: serde_json::Value = match read_artifact_json(&a) {
+                Ok(json) => json,
+                Err(e) if e.is::<ArtifactTooLarge>() => return Err(e),
+                // Artifacts that can't be read or parsed can't be compared, so they're skipped.
+                Err(_) => continue,
+            };
+            if exclusions.excludes(&Self::artifact_source_path(&a, &json)) {
+                continue
+            }
+            // If metadata and sources are missing, this can't be the right contract.
+            let Some(sources_obj) =
+                json.get("metadata").and_then(|m| m.get("sources")).and_then(|s| s.as_object())
+            else {
+                continue
+            };
+            let all_sources_are_libs = sources_obj.keys().all(|key| key.starts_with("lib/"));
+            if !all_sources_are_libs {
+                filtered.push(a);
+            }
+        }
+        Ok(filtered)
     }
 
-    /// Returns the names of all profiles found in the `foundry.toml` file.
-    fn foundry_profiles(config_file: &PathBuf) -> Result<Vec<String>, Box<dyn Error>> {
-        let contents = fs::read_to_string(config_file).unwrap();
-        let data = contents.parse::<toml::Value>();
-        if data.is_err() {
-            return Err("Unable to parse foundry.toml file".into())
+    /// Returns the path of the source file the artifact was compiled from, as recorded in its
+    /// metadata's compilation target. If the metadata doesn't have one, the name of the artifact's
+    /// directory is used, since Forge names it after the source file, e.g. `Counter.t.sol`.
+    fn artifact_source_path(artifact: &Path, json: &serde_json::Value) -> String {
+        let compilation_target = json["metadata"]["settings"]["compilationTarget"].as_object();
+        match compilation_target.and_then(|target| target.keys().next()) {
+            Some(source_path) => source_path.clone(),
+            None => artifact
+                .parent()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
         }
+    }
+
+    /// Returns the names of all profiles found in the `foundry.toml` file. Returns an error if the
+    /// file doesn't exist, can't be parsed, or its profiles aren't tables.
+    fn foundry_profiles(config_file: &Path) -> Result<Vec<String>, BuildConfigError> {
+        let contents = fs::read_to_string(config_file).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => {
+                BuildConfigError::Missing("No foundry.toml found in the repository".to_string())
+            }
+            _ => BuildConfigError::Invalid(format!("Unable to read foundry.toml: {e}")),
+        })?;
+        let data = contents
+            .parse::<toml::Value>()
+            .map_err(|e| BuildConfigError::Invalid(format!("Unable to parse foundry.toml: {e}")))?;
 
         let mut profiles = Vec::new();
-        if let Some(profiles_table) =
-            data.unwrap().as_table().unwrap().get("profile").and_then(|v| v.as_table())
-        {
-            for key in profiles_table.keys() {
-                profiles.push(key.to_string());
+        match data.get("profile") {
+            Some(toml::Value::Table(profiles_table)) => {
+                for (key, profile) in profiles_table {
+                    if !profile.is_table() {
+                        let msg = format!("Invalid foundry.toml: profile '{key}' is not a table");
+                        return Err(BuildConfigError::Invalid(msg))
+                    }
+                    profiles.push(key.to_string());
+                }
             }
+            Some(_) => {
+                let msg = "Invalid foundry.toml: `profile` is not a table".to_string();
+                return Err(BuildConfigError::Invalid(msg))
+            }
+            None => {}
         }
 
         if !profiles.contains(&"default".to_string()) {
@@ -69,6 +225,67 @@ impl Foundry {
         }
         Ok(profiles)
     }
+
+    /// Returns the names of the profiles in `foundry.toml` that compile with the IR pipeline, i.e.
+    /// set `via_ir = true`. Profiles inherit the setting from the default profile unless they set
+    /// it themselves.
+    fn via_ir_profiles(config_file: &Path) -> Vec<String> {
+        let Ok(contents) = fs::read_to_string(config_file) else { return Vec::new() };
+        let Ok(data) = contents.parse::<toml::Value>() else { return Vec::new() };
+        let Some(profiles) = data.get("profile").and_then(|v| v.as_table()) else {
+            return Vec::new()
+        };
+
+        let via_ir = |profile: &toml::Value| profile.get("via_ir").and_then(|v| v.as_bool());
+        let default_via_ir = profiles.get("default").and_then(via_ir).unwrap_or(false);
+        profiles
+            .iter()
+            .filter(|(_, profile)| via_ir(profile).unwrap_or(default_via_ir))
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    /// Returns the paths of the git submodules listed in the project's `.gitmodules` that are
+    /// missing or empty, such as when the repository was cloned without its submodules.
+    fn missing_submodules(&self) -> Vec<String> {
+        let Ok(contents) = fs::read_to_string(self.path.join(".gitmodules")) else {
+            return Vec::new()
+        };
+        contents
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .filter(|(key, _)| key.trim() == "path")
+            .map(|(_, path)| path.trim().to_string())
+            .filter(|path| Self::is_missing_or_empty(&self.path.join(path)))
+            .collect()
+    }
+
+    /// Returns true if the project declares Soldeer dependencies, in the `dependencies` table of
+    /// `foundry.toml` or `soldeer.toml`, but none have been installed.
+    fn missing_soldeer_dependencies(&self) -> bool {
+        let declares_dependencies = ["foundry.toml", "soldeer.toml"].iter().any(|file| {
+            let Ok(contents) = fs::read_to_string(self.path.join(file)) else { return false };
+            let Ok(data) = contents.parse::<toml::Value>() else { return false };
+            data.get("dependencies")
+                .and_then(|v| v.as_table())
+                .map_or(false, |dependencies| !dependencies.is_empty())
+        });
+        declares_dependencies
+            && Self::is_missing_or_empty(&self.path.join(SOLDEER_DEPENDENCIES_DIR))
+    }
+
+    /// Returns true if there's nothing at the path, or it's an empty directory.
+    fn is_missing_or_empty(path: &Path) -> bool {
+        fs::read_dir(path).map_or(!path.exists(), |mut entries| entries.next().is_none())
+    }
+
+    /// Returns true if the value is an exact solc version such as `0.8.19`, as opposed to a
+    /// version requirement or a path to a compiler binary.
+    fn is_exact_solc_version(value: &str) -> bool {
+        let parts: Vec<&str> = value.split('.').collect();
+        parts.len() == 3
+            && parts.iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+    }
 }
 
 impl Framework for Foundry {
@@ -76,7 +293,7 @@ impl Framework for Foundry {
         if !Self::is_supported(path) {
             return Err("Not a foundry project.".into())
         }
-        Ok(Self { path: path.to_path_buf() })
+        Ok(Self::from_artifacts_root(path))
     }
 
     fn is_supported(path: &Path) -> bool {
@@ -84,66 +301,149 @@ impl Framework for Foundry {
         file.exists() && file.is_file()
     }
 
-    fn build_commands(&self, hint: Option<String>) -> Result<Vec<Command>, Box<dyn Error>> {
+    fn build_commands(
+        &self,
+        hint: Option<String>,
+    ) -> Result<Vec<(Command, Self)>, BuildConfigError> {
         // For forge projects, the hint is expected to be the profile name.
         let maybe_profile_name = hint;
 
         let config_file = self.path.join("foundry.toml");
         let mut profile_names = Self::foundry_profiles(&config_file)?;
-        println!("  Found profiles: {:?}", profile_names);
+        let via_ir_profiles = Self::via_ir_profiles(&config_file);
+        tracing::debug!(profiles = ?profile_names, "Found Foundry profiles");
 
         // If we are given a profile name, only build that profile, otherwise build all profiles.
         if let Some(profile_name) = maybe_profile_name {
             if !profile_names.contains(&profile_name) {
-                return Err(format!("Profile '{}' not found in foundry.toml.", profile_name).into())
+                let msg = format!(
+                    "Profile '{}' not found in foundry.toml. Available profiles: {}.",
+                    profile_name,
+                    profile_names.join(", ")
+                );
+                return Err(BuildConfigError::UnknownProfile(msg))
             }
             profile_names = vec![profile_name];
-        } else {
-            return Err("Currently a profile name must be provided for forge projects.".into())
         }
 
-        // Generate the build commands.
+        // Generate the build commands. Each command runs in the project directory, rather than
+        // relying on the process-wide working directory which is shared by concurrent requests.
+        // Profiles are built concurrently, so each writes its artifacts, build info, and cache to
+        // its own directories. These are numbered rather than named after the profile since
+        // profile names come from the repository.
         let commands = profile_names
             .into_iter()
-            .map(|profile_name| {
+            .enumerate()
+            .map(|(index, profile_name)| {
+                let out_dir = self.path.join(BUILD_OUT_DIR).join(index.to_string());
+                let build_info_dir = self.path.join(BUILD_INFO_DIR).join(index.to_string());
+                let cache_path = self
+                    .path
+                    .join(BUILD_CACHE_DIR)
+                    .join(index.to_string())
+                    .join("solidity-files-cache.json");
                 let mut command = Command::new("forge");
                 command
+                    .current_dir(&self.path)
                     .arg("build")
                     .arg("--skip")
                     .arg("test")
                     .arg("script")
+                    .arg("--out")
+                    .arg(&out_dir)
+                    .arg("--cache-path")
+                    .arg(cache_path)
                     .arg("--build-info")
                     .arg("--build-info-path")
-                    .arg("build_info")
-                    .env("FOUNDRY_PROFILE", profile_name);
-                command
+                    .arg(&build_info_dir)
+                    .env("FOUNDRY_PROFILE", &profile_name);
+                // Forge reads this from the profile too, but passing it explicitly makes the
+                // pipeline visible in the build command returned to the caller.
+                if via_ir_profiles.contains(&profile_name) {
+                    command.arg("--via-ir");
+                }
+                let project = Self {
+                    path: self.path.clone(),
+                    out_dir: Some(out_dir),
+                    build_info_dir,
+                    profile: Some(profile_name),
+                };
+                (command, project)
             })
-            .collect::<Vec<Command>>();
+            .collect::<Vec<_>>();
         Ok(commands)
     }
 
-    fn get_artifacts(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
-        let mut artifacts = Vec::new();
+    fn pinned_solc_versions(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let contents = fs::read_to_string(self.path.join("foundry.toml"))?;
+        let data = match contents.parse::<toml::Value>() {
+            Ok(data) => data,
+            Err(_) => return Err("Unable to parse foundry.toml file".into()),
+        };
 
-        let out_dirs =
-            WalkDir::new(&self.path).min_depth(1).max_depth(1).into_iter().filter_entry(|entry| {
-                entry.file_type().is_dir()
-                    && entry.file_name().to_string_lossy().to_lowercase().contains("out")
-            });
-
-        for entry in out_dirs.into_iter().filter_map(Result::ok) {
-            if entry.path().is_dir() {
-                for inner_entry in WalkDir::new(entry.path()).into_iter().filter_map(Result::ok) {
-                    if inner_entry.file_type().is_file()
-                        && inner_entry.path().extension().map_or(false, |ext| ext == "json")
-                    {
-                        artifacts.push(inner_entry.into_path());
+        // Every profile is checked, since any of them may be built. Profiles that don't pin a
+        // version let forge auto-detect one from the pragmas, and forge installs those itself.
+        let mut versions = Vec::new();
+        if let Some(profiles_table) = data.get("profile").and_then(|v| v.as_table()) {
+            for profile in profiles_table.values() {
+                let version = profile
+                    .get("solc_version")
+                    .or_else(|| profile.get("solc"))
+                    .and_then(|v| v.as_str())
+                    .filter(|v| Self::is_exact_solc_version(v));
+                if let Some(version) = version {
+                    if !versions.iter().any(|v| v == version) {
+                        versions.push(version.to_string());
                     }
                 }
             }
         }
+        Ok(versions)
+    }
+
+    fn dependency_install_commands(&self) -> Vec<Command> {
+        let mut commands = Vec::new();
+        let missing_submodules = self.missing_submodules();
+        if !missing_submodules.is_empty() {
+            tracing::debug!(submodules = ?missing_submodules, "Found missing submodules");
+            // Without arguments, forge installs the libraries listed in `.gitmodules`.
+            let mut command = Command::new("forge");
+            command.current_dir(&self.path).arg("install");
+            commands.push(command);
+        }
+        if self.missing_soldeer_dependencies() {
+            tracing::debug!("Found missing Soldeer dependencies");
+            let mut command = Command::new("forge");
+            command.current_dir(&self.path).arg("soldeer").arg("install");
+            commands.push(command);
+        }
+        commands
+    }
+
+    fn profile(&self) -> Option<String> {
+        self.profile.clone()
+    }
+
+    fn build_info_dir(&self) -> PathBuf {
+        self.build_info_dir.clone()
+    }
+
+    fn get_artifacts(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut artifacts = Vec::new();
+
+        let exclusions = ArtifactExclusions::from_env();
+
+        // Artifacts from a single build are all in its output directory.
+        if let Some(out_dir) = &self.out_dir {
+            Self::collect_artifacts(out_dir, &mut artifacts);
+            return Self::filter_artifacts(artifacts, &exclusions)
+        }
+
+        for out_dir in self.configured_out_dirs() {
+            Self::collect_artifacts(&self.path.join(out_dir), &mut artifacts);
+        }
 
-        Ok(Self::filter_artifacts(artifacts))
+        Self::filter_artifacts(artifacts, &exclusions)
     }
 
     fn structure_found_creation_code(
@@ -168,7 +468,8 @@ impl Framework for Foundry {
             (leading_code.to_vec().into(), metadata)
         };
 
-        Ok(FoundCreationBytecode { raw_code, leading_code, metadata })
+        let (link_references, _) = Self::get_artifact_link_references(artifact)?;
+        Ok(FoundCreationBytecode { raw_code, leading_code, metadata, link_references })
     }
 
     fn structure_expected_creation_code(
@@ -191,14 +492,8 @@ impl Framework for Foundry {
         let metadata_hash: Option<Bytes> = if let (Some(start_index), Some(end_index)) =
             (found.metadata.start_index, found.metadata.end_index)
         {
-            // There may be cases where the found bytecode has a full metadata hash, but the
-            // expected bytecode only has CBOR or none. In those cases the `end_index` will be
-            // longer than the length of the expected bytecode, and this line will panic since
-            // `end_index` will be out of bounds. To avoid this while still enabling verification
-            // for different metadata hashes, the end index is `min(end_index, expected.len())`.
-            // let end_index = std::cmp::min(end_index, expected.len());
-            let adjusted_end_index = std::cmp::min(end_index, expected.len());
-            Some(expected[start_index..adjusted_end_index].to_vec().into())
+            let end_index = expected_metadata_end(expected, start_index, end_index);
+            Some(expected[start_index..end_index].to_vec().into())
         } else {
             None
         };
@@ -213,10 +508,12 @@ impl Framework for Foundry {
             None
         };
 
+        let end_index =
+            found.metadata.start_index.zip(metadata_hash.as_ref()).map(|(i, hash)| i + hash.len());
         let metadata = MetadataInfo {
             hash: metadata_hash,
             start_index: found.metadata.start_index,
-            end_index: found.metadata.end_index,
+            end_index,
         };
 
         Ok(ExpectedCreationBytecode {
@@ -249,7 +546,14 @@ impl Framework for Foundry {
             (leading_code.to_vec().into(), metadata)
         };
 
-        Ok(FoundDeployedBytecode { raw_code, leading_code, metadata, immutable_references })
+        let (_, link_references) = Self::get_artifact_link_references(artifact)?;
+        Ok(FoundDeployedBytecode {
+            raw_code,
+            leading_code,
+            metadata,
+            immutable_references,
+            link_references,
+        })
     }
 
     fn structure_expected_deployed_code(
@@ -271,22 +575,18 @@ impl Framework for Foundry {
         let metadata_hash: Option<Bytes> = if let (Some(start_index), Some(end_index)) =
             (found.metadata.start_index, found.metadata.end_index)
         {
-            // There may be cases where the found bytecode has a full metadata hash, but the
-            // expected bytecode only has CBOR or none. In those cases the `end_index` will be
-            // longer than the length of the expected bytecode, and this line will panic since
-            // `end_index` will be out of bounds. To avoid this while still enabling verification
-            // for different metadata hashes, the end index is `min(end_index, expected.len())`.
-            // let end_index = std::cmp::min(end_index, expected.len());
-            let adjusted_end_index = std::cmp::min(end_index, expected.len());
-            Some(expected[start_index..adjusted_end_index].to_vec().into())
+            let end_index = expected_metadata_end(expected, start_index, end_index);
+            Some(expected[start_index..end_index].to_vec().into())
         } else {
             None
         };
 
+        let end_index =
+            found.metadata.start_index.zip(metadata_hash.as_ref()).map(|(i, hash)| i + hash.len());
         let metadata = MetadataInfo {
             hash: metadata_hash,
             start_index: found.metadata.start_index,
-            end_index: found.metadata.end_index,
+            end_index,
         };
 
         Ok(ExpectedDeployedBytecode {
@@ -298,8 +598,7 @@ impl Framework for Foundry {
     }
 
     fn get_artifact_abi(artifact: &Path) -> Result<LosslessAbi, Box<dyn Error>> {
-        let file_content = fs::read_to_string(artifact)?;
-        let json_content: serde_json::Value = serde_json::from_str(&file_content)?;
+        let json_content: serde_json::Value = read_artifact_json(artifact)?;
         let abi_value = json_content
             .get("abi")
             .ok_or(format!("Missing 'bytecode' field in artifact JSON: {}", artifact.display()))?;
@@ -307,42 +606,31 @@ impl Framework for Foundry {
     }
 
     fn get_artifact_creation_code(artifact: &Path) -> Result<Bytes, Box<dyn Error>> {
-        let file_content = fs::read_to_string(artifact)?;
-        let json_content: serde_json::Value = serde_json::from_str(&file_content)?;
-        let creation_code_value = json_content
-            .get("bytecode")
-            .ok_or_else(|| {
-                format!("Missing 'bytecode' field in artifact JSON: {}", artifact.display())
-            })?
-            .get("object")
-            .ok_or_else(|| {
-                format!("Missing 'object' field in bytecode JSON: {}", artifact.display())
-            })?;
-        let creation_code: Bytes = serde_json::from_value(creation_code_value.clone())?;
-        Ok(creation_code)
+        let json_content: serde_json::Value = read_artifact_json(artifact)?;
+        let creation_code_value = json_content.get("bytecode").ok_or_else(|| {
+            format!("Missing 'bytecode' field in artifact JSON: {}", artifact.display())
+        })?;
+        let creation_code: CompactBytecode = serde_json::from_value(creation_code_value.clone())?;
+        bytecode_object_to_bytes(&creation_code.object, &creation_code.link_references)
     }
 
     fn get_artifact_deployed_code(
         artifact: &Path,
     ) -> Result<(Bytes, ImmutableReferences), Box<dyn Error>> {
-        let file_content = fs::read_to_string(artifact)?;
-        let artifact: ConfigurableContractArtifact = serde_json::from_str(&file_content)?;
-
-        let deployed_code_object = artifact.deployed_bytecode.ok_or("No deployedBytecode found")?;
-        let deployed_code =
-            match deployed_code_object.bytecode.ok_or("No bytecode object found")?.object {
-                BytecodeObject::Bytecode(bytes) => bytes,
-                BytecodeObject::Unlinked(_) => {
-                    return Err("Linked libraries not yet supported".into())
-                }
-            };
+        // Only the deployed bytecode is parsed, so an AST we can't model doesn't break comparison.
+        let json_content: serde_json::Value = read_artifact_json(artifact)?;
+        let deployed_code_value =
+            json_content.get("deployedBytecode").ok_or("No deployedBytecode found")?;
+        let deployed_code_object: CompactDeployedBytecode =
+            serde_json::from_value(deployed_code_value.clone())?;
+        let bytecode = deployed_code_object.bytecode.ok_or("No bytecode object found")?;
+        let deployed_code = bytecode_object_to_bytes(&bytecode.object, &bytecode.link_references)?;
         let immutable_references = deployed_code_object.immutable_references;
         Ok((deployed_code, immutable_references))
     }
 
     fn get_artifact_metadata_settings(artifact: &Path) -> Result<SettingsMetadata, Box<dyn Error>> {
-        let file_content = fs::read_to_string(artifact)?;
-        let json_content: serde_json::Value = serde_json::from_str(&file_content)?;
+        let json_content: serde_json::Value = read_artifact_json(artifact)?;
         let settings_value = json_content
             .get("metadata")
             .ok_or_else(|| {
@@ -364,9 +652,23 @@ impl Framework for Foundry {
     }
 }
 
+/// Returns the index where the metadata that starts at `start_index` ends in the expected code,
+/// given the `end_index` of the found metadata. The on-chain metadata may be a different length
+/// than the found metadata, e.g. CBOR only instead of a full IPFS hash, so its end is read from
+/// its own CBOR encoding when that parses, which leaves exactly the constructor arguments after
+/// it. Otherwise this falls back to `min(end_index, expected.len())`, since `end_index` may be out
+/// of bounds when the expected code is shorter.
+fn expected_metadata_end(expected: &Bytes, start_index: usize, end_index: usize) -> usize {
+    match expected.get(start_index..).and_then(metadata_length_at) {
+        Some(length) => start_index + length,
+        None => std::cmp::min(end_index, expected.len()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::frameworks::framework::read_artifact_json_with_limit;
     use ethers_solc::artifacts::{BytecodeHash, SettingsMetadata};
     use serde_json::json;
     use std::{error::Error, fs::File, io::Write, path::PathBuf, str::FromStr};
@@ -384,6 +686,340 @@ mod tests {
         Ok(path)
     }
 
+    #[test]
+    fn test_build_commands_run_in_project_dir() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\nsrc = 'src'\n")?;
+
+        let foundry = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+        let commands = foundry.build_commands(Some("default".to_string()))?;
+
+        assert_eq!(commands.len(), 1);
+        for (command, _) in commands {
+            assert_eq!(command.get_current_dir(), Some(project_dir.path()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_commands_validate_foundry_toml() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        let foundry = Foundry::from_artifacts_root(project_dir.path());
+
+        // A missing config is reported as missing rather than invalid.
+        let err = foundry.build_commands(None).unwrap_err();
+        assert!(matches!(err, BuildConfigError::Missing(_)), "{err}");
+
+        // A config that isn't valid TOML is invalid.
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "[profile.default
+src = 'src'
+",
+        )?;
+        let err = foundry.build_commands(None).unwrap_err();
+        assert!(matches!(err, BuildConfigError::Invalid(_)), "{err}");
+
+        // So is valid TOML whose profiles aren't tables.
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "profile = 'default'
+",
+        )?;
+        let err = foundry.build_commands(None).unwrap_err();
+        assert!(matches!(err, BuildConfigError::Invalid(_)), "{err}");
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "[profile]
+default = 1
+",
+        )?;
+        let err = foundry.build_commands(None).unwrap_err();
+        assert!(matches!(err, BuildConfigError::Invalid(_)), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_commands_use_distinct_output_dirs() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        let config = "[profile.default]\nsrc = 'src'\n[profile.optimized]\noptimizer = true\n";
+        fs::write(project_dir.path().join("foundry.toml"), config)?;
+        let foundry = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+
+        // Without a hint every profile is built, each into its own directories.
+        let builds = foundry.build_commands(None)?;
+        assert_eq!(builds.len(), 2);
+        let (_, first) = &builds[0];
+        let (_, second) = &builds[1];
+        assert_ne!(first.out_dir, second.out_dir);
+        assert_ne!(first.build_info_dir(), second.build_info_dir());
+
+        // Each build only sees its own artifacts.
+        let artifact = json!({ "metadata": { "sources": { "src/Counter.sol": {} } } });
+        for (_, project) in &builds {
+            let dir = project.out_dir.as_ref().ok_or("Missing out dir")?.join("Counter.sol");
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join("Counter.json"), artifact.to_string())?;
+        }
+        for (_, project) in &builds {
+            let artifacts = project.get_artifacts()?;
+            assert_eq!(artifacts.len(), 1);
+            assert!(artifacts[0].starts_with(project.out_dir.as_ref().ok_or("Missing out dir")?));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifacts_in_nested_out_dir() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\nsrc = 'src'\n")?;
+        let out_dir = project_dir.path().join("out");
+        let artifact = json!({ "metadata": { "sources": { "src/Tokens.sol": {} } } });
+
+        // `Tokens.sol` defines two contracts, which get an artifact each in the file's directory.
+        let tokens_dir = out_dir.join("Tokens.sol");
+        fs::create_dir_all(&tokens_dir)?;
+        fs::write(tokens_dir.join("Token.json"), artifact.to_string())?;
+        fs::write(tokens_dir.join("Vault.json"), artifact.to_string())?;
+        // Files that aren't artifacts are skipped, including build info nested in the output.
+        fs::write(tokens_dir.join("notes.txt"), "not an artifact")?;
+        let build_info_dir = out_dir.join("build-info");
+        fs::create_dir_all(&build_info_dir)?;
+        let build_info =
+            json!({ "output": {}, "metadata": { "sources": { "src/Tokens.sol": {} } } });
+        fs::write(build_info_dir.join("abc123.json"), build_info.to_string())?;
+
+        let foundry = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+        let artifacts = foundry.get_artifacts()?;
+        let relative: Vec<_> = artifacts
+            .iter()
+            .map(|artifact| artifact.strip_prefix(&out_dir).map(Path::to_path_buf))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(
+            relative,
+            vec![PathBuf::from("Tokens.sol/Token.json"), PathBuf::from("Tokens.sol/Vault.json")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifacts_skips_tests_and_scripts() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\nsrc = 'src'\n")?;
+        let out_dir = project_dir.path().join("out");
+        let write_artifact = |file: &str, name: &str, source_path: Option<&str>| {
+            let mut artifact = json!({ "metadata": { "sources": { "src/Counter.sol": {} } } });
+            if let Some(source_path) = source_path {
+                artifact["metadata"]["settings"]["compilationTarget"] =
+                    json!({ source_path: name });
+            }
+            let dir = out_dir.join(file);
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join(format!("{name}.json")), artifact.to_string())
+        };
+        write_artifact("Counter.sol", "Counter", Some("src/Counter.sol"))?;
+        write_artifact("Counter.t.sol", "CounterTest", Some("test/Counter.t.sol"))?;
+        write_artifact("Deploy.s.sol", "Deploy", Some("script/Deploy.s.sol"))?;
+        write_artifact("Helpers.sol", "Helpers", Some("test/utils/Helpers.sol"))?;
+        // Without a compilation target, the source file is known from the directory name.
+        write_artifact("Token.t.sol", "TokenTest", None)?;
+
+        let foundry = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+        let artifacts = foundry.get_artifacts()?;
+        assert_eq!(artifacts, vec![out_dir.join("Counter.sol/Counter.json")]);
+
+        // The exclusions are configurable.
+        let mut all_artifacts = Vec::new();
+        Foundry::collect_artifacts(&out_dir, &mut all_artifacts);
+        let exclusions = ArtifactExclusions::parse("*.s.sol, test/utils/");
+        let artifacts = Foundry::filter_artifacts(all_artifacts.clone(), &exclusions)?;
+        let names: Vec<_> = artifacts.iter().filter_map(|a| a.file_stem()).collect();
+        assert_eq!(names, vec!["Counter", "CounterTest", "TokenTest"]);
+        let artifacts = Foundry::filter_artifacts(all_artifacts, &ArtifactExclusions::none())?;
+        assert_eq!(artifacts.len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_oversized_artifact() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\nsrc = 'src'\n")?;
+        let dir = project_dir.path().join("out/Counter.sol");
+        fs::create_dir_all(&dir)?;
+        let artifact = dir.join("Counter.json");
+        let padding = "0".repeat(4096);
+        let content = json!({
+            "metadata": { "sources": { "src/Counter.sol": {} } },
+            "bytecode": { "object": format!("0x{padding}") },
+        });
+        fs::write(&artifact, content.to_string())?;
+
+        // Artifacts under the limit are read as usual.
+        let json: serde_json::Value = read_artifact_json_with_limit(&artifact, 1 << 20)?;
+        assert_eq!(json, content);
+
+        // Artifacts over the limit are rejected without being parsed.
+        let err = read_artifact_json_with_limit::<serde_json::Value>(&artifact, 1024)
+            .err()
+            .ok_or("Oversized artifact was parsed")?;
+        let err = err.downcast_ref::<ArtifactTooLarge>().ok_or("Wrong error type")?;
+        assert_eq!(err, &ArtifactTooLarge { path: artifact, limit: 1024 });
+
+        // Deeply nested JSON fails to parse instead of overflowing the stack.
+        let nested = project_dir.path().join("Nested.json");
+        fs::write(&nested, format!("{}{}", "[".repeat(100_000), "]".repeat(100_000)))?;
+        let err = read_artifact_json_with_limit::<serde_json::Value>(&nested, 1 << 20)
+            .err()
+            .ok_or("Deeply nested artifact was parsed")?;
+        assert!(err.is::<serde_json::Error>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifacts_in_configured_out_dir() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        let config = "[profile.default]\nsrc = 'src'\nout = 'artifacts'\n\
+                      [profile.escape]\nout = '../elsewhere'\n";
+        fs::write(project_dir.path().join("foundry.toml"), config)?;
+        let artifact = json!({ "metadata": { "sources": { "src/Counter.sol": {} } } });
+        for out_dir in ["artifacts", "out"] {
+            let dir = project_dir.path().join(out_dir).join("Counter.sol");
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join("Counter.json"), artifact.to_string())?;
+        }
+
+        // Artifacts are read from the configured directory, rather than the default one, and
+        // directories outside the project are ignored.
+        let foundry = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+        assert_eq!(foundry.configured_out_dirs(), vec![PathBuf::from("artifacts")]);
+        let artifacts = foundry.get_artifacts()?;
+        assert_eq!(artifacts, vec![project_dir.path().join("artifacts/Counter.sol/Counter.json")]);
+
+        // Without `out`, the default directory is used.
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\nsrc = 'src'\n")?;
+        let artifacts = foundry.get_artifacts()?;
+        assert_eq!(artifacts, vec![project_dir.path().join("out/Counter.sol/Counter.json")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_commands_select_profile() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        let config = "[profile.default]\noptimizer = true\noptimizer_runs = 200\n\
+                      [profile.optimized]\noptimizer = true\noptimizer_runs = 10000\n";
+        fs::write(project_dir.path().join("foundry.toml"), config)?;
+        let foundry = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+
+        /// Returns the value of `FOUNDRY_PROFILE` the command runs with.
+        fn profile_env(command: &Command) -> Option<String> {
+            command
+                .get_envs()
+                .find(|(key, _)| *key == "FOUNDRY_PROFILE")
+                .and_then(|(_, value)| value)
+                .map(|value| value.to_string_lossy().to_string())
+        }
+
+        // Without a profile, each profile is built with its own command.
+        let builds = foundry.build_commands(None)?;
+        let mut profiles: Vec<_> = builds
+            .iter()
+            .map(|(command, project)| {
+                assert_eq!(profile_env(command), project.profile());
+                project.profile()
+            })
+            .collect();
+        profiles.sort();
+        assert_eq!(profiles, vec![Some("default".to_string()), Some("optimized".to_string())]);
+
+        // A selected profile is the only one built.
+        let builds = foundry.build_commands(Some("optimized".to_string()))?;
+        assert_eq!(builds.len(), 1);
+        assert_eq!(profile_env(&builds[0].0).as_deref(), Some("optimized"));
+        assert_eq!(builds[0].1.profile().as_deref(), Some("optimized"));
+
+        // Unknown profiles are rejected, listing the profiles that exist.
+        let err = foundry.build_commands(Some("missing".to_string())).unwrap_err().to_string();
+        assert!(err.contains("Available profiles: default, optimized"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_commands_use_via_ir() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        let config = "[profile.default]\nvia_ir = true\n[profile.inherited]\noptimizer = true\n\
+                      [profile.legacy]\nvia_ir = false\n";
+        fs::write(project_dir.path().join("foundry.toml"), config)?;
+        let foundry = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+
+        // Profiles that don't set `via_ir` inherit it from the default profile.
+        for (command, project) in foundry.build_commands(None)? {
+            let uses_via_ir = command.get_args().any(|arg| arg == "--via-ir");
+            assert_eq!(uses_via_ir, project.profile() != Some("legacy".to_string()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_dependency_install_commands() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\nsrc = 'src'\n")?;
+        let foundry = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+        let args = |foundry: &Foundry| {
+            foundry
+                .dependency_install_commands()
+                .iter()
+                .map(|command| {
+                    let args = command.get_args().map(|arg| arg.to_string_lossy().to_string());
+                    args.collect::<Vec<_>>().join(" ")
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // A project without dependencies has nothing to install.
+        assert!(args(&foundry).is_empty());
+
+        // A submodule that wasn't cloned is installed with forge, until its directory has files.
+        fs::write(
+            project_dir.path().join(".gitmodules"),
+            "[submodule \"lib/forge-std\"]\n\tpath = lib/forge-std\n\turl = \
+             https://github.com/foundry-rs/forge-std\n",
+        )?;
+        assert_eq!(args(&foundry), vec!["install"]);
+        fs::create_dir_all(project_dir.path().join("lib").join("forge-std"))?;
+        assert_eq!(args(&foundry), vec!["install"]);
+        fs::write(project_dir.path().join("lib").join("forge-std").join("foundry.toml"), "")?;
+        assert!(args(&foundry).is_empty());
+
+        // Soldeer dependencies are installed with `forge soldeer install` until they're present.
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "[profile.default]\nsrc = 'src'\n\n[dependencies]\nforge-std = '1.9.2'\n",
+        )?;
+        assert_eq!(args(&foundry), vec!["soldeer install"]);
+        let installed = project_dir.path().join(SOLDEER_DEPENDENCIES_DIR).join("forge-std-1.9.2");
+        fs::create_dir_all(&installed)?;
+        assert!(args(&foundry).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pinned_solc_versions() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        let config = "[profile.default]\nsolc_version = '0.8.19'\n\
+                      [profile.legacy]\nsolc = '0.7.6'\n\
+                      [profile.local]\nsolc = '/usr/bin/solc'\n\
+                      [profile.same]\nsolc_version = '0.8.19'\n\
+                      [profile.auto]\noptimizer = true\n";
+        fs::write(project_dir.path().join("foundry.toml"), config)?;
+        let foundry = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+
+        // Paths to compiler binaries and duplicates are skipped.
+        let mut versions = foundry.pinned_solc_versions()?;
+        versions.sort();
+        assert_eq!(versions, vec!["0.7.6", "0.8.19"]);
+        Ok(())
+    }
+
     #[test]
     fn test_structure_found_creation_code() -> Result<(), Box<dyn Error>> {
         struct TestCase {
@@ -402,6 +1038,7 @@ mod tests {
                     raw_code: Bytes::from_str("0x1234")?,
                     leading_code: Bytes::from_str("0x1234")?,
                     metadata: MetadataInfo::default(),
+                    link_references: LinkReferences::new(),
                 },
             },
             // Test case 2: BytecodeHash::Ipfs and appendCBOR = true
@@ -418,11 +1055,12 @@ mod tests {
                         start_index: Some(6),
                         end_index: Some(10),
                     },
+                    link_references: LinkReferences::new(),
                 },
             },
         ];
 
-        let foundry = Foundry { path: PathBuf::new() };
+        let foundry = Foundry::from_artifacts_root(Path::new(""));
         let artifact_path = tempfile::NamedTempFile::new()?;
         for test_case in test_cases {
             let artifact = create_test_artifact(&artifact_path, &test_case.content)?;
@@ -435,7 +1073,7 @@ mod tests {
 
     #[test]
     fn test_structure_expected_creation_code() -> Result<(), Box<dyn Error>> {
-        let foundry = Foundry { path: PathBuf::new() };
+        let foundry = Foundry::from_artifacts_root(Path::new(""));
 
         // First test the case where expected code is too short to structure.
         struct FailureTestCase {
@@ -450,6 +1088,7 @@ mod tests {
                 raw_code: Bytes::from_str("0x123456")?,
                 leading_code: Bytes::from_str("0x123456")?,
                 metadata: MetadataInfo::default(),
+                link_references: LinkReferences::new(),
             },
             expected: Bytes::from_str("0x1234")?,
         }];
@@ -478,6 +1117,7 @@ mod tests {
                     raw_code: Bytes::from_str("0x1234")?,
                     leading_code: Bytes::from_str("0x1234")?,
                     metadata: MetadataInfo::default(),
+                    link_references: LinkReferences::new(),
                 },
                 expected: Bytes::from_str("0x1234")?,
                 expected_output: ExpectedCreationBytecode {
@@ -497,6 +1137,7 @@ mod tests {
                         start_index: Some(6),
                         end_index: Some(10),
                     },
+                    link_references: LinkReferences::new(),
                 },
                 expected: Bytes::from_str("0x1234567890abcdef0002")?,
                 expected_output: ExpectedCreationBytecode {
@@ -521,6 +1162,7 @@ mod tests {
                         start_index: Some(6),
                         end_index: Some(10),
                     },
+                    link_references: LinkReferences::new(),
                 },
                 expected: Bytes::from_str("0x1234567890abffff0002")?,
                 expected_output: ExpectedCreationBytecode {
@@ -540,6 +1182,7 @@ mod tests {
                     raw_code: Bytes::from_str("0x1234")?,
                     leading_code: Bytes::from_str("0x1234")?,
                     metadata: MetadataInfo::default(),
+                    link_references: LinkReferences::new(),
                 },
                 expected: Bytes::from_str("0x12345678")?,
                 expected_output: ExpectedCreationBytecode {
@@ -559,6 +1202,7 @@ mod tests {
                         start_index: Some(6),
                         end_index: Some(10),
                     },
+                    link_references: LinkReferences::new(),
                 },
                 expected: Bytes::from_str("0x1234567890abffff0002aaaaaa")?,
                 expected_output: ExpectedCreationBytecode {
@@ -572,6 +1216,34 @@ mod tests {
                     constructor_args: Some(Bytes::from_str("0xaaaaaa")?),
                 },
             },
+            TestCase {
+                description: "Test case 6: Shorter CBOR metadata, constructor args.".to_string(),
+                found: FoundCreationBytecode {
+                    raw_code: Bytes::from_str("0x6080a264736f6c6343000813646970667342abcd0012")?,
+                    leading_code: Bytes::from_str("0x6080")?,
+                    metadata: MetadataInfo {
+                        hash: Some(Bytes::from_str("0xa264736f6c6343000813646970667342abcd0012")?),
+                        start_index: Some(2),
+                        end_index: Some(22),
+                    },
+                    link_references: LinkReferences::new(),
+                },
+                expected: Bytes::from_str(
+                    "0x6080a164736f6c6343000813000aaaaaaaaaaaaaaaaaaaaaaaaa",
+                )?,
+                expected_output: ExpectedCreationBytecode {
+                    raw_code: Bytes::from_str(
+                        "0x6080a164736f6c6343000813000aaaaaaaaaaaaaaaaaaaaaaaaa",
+                    )?,
+                    leading_code: Bytes::from_str("0x6080")?,
+                    metadata: MetadataInfo {
+                        hash: Some(Bytes::from_str("0xa164736f6c6343000813000a")?),
+                        start_index: Some(2),
+                        end_index: Some(14),
+                    },
+                    constructor_args: Some(Bytes::from_str("0xaaaaaaaaaaaaaaaaaaaaaaaa")?),
+                },
+            },
         ];
 
         for test_case in test_cases {
@@ -639,6 +1311,16 @@ mod tests {
                 content: json!({ "bytecode": { "object": "" }}),
                 expected: Bytes::from_str("")?,
             },
+            // Test case 3: Creation code links a library, so the placeholder is zeroed out.
+            TestCase {
+                content: json!({ "bytecode": {
+                    "object": "0x6073__$1d0a7da4a6f5e8a3b9eb3e3b7ef9e18bf1$__3014",
+                    "linkReferences": {
+                        "src/MathLib.sol": { "MathLib": [{ "start": 2, "length": 20 }] }
+                    }
+                }}),
+                expected: Bytes::from_str("0x607300000000000000000000000000000000000000003014")?,
+            },
         ];
 
         for test_case in test_cases {
@@ -5,13 +5,170 @@ use crate::bytecode::{
 };
 use ethers::types::Bytes;
 use ethers_solc::artifacts::{LosslessAbi, SettingsMetadata};
+use serde::de::DeserializeOwned;
 use std::{
     error::Error,
+    fmt, fs,
+    io::{BufReader, Read},
     path::{Path, PathBuf},
     process::Command,
     result::Result,
 };
 
+/// Returned when a project's build config can't be used to build it, before any build is run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildConfigError {
+    /// The config file doesn't exist.
+    Missing(String),
+    /// The config file exists but can't be read or parsed, or has the wrong structure.
+    Invalid(String),
+    /// The config is valid, but doesn't define the requested profile.
+    UnknownProfile(String),
+}
+
+impl fmt::Display for BuildConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildConfigError::Missing(msg)
+            | BuildConfigError::Invalid(msg)
+            | BuildConfigError::UnknownProfile(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl Error for BuildConfigError {}
+
+/// Environment variable listing the source paths whose artifacts are never compared, separated by
+/// commas.
+pub const EXCLUDED_ARTIFACT_SOURCES_ENV_VAR: &str = "EXCLUDED_ARTIFACT_SOURCES";
+
+/// The source paths excluded by default: Foundry's test and script directories and file suffixes.
+const DEFAULT_EXCLUDED_ARTIFACT_SOURCES: &str = "test/,script/,*.t.sol,*.s.sol";
+
+/// The source paths whose artifacts are skipped, such as tests and scripts. These are never the
+/// deployed contract, so comparing them is wasted work, and they can share a name with the
+/// contract they test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactExclusions {
+    /// The patterns a source path is excluded by. A pattern ending in `/` matches every path in
+    /// that directory, a pattern starting with `*` matches paths ending with the rest, and any
+    /// other pattern matches the path exactly.
+    patterns: Vec<String>,
+}
+
+impl Default for ArtifactExclusions {
+    fn default() -> Self {
+        Self::parse(DEFAULT_EXCLUDED_ARTIFACT_SOURCES)
+    }
+}
+
+impl ArtifactExclusions {
+    /// Reads the exclusions from `EXCLUDED_ARTIFACT_SOURCES`. Unset or empty uses the defaults, and
+    /// `none` excludes nothing.
+    pub fn from_env() -> Self {
+        match std::env::var(EXCLUDED_ARTIFACT_SOURCES_ENV_VAR) {
+            Ok(value) if value.trim() == "none" => Self::none(),
+            Ok(value) if !value.trim().is_empty() => Self::parse(&value),
+            _ => Self::default(),
+        }
+    }
+
+    /// Returns exclusions that don't exclude anything.
+    pub fn none() -> Self {
+        Self { patterns: Vec::new() }
+    }
+
+    /// Parses a comma-separated list of patterns.
+    pub fn parse(patterns: &str) -> Self {
+        let patterns = patterns
+            .split(',')
+            .map(|pattern| pattern.trim().trim_start_matches("./").to_string())
+            .filter(|pattern| !pattern.is_empty())
+            .collect();
+        Self { patterns }
+    }
+
+    /// Returns true if artifacts compiled from the source file at `source_path`, relative to the
+    /// project root, are skipped.
+    pub fn excludes(&self, source_path: &str) -> bool {
+        let source_path = source_path.trim_start_matches("./");
+        self.patterns.iter().any(|pattern| {
+            if pattern.ends_with('/') {
+                source_path.starts_with(pattern.as_str())
+            } else if let Some(suffix) = pattern.strip_prefix('*') {
+                source_path.ends_with(suffix)
+            } else {
+                source_path == pattern
+            }
+        })
+    }
+}
+
+/// Environment variable used to override the maximum size, in bytes, of an artifact or build info
+/// file that will be parsed.
+pub const MAX_ARTIFACT_BYTES_ENV_VAR: &str = "MAX_ARTIFACT_BYTES";
+
+/// Default maximum size of an artifact or build info file. Build info holds every source and
+/// compiler output of a build, so this is generous, but it stops a malicious repository from
+/// exhausting memory with a huge artifact.
+const DEFAULT_MAX_ARTIFACT_BYTES: u64 = 128 * 1024 * 1024;
+
+/// Returned when an artifact or build info file is larger than the maximum size that is parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactTooLarge {
+    /// The path of the artifact.
+    pub path: PathBuf,
+    /// The maximum size of an artifact, in bytes.
+    pub limit: u64,
+}
+
+impl fmt::Display for ArtifactTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Artifact {} is larger than the {} byte limit", self.path.display(), self.limit)
+    }
+}
+
+impl Error for ArtifactTooLarge {}
+
+/// Returns the maximum size of an artifact from `MAX_ARTIFACT_BYTES`, or the default of 128 MiB if
+/// it isn't set to a positive number.
+pub fn max_artifact_bytes() -> u64 {
+    std::env::var(MAX_ARTIFACT_BYTES_ENV_VAR)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_MAX_ARTIFACT_BYTES)
+}
+
+/// Reads and parses the JSON artifact or build info file at `path`, returning an `ArtifactTooLarge`
+/// error if it's larger than `MAX_ARTIFACT_BYTES`. See `read_artifact_json_with_limit`.
+pub fn read_artifact_json<T: DeserializeOwned>(path: &Path) -> Result<T, Box<dyn Error>> {
+    read_artifact_json_with_limit(path, max_artifact_bytes())
+}
+
+/// Reads and parses the JSON file at `path`, returning an `ArtifactTooLarge` error if it's larger
+/// than `limit` bytes. The file is parsed as it's read rather than loaded into a string first, and
+/// reading stops at the limit even if the file grows after its size is checked. Nesting is limited
+/// by `serde_json`'s recursion limit, so deeply nested JSON fails to parse instead of overflowing
+/// the stack.
+pub fn read_artifact_json_with_limit<T: DeserializeOwned>(
+    path: &Path,
+    limit: u64,
+) -> Result<T, Box<dyn Error>> {
+    let too_large = || Box::new(ArtifactTooLarge { path: path.to_path_buf(), limit });
+    let file = fs::File::open(path)?;
+    if file.metadata()?.len() > limit {
+        return Err(too_large())
+    }
+    let mut reader = BufReader::new(file.take(limit));
+    match serde_json::from_reader(&mut reader) {
+        Ok(value) => Ok(value),
+        // The file was cut off at the limit, so it grew after its size was checked.
+        Err(e) if e.is_eof() && reader.get_ref().limit() == 0 => Err(too_large()),
+        Err(e) => Err(Box::new(e)),
+    }
+}
+
 /// Trait for abstracting framework-specific logic, such as how to build the project and parse
 /// compilation artifacts.
 pub trait Framework {
@@ -25,10 +182,42 @@ pub trait Framework {
     /// Verifies this is the expected project type and returns true if so.
     fn is_supported(path: &Path) -> bool;
 
-    /// Returns the build commands to compile the repo.
-    fn build_commands(&self, hint: Option<String>) -> Result<Vec<Command>, Box<dyn Error>>;
+    /// Returns the build commands to compile the repo. Each command writes to its own output
+    /// directories so the commands can run concurrently, and is paired with a view of the project
+    /// that reads only the artifacts and build info generated by that command. The config is
+    /// validated first, so a missing or malformed config is reported before anything is built.
+    fn build_commands(
+        &self,
+        hint: Option<String>,
+    ) -> Result<Vec<(Command, Self)>, BuildConfigError>
+    where
+        Self: Sized;
+
+    /// Returns the exact solc versions pinned by the project's config, which must be installed
+    /// before building. Frameworks that download their own compilers, such as Hardhat, don't need
+    /// to override this.
+    fn pinned_solc_versions(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(Vec::new())
+    }
+
+    /// Returns the commands that install dependencies the project declares but doesn't contain,
+    /// such as libraries that must be fetched before it builds. Empty if nothing is missing.
+    /// Frameworks that install their dependencies as part of the build don't need to override this.
+    fn dependency_install_commands(&self) -> Vec<Command> {
+        Vec::new()
+    }
+
+    /// Returns the name of the profile this view of the project is built with. Frameworks without
+    /// build profiles don't need to override this.
+    fn profile(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns the directory containing the build info files generated by compilation.
+    fn build_info_dir(&self) -> PathBuf;
 
-    /// Returns the artifacts generated by compilation.
+    /// Returns the artifacts generated by compilation, skipping those for sources excluded by
+    /// `ArtifactExclusions`, such as tests and scripts.
     fn get_artifacts(&self) -> Result<Vec<PathBuf>, Box<dyn Error>>;
 
     // -------- Bytecode Structuring --------
@@ -0,0 +1,407 @@
+use super::{
+    foundry::Foundry,
+    framework::{read_artifact_json, ArtifactExclusions, BuildConfigError, Framework},
+};
+
+use crate::bytecode::{
+    ExpectedCreationBytecode, ExpectedDeployedBytecode, FoundCreationBytecode,
+    FoundDeployedBytecode, ImmutableReferences,
+};
+use ethers::types::Bytes;
+use ethers_solc::artifacts::{LosslessAbi, SettingsMetadata};
+use serde_json::{json, Value};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    path::{Component, Path, PathBuf},
+    process::Command,
+    result::Result,
+};
+
+/// The Hardhat config file names we look for, in order of preference.
+const CONFIG_FILES: [&str; 2] = ["hardhat.config.ts", "hardhat.config.js"];
+
+/// Name of the directory, relative to the project root, that Hardhat artifacts are normalized into.
+const NORMALIZED_ARTIFACTS_DIR: &str = "cove_artifacts";
+
+/// Implementation of the `Framework` trait for Hardhat projects.
+///
+/// Hardhat's per-contract artifacts in `artifacts/` don't contain the compiler metadata or AST, so
+/// instead we read the build info files in `artifacts/build-info/`, which contain the full solc
+/// standard JSON output. Each contract in that output is normalized into a Foundry-style artifact,
+/// which lets us reuse Foundry's bytecode structuring and keeps the rest of the pipeline unchanged.
+pub struct Hardhat {
+    /// Path to the project.
+    path: PathBuf,
+    /// Used to structure bytecode from the normalized, Foundry-style artifacts.
+    normalized: Foundry,
+}
+
+impl Hardhat {
+    /// Given a solc standard JSON output, returns a Foundry-style artifact for each contract, along
+    /// with the source path and contract name. Contracts where all sources are dependencies (i.e.
+    /// from `node_modules`) are skipped since they can't be the contract the user is interested in,
+    /// as are contracts from sources that `exclusions` excludes, such as tests and scripts.
+    fn normalize_build_info(
+        build_info: &Value,
+        exclusions: &ArtifactExclusions,
+    ) -> Vec<(String, String, Value)> {
+        let mut artifacts = Vec::new();
+        let output = &build_info["output"];
+        let Some(contracts) = output["contracts"].as_object() else { return artifacts };
+
+        for (source_path, source_contracts) in contracts {
+            if Self::is_dependency(source_path) || exclusions.excludes(source_path) {
+                continue
+            }
+            let Some(source_contracts) = source_contracts.as_object() else { continue };
+            let source = &output["sources"][source_path];
+
+            for (contract_name, contract) in source_contracts {
+                // Hardhat stores the metadata as a stringified JSON object.
+                let metadata = contract["metadata"]
+                    .as_str()
+                    .and_then(|m| serde_json::from_str::<Value>(m).ok())
+                    .unwrap_or(Value::Null);
+
+                let evm = &contract["evm"];
+                let artifact = json!({
+                    "abi": contract["abi"],
+                    "bytecode": {
+                        "object": Self::prefixed_hex(&evm["bytecode"]["object"]),
+                        "sourceMap": evm["bytecode"]["sourceMap"],
+                        "linkReferences": evm["bytecode"]["linkReferences"],
+                    },
+                    "deployedBytecode": {
+                        "object": Self::prefixed_hex(&evm["deployedBytecode"]["object"]),
+                        "sourceMap": evm["deployedBytecode"]["sourceMap"],
+                        "linkReferences": evm["deployedBytecode"]["linkReferences"],
+                        "immutableReferences": evm["deployedBytecode"]["immutableReferences"],
+                    },
+                    "methodIdentifiers": evm["methodIdentifiers"],
+                    "metadata": metadata,
+                    "rawMetadata": contract["metadata"],
+                    "ast": source["ast"],
+                    "id": source["id"],
+                });
+                artifacts.push((source_path.clone(), contract_name.clone(), artifact));
+            }
+        }
+        artifacts
+    }
+
+    /// Returns true if the source path belongs to a dependency rather than the project itself.
+    fn is_dependency(source_path: &str) -> bool {
+        source_path.starts_with('@') || source_path.starts_with("node_modules/")
+    }
+
+    /// Returns the directory, relative to the normalized artifacts directory, that a source's
+    /// artifacts are written to. This mirrors the full source path, so same-named sources in
+    /// different directories don't overwrite each other. Only normal components are kept, so a
+    /// source path can't place artifacts outside that directory.
+    fn artifact_dir(source_path: &str) -> PathBuf {
+        Path::new(source_path)
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => Some(part),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Solc outputs bytecode without a `0x` prefix, but Foundry artifacts include it.
+    fn prefixed_hex(object: &Value) -> Value {
+        match object.as_str() {
+            Some(hex) if !hex.starts_with("0x") => Value::String(format!("0x{hex}")),
+            Some(hex) => Value::String(hex.to_string()),
+            None => Value::String("0x".to_string()),
+        }
+    }
+}
+
+impl Framework for Hardhat {
+    fn new(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if !Self::is_supported(path) {
+            return Err("Not a hardhat project.".into())
+        }
+        let normalized = Foundry::from_artifacts_root(path);
+        Ok(Self { path: path.to_path_buf(), normalized })
+    }
+
+    fn is_supported(path: &Path) -> bool {
+        CONFIG_FILES.iter().any(|name| path.join(name).is_file())
+    }
+
+    fn build_commands(
+        &self,
+        _hint: Option<String>,
+    ) -> Result<Vec<(Command, Self)>, BuildConfigError> {
+        // `--no` stops npx from fetching Hardhat if it isn't installed, and `--force` ensures we
+        // don't reuse a stale cache.
+        let mut command = Command::new("npx");
+        command.current_dir(&self.path).args(["--no", "hardhat", "compile", "--force"]);
+        let project =
+            Self { path: self.path.clone(), normalized: Foundry::from_artifacts_root(&self.path) };
+        Ok(vec![(command, project)])
+    }
+
+    fn dependency_install_commands(&self) -> Vec<Command> {
+        if !self.path.join("package.json").is_file() || self.path.join("node_modules").is_dir() {
+            return Vec::new()
+        }
+        // Packages' lifecycle scripts are arbitrary code, so they aren't run. Hardhat and solc
+        // don't need them to compile.
+        let install = if self.path.join("package-lock.json").is_file() { "ci" } else { "install" };
+        let mut command = Command::new("npm");
+        command.current_dir(&self.path).args([
+            install,
+            "--ignore-scripts",
+            "--no-audit",
+            "--no-fund",
+        ]);
+        vec![command]
+    }
+
+    fn build_info_dir(&self) -> PathBuf {
+        self.path.join("artifacts").join("build-info")
+    }
+
+    fn get_artifacts(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let normalized_dir = self.path.join(NORMALIZED_ARTIFACTS_DIR);
+        let exclusions = ArtifactExclusions::from_env();
+        let mut artifacts = Vec::new();
+        let mut written = HashSet::new();
+
+        for entry in fs::read_dir(self.build_info_dir())?.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.extension().map_or(true, |ext| ext != "json") {
+                continue
+            }
+            let build_info: Value = read_artifact_json(&path)?;
+
+            // Mirror Foundry's `out/<File>.sol/<Contract>.json` layout, keyed by the full source
+            // path. A contract in more than one build info file is only returned once.
+            for (source_path, contract_name, artifact) in
+                Self::normalize_build_info(&build_info, &exclusions)
+            {
+                let artifact_dir = normalized_dir.join(Self::artifact_dir(&source_path));
+                let artifact_path = artifact_dir.join(format!("{contract_name}.json"));
+                if !written.insert(artifact_path.clone()) {
+                    continue
+                }
+                fs::create_dir_all(&artifact_dir)?;
+                fs::write(&artifact_path, artifact.to_string())?;
+                artifacts.push(artifact_path);
+            }
+        }
+
+        Ok(artifacts)
+    }
+
+    fn structure_found_creation_code(
+        &self,
+        artifact: &Path,
+    ) -> Result<FoundCreationBytecode, Box<dyn Error>> {
+        self.normalized.structure_found_creation_code(artifact)
+    }
+
+    fn structure_expected_creation_code(
+        &self,
+        artifact: &Path,
+        found: &FoundCreationBytecode,
+        expected: &Bytes,
+    ) -> Result<ExpectedCreationBytecode, Box<dyn Error>> {
+        self.normalized.structure_expected_creation_code(artifact, found, expected)
+    }
+
+    fn structure_found_deployed_code(
+        &self,
+        artifact: &Path,
+    ) -> Result<FoundDeployedBytecode, Box<dyn Error>> {
+        self.normalized.structure_found_deployed_code(artifact)
+    }
+
+    fn structure_expected_deployed_code(
+        &self,
+        found: &FoundDeployedBytecode,
+        expected: &Bytes,
+    ) -> Result<ExpectedDeployedBytecode, Box<dyn Error>> {
+        self.normalized.structure_expected_deployed_code(found, expected)
+    }
+
+    fn get_artifact_abi(artifact: &Path) -> Result<LosslessAbi, Box<dyn Error>> {
+        Foundry::get_artifact_abi(artifact)
+    }
+
+    fn get_artifact_creation_code(artifact: &Path) -> Result<Bytes, Box<dyn Error>> {
+        Foundry::get_artifact_creation_code(artifact)
+    }
+
+    fn get_artifact_deployed_code(
+        artifact: &Path,
+    ) -> Result<(Bytes, ImmutableReferences), Box<dyn Error>> {
+        Foundry::get_artifact_deployed_code(artifact)
+    }
+
+    fn get_artifact_metadata_settings(artifact: &Path) -> Result<SettingsMetadata, Box<dyn Error>> {
+        Foundry::get_artifact_metadata_settings(artifact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// A trimmed-down Hardhat build info file for a project with one contract and one dependency.
+    fn build_info_fixture() -> Value {
+        let metadata = json!({
+            "compiler": { "version": "0.8.19+commit.7dd6d404" },
+            "language": "Solidity",
+            "output": { "abi": [], "devdoc": {}, "userdoc": {} },
+            "settings": {
+                "compilationTarget": { "contracts/Greeter.sol": "Greeter" },
+                "evmVersion": "paris",
+                "libraries": {},
+                "metadata": { "bytecodeHash": "ipfs" },
+                "optimizer": { "enabled": false, "runs": 200 },
+                "remappings": []
+            },
+            "sources": {
+                "contracts/Greeter.sol": { "keccak256": "0x1234", "urls": [], "license": "MIT" }
+            },
+            "version": 1
+        });
+        json!({
+            "_format": "hh-sol-build-info-1",
+            "id": "abc123",
+            "solcVersion": "0.8.19",
+            "solcLongVersion": "0.8.19+commit.7dd6d404",
+            "input": { "language": "Solidity", "sources": {}, "settings": {} },
+            "output": {
+                "contracts": {
+                    "contracts/Greeter.sol": {
+                        "Greeter": {
+                            "abi": [],
+                            "metadata": metadata.to_string(),
+                            "evm": {
+                                "bytecode": { "object": "6080", "linkReferences": {} },
+                                "deployedBytecode": {
+                                    "object": "6001",
+                                    "linkReferences": {},
+                                    "immutableReferences": {}
+                                },
+                                "methodIdentifiers": {}
+                            }
+                        }
+                    },
+                    "@openzeppelin/contracts/access/Ownable.sol": {
+                        "Ownable": { "abi": [], "evm": {} }
+                    }
+                },
+                "sources": {
+                    "contracts/Greeter.sol": { "id": 0, "ast": { "nodeType": "SourceUnit" } }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_is_supported() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        assert!(!Hardhat::is_supported(project_dir.path()));
+
+        fs::write(project_dir.path().join("hardhat.config.ts"), "export default {};")?;
+        assert!(Hardhat::is_supported(project_dir.path()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifacts() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("hardhat.config.js"), "module.exports = {};")?;
+        let hardhat = Hardhat::new(project_dir.path()).map_err(|e| e.to_string())?;
+
+        fs::create_dir_all(hardhat.build_info_dir())?;
+        fs::write(hardhat.build_info_dir().join("abc123.json"), build_info_fixture().to_string())?;
+
+        // Only the project's contract is returned, not the dependency.
+        let artifacts = hardhat.get_artifacts()?;
+        assert_eq!(artifacts.len(), 1);
+        assert!(artifacts[0].ends_with("cove_artifacts/contracts/Greeter.sol/Greeter.json"));
+
+        // The normalized artifact is in Foundry's format, so the existing parsing works.
+        let creation_code = Hardhat::get_artifact_creation_code(&artifacts[0])?;
+        assert_eq!(creation_code, Bytes::from_str("0x6080")?);
+        let (deployed_code, _) = Hardhat::get_artifact_deployed_code(&artifacts[0])?;
+        assert_eq!(deployed_code, Bytes::from_str("0x6001")?);
+        let settings = Hardhat::get_artifact_metadata_settings(&artifacts[0])?;
+        assert_eq!(settings.bytecode_hash, Some(ethers_solc::artifacts::BytecodeHash::Ipfs));
+
+        let found = hardhat.structure_found_creation_code(&artifacts[0])?;
+        assert_eq!(found.raw_code, Bytes::from_str("0x6080")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifacts_keeps_same_named_sources_apart() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("hardhat.config.js"), "module.exports = {};")?;
+        let hardhat = Hardhat::new(project_dir.path()).map_err(|e| e.to_string())?;
+
+        // A second `Greeter.sol` in another directory, with different code.
+        let mut build_info = build_info_fixture();
+        let mut greeter = build_info["output"]["contracts"]["contracts/Greeter.sol"].clone();
+        greeter["Greeter"]["evm"]["bytecode"]["object"] = json!("6081");
+        build_info["output"]["contracts"]["contracts/v2/Greeter.sol"] = greeter;
+
+        // The same compilation is in two build info files, so each contract is still returned once.
+        fs::create_dir_all(hardhat.build_info_dir())?;
+        fs::write(hardhat.build_info_dir().join("abc123.json"), build_info.to_string())?;
+        fs::write(hardhat.build_info_dir().join("def456.json"), build_info.to_string())?;
+
+        let mut artifacts = hardhat.get_artifacts()?;
+        artifacts.sort();
+        assert_eq!(artifacts.len(), 2, "{artifacts:?}");
+        assert!(artifacts[0].ends_with("cove_artifacts/contracts/Greeter.sol/Greeter.json"));
+        assert!(artifacts[1].ends_with("cove_artifacts/contracts/v2/Greeter.sol/Greeter.json"));
+        assert_eq!(Hardhat::get_artifact_creation_code(&artifacts[0])?, Bytes::from_str("0x6080")?);
+        assert_eq!(Hardhat::get_artifact_creation_code(&artifacts[1])?, Bytes::from_str("0x6081")?);
+
+        // Source paths can't place artifacts outside the normalized artifacts directory.
+        assert_eq!(Hardhat::artifact_dir("../../Greeter.sol"), PathBuf::from("Greeter.sol"));
+        assert_eq!(Hardhat::artifact_dir("/tmp/Greeter.sol"), PathBuf::from("tmp/Greeter.sol"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dependency_install_commands() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("hardhat.config.js"), "module.exports = {};")?;
+        let hardhat = Hardhat::new(project_dir.path()).map_err(|e| e.to_string())?;
+        let args = |command: &Command| -> Vec<String> {
+            command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect()
+        };
+
+        // Nothing to install without a `package.json`.
+        assert!(hardhat.dependency_install_commands().is_empty());
+
+        // Packages are installed without running their lifecycle scripts.
+        fs::write(project_dir.path().join("package.json"), "{}")?;
+        let commands = hardhat.dependency_install_commands();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].get_program(), "npm");
+        assert_eq!(args(&commands[0]), ["install", "--ignore-scripts", "--no-audit", "--no-fund"]);
+
+        // A lockfile is installed from exactly.
+        fs::write(project_dir.path().join("package-lock.json"), "{}")?;
+        let commands = hardhat.dependency_install_commands();
+        assert_eq!(args(&commands[0]), ["ci", "--ignore-scripts", "--no-audit", "--no-fund"]);
+
+        // Nothing to install once the packages are there.
+        fs::create_dir(project_dir.path().join("node_modules"))?;
+        assert!(hardhat.dependency_install_commands().is_empty());
+        Ok(())
+    }
+}
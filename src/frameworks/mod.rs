@@ -3,3 +3,6 @@ pub mod framework;
 
 /// Defines the `Foundry` struct which implements the `Framework` trait.
 pub mod foundry;
+
+/// Defines the `Hardhat` struct which implements the `Framework` trait.
+pub mod hardhat;
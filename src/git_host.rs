@@ -0,0 +1,251 @@
+use reqwest::Url;
+use std::{collections::HashMap, fmt};
+
+/// Environment variable configuring the credentials used to clone from specific git hosts, as a
+/// comma-separated list of `host=token` or `host=username:token` entries.
+pub const GIT_HOST_CREDENTIALS_ENV_VAR: &str = "GIT_HOST_CREDENTIALS";
+
+/// A git hosting provider. The provider determines how repository URLs are normalized and the
+/// username access tokens are sent with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitProvider {
+    /// github.com.
+    GitHub,
+    /// gitlab.com, or a self-hosted instance whose host starts with `gitlab.`.
+    GitLab,
+    /// bitbucket.org.
+    Bitbucket,
+    /// Any other host, such as a self-hosted Gitea instance.
+    Other,
+}
+
+impl GitProvider {
+    /// Infers the provider from the host of a repository URL.
+    pub fn from_host(host: &str) -> Self {
+        let host = host.trim_end_matches('.').to_lowercase();
+        match host.as_str() {
+            "github.com" | "www.github.com" => GitProvider::GitHub,
+            "gitlab.com" | "www.gitlab.com" => GitProvider::GitLab,
+            "bitbucket.org" | "www.bitbucket.org" => GitProvider::Bitbucket,
+            _ if host.starts_with("gitlab.") => GitProvider::GitLab,
+            _ => GitProvider::Other,
+        }
+    }
+
+    /// Returns the username the provider expects access tokens to be sent with over HTTPS. GitLab
+    /// accepts `oauth2` for personal, project, and group access tokens, and Bitbucket requires
+    /// `x-token-auth` for repository and workspace access tokens. Other hosts only check the
+    /// token, so GitHub's username is used.
+    pub fn token_username(&self) -> &'static str {
+        match self {
+            GitProvider::GitLab => "oauth2",
+            GitProvider::Bitbucket => "x-token-auth",
+            GitProvider::GitHub | GitProvider::Other => "x-access-token",
+        }
+    }
+}
+
+/// Normalizes a repository URL so it clones reliably from each provider. The query and fragment
+/// are removed, as are trailing slashes. For GitHub, GitLab, and Bitbucket, links to pages within
+/// the repository, such as `/tree/main` or GitLab's `/-/blob/main/README.md`, are trimmed to the
+/// repository itself, and `.git` is appended if missing. URLs on other hosts keep their path, since
+/// self-hosted servers don't all use the `.git` suffix.
+pub fn normalize_repo_url(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_query(None);
+    url.set_fragment(None);
+
+    let provider = url.host_str().map_or(GitProvider::Other, GitProvider::from_host);
+    let segments: Vec<&str> = url.path().split('/').filter(|segment| !segment.is_empty()).collect();
+    let repo_segments = match provider {
+        // Repositories are always `owner/repo`, and anything after is a page within it.
+        GitProvider::GitHub | GitProvider::Bitbucket => &segments[..segments.len().min(2)],
+        // Groups can be nested, so pages within the repository are found by their `-` segment.
+        GitProvider::GitLab => {
+            let end = segments.iter().position(|segment| *segment == "-").unwrap_or(segments.len());
+            &segments[..end]
+        }
+        GitProvider::Other => &segments[..],
+    };
+    let mut path = repo_segments.join("/");
+    if provider != GitProvider::Other && !path.is_empty() && !path.ends_with(".git") {
+        path.push_str(".git");
+    }
+    url.set_path(&path);
+    url
+}
+
+/// Credentials used to clone repositories over HTTPS. They're only sent to the host they're for,
+/// so they aren't leaked to the hosts of submodules. The token is redacted when debug printed so it
+/// never ends up in logs.
+#[derive(Clone, PartialEq, Eq)]
+pub struct GitCredentials {
+    /// The host the credentials are sent to, including the port if it isn't the default.
+    pub host: String,
+    /// The username sent with the token.
+    pub username: String,
+    /// The access token, sent as the password.
+    pub token: String,
+}
+
+impl fmt::Debug for GitCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GitCredentials")
+            .field("host", &self.host)
+            .field("username", &self.username)
+            .field("token", &"<redacted>")
+            .finish()
+    }
+}
+
+impl GitCredentials {
+    /// Returns the credentials to clone `url` with: the caller's `auth_token` if given, otherwise
+    /// any configured for the URL's host in `GIT_HOST_CREDENTIALS`. Returns `None` if there are
+    /// neither, or the URL has no host.
+    pub fn for_url(url: &Url, auth_token: Option<&str>) -> Option<Self> {
+        HostCredentials::from_env().resolve(url, auth_token)
+    }
+}
+
+/// The credentials operators configured for specific git hosts, such as a deploy token for a
+/// self-hosted GitLab instance, used when the caller doesn't provide a token.
+#[derive(Debug, Default)]
+pub struct HostCredentials {
+    /// The username, if configured, and token for each normalized host.
+    hosts: HashMap<String, (Option<String>, String)>,
+}
+
+impl HostCredentials {
+    /// Reads the credentials from `GIT_HOST_CREDENTIALS`. Unset means no hosts have credentials.
+    pub fn from_env() -> Self {
+        std::env::var(GIT_HOST_CREDENTIALS_ENV_VAR)
+            .map(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    /// Parses a comma-separated list of `host=token` or `host=username:token` entries. Without a
+    /// username, the host's provider determines it. Entries without a host or token are ignored.
+    pub fn parse(value: &str) -> Self {
+        let hosts = value
+            .split(',')
+            .filter_map(|entry| {
+                let (host, credentials) = entry.trim().split_once('=')?;
+                let host = host.trim().trim_end_matches('.').to_lowercase();
+                let (username, token) = match credentials.trim().split_once(':') {
+                    Some((username, token)) => (Some(username.to_string()), token.to_string()),
+                    None => (None, credentials.trim().to_string()),
+                };
+                (!host.is_empty() && !token.is_empty()).then_some((host, (username, token)))
+            })
+            .collect();
+        Self { hosts }
+    }
+
+    /// Returns the credentials to clone `url` with, preferring the caller's `auth_token` over any
+    /// configured for the URL's host.
+    pub fn resolve(&self, url: &Url, auth_token: Option<&str>) -> Option<GitCredentials> {
+        let host = url.host_str()?.trim_end_matches('.').to_lowercase();
+        let host = match url.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host,
+        };
+        let default_username = GitProvider::from_host(url.host_str()?).token_username();
+        let (username, token) = match auth_token {
+            Some(token) => (None, token.to_string()),
+            None => self.hosts.get(&host).cloned()?,
+        };
+        let username = username.unwrap_or_else(|| default_username.to_string());
+        Some(GitCredentials { host, username, token })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_repo_url() -> Result<(), Box<dyn std::error::Error>> {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // GitHub.
+            ("https://github.com/ScopeLift/cove", "https://github.com/ScopeLift/cove.git"),
+            ("https://github.com/ScopeLift/cove.git", "https://github.com/ScopeLift/cove.git"),
+            ("https://github.com/ScopeLift/cove/", "https://github.com/ScopeLift/cove.git"),
+            ("https://GitHub.com/ScopeLift/cove/tree/main", "https://github.com/ScopeLift/cove.git"),
+            ("https://github.com/ScopeLift/cove?tab=readme#cove", "https://github.com/ScopeLift/cove.git"),
+            // GitLab, including nested groups and self-hosted instances.
+            ("https://gitlab.com/group/project", "https://gitlab.com/group/project.git"),
+            ("https://gitlab.com/group/sub/project", "https://gitlab.com/group/sub/project.git"),
+            ("https://gitlab.com/group/project/-/tree/main", "https://gitlab.com/group/project.git"),
+            ("https://gitlab.example.com/group/project", "https://gitlab.example.com/group/project.git"),
+            // Bitbucket.
+            ("https://bitbucket.org/workspace/repo", "https://bitbucket.org/workspace/repo.git"),
+            ("https://bitbucket.org/workspace/repo/src/main/", "https://bitbucket.org/workspace/repo.git"),
+            // Other hosts keep their path, without a `.git` suffix added.
+            ("https://git.example.com/org/repo", "https://git.example.com/org/repo"),
+            ("https://git.example.com/org/repo/", "https://git.example.com/org/repo"),
+            ("https://git.example.com:8443/org/repo.git", "https://git.example.com:8443/org/repo.git"),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(normalize_repo_url(&Url::parse(input)?).as_str(), expected, "{input}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_provider_from_host() {
+        assert_eq!(GitProvider::from_host("github.com"), GitProvider::GitHub);
+        assert_eq!(GitProvider::from_host("GitLab.com"), GitProvider::GitLab);
+        assert_eq!(GitProvider::from_host("gitlab.example.com"), GitProvider::GitLab);
+        assert_eq!(GitProvider::from_host("bitbucket.org"), GitProvider::Bitbucket);
+        assert_eq!(GitProvider::from_host("git.example.com"), GitProvider::Other);
+    }
+
+    #[test]
+    fn test_resolve_credentials() -> Result<(), Box<dyn std::error::Error>> {
+        let configured = HostCredentials::parse(
+            "gitlab.example.com=gldt-deploy:deploy-token, bitbucket.org=bb-token,\
+             git.example.com:8443=gitea-token,invalid,empty=",
+        );
+        let resolve =
+            |url: &str, auth_token: Option<&str>| -> Result<_, Box<dyn std::error::Error>> {
+                Ok(configured.resolve(&Url::parse(url)?, auth_token))
+            };
+
+        // Configured usernames are used as is, e.g. for GitLab deploy tokens.
+        let credentials = resolve("https://gitlab.example.com/group/project.git", None)?
+            .ok_or("No credentials")?;
+        assert_eq!(credentials.host, "gitlab.example.com");
+        assert_eq!(credentials.username, "gldt-deploy");
+        assert_eq!(credentials.token, "deploy-token");
+
+        // Otherwise the provider's token username is used.
+        let credentials =
+            resolve("https://bitbucket.org/workspace/repo.git", None)?.ok_or("No credentials")?;
+        assert_eq!(credentials.username, "x-token-auth");
+        assert_eq!(credentials.token, "bb-token");
+
+        // Hosts on non-default ports are configured with their port.
+        let credentials =
+            resolve("https://git.example.com:8443/org/repo", None)?.ok_or("No credentials")?;
+        assert_eq!(credentials.host, "git.example.com:8443");
+        assert_eq!(credentials.username, "x-access-token");
+
+        // The caller's token takes precedence over configured credentials.
+        let credentials = resolve("https://gitlab.example.com/group/project.git", Some("mine"))?
+            .ok_or("No credentials")?;
+        assert_eq!(credentials.username, "oauth2");
+        assert_eq!(credentials.token, "mine");
+
+        // Other hosts have no credentials unless the caller provides a token.
+        assert!(resolve("https://github.com/org/repo.git", None)?.is_none());
+        assert!(resolve("https://empty/org/repo.git", None)?.is_none());
+        let credentials =
+            resolve("https://github.com/org/repo.git", Some("mine"))?.ok_or("No credentials")?;
+        assert_eq!(credentials.username, "x-access-token");
+
+        // The token is never debug printed.
+        assert!(!format!("{credentials:?}").contains("mine"));
+        Ok(())
+    }
+}
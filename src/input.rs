@@ -0,0 +1,136 @@
+use ethers::types::{Address, Bytes};
+use std::{error::Error, fmt, str::FromStr};
+
+/// Length of an address in bytes.
+const ADDRESS_LENGTH: usize = 20;
+
+/// Returned when caller-provided hex can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HexError {
+    /// The input contains a character that isn't a hex digit.
+    InvalidCharacter(char),
+    /// The input has an odd number of hex digits, so it isn't a whole number of bytes.
+    OddLength(usize),
+    /// The input is a whole number of bytes, but not the number expected.
+    InvalidLength {
+        /// The number of bytes expected.
+        expected: usize,
+        /// The number of bytes found.
+        found: usize,
+    },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::InvalidCharacter(c) => write!(f, "invalid hex character {c:?}"),
+            HexError::OddLength(digits) => write!(f, "odd number of hex digits ({digits})"),
+            HexError::InvalidLength { expected, found } => {
+                write!(f, "expected {expected} bytes, found {found}")
+            }
+        }
+    }
+}
+
+impl Error for HexError {}
+
+/// Normalizes caller-provided hex to lowercase digits without a `0x` prefix. Surrounding
+/// whitespace is trimmed, and the prefix may be `0x`, `0X`, or omitted. Returns an error if the
+/// remaining characters aren't an even number of hex digits.
+pub fn normalize_hex(input: &str) -> Result<String, HexError> {
+    let input = input.trim();
+    let digits = input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")).unwrap_or(input);
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(HexError::InvalidCharacter(c))
+    }
+    if digits.len() % 2 != 0 {
+        return Err(HexError::OddLength(digits.len()))
+    }
+    Ok(digits.to_ascii_lowercase())
+}
+
+/// Parses caller-provided hex into bytes, accepting any form `normalize_hex` does. Empty input
+/// parses to empty bytes.
+pub fn parse_hex_bytes(input: &str) -> Result<Bytes, HexError> {
+    let digits = normalize_hex(input)?;
+    Ok(Bytes::from_str(&digits).expect("normalized hex is valid"))
+}
+
+/// Parses a caller-provided address, accepting any form `normalize_hex` does. The checksum of
+/// mixed-case addresses isn't validated, so addresses can be copied from any source.
+pub fn parse_hex_address(input: &str) -> Result<Address, HexError> {
+    let bytes = parse_hex_bytes(input)?;
+    if bytes.len() != ADDRESS_LENGTH {
+        return Err(HexError::InvalidLength { expected: ADDRESS_LENGTH, found: bytes.len() })
+    }
+    Ok(Address::from_slice(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex_bytes() -> Result<(), Box<dyn Error>> {
+        let expected = Bytes::from_str("0x6080604052fe")?;
+        let accepted = vec![
+            ("0x6080604052fe", "prefixed"),
+            ("6080604052fe", "bare"),
+            ("0X6080604052fe", "uppercase prefix"),
+            ("0x6080604052FE", "uppercase digits"),
+            ("  0x6080604052fe\n", "surrounding whitespace"),
+        ];
+        for (input, description) in accepted {
+            assert_eq!(parse_hex_bytes(input), Ok(expected.clone()), "{description}");
+        }
+
+        assert_eq!(parse_hex_bytes("0x"), Ok(Bytes::new()));
+        assert_eq!(parse_hex_bytes(""), Ok(Bytes::new()));
+
+        let rejected = vec![
+            ("0x608", HexError::OddLength(3), "odd length"),
+            ("0x60zz", HexError::InvalidCharacter('z'), "non-hex characters"),
+            ("0x60 80", HexError::InvalidCharacter(' '), "inner whitespace"),
+            ("0x0x6080", HexError::InvalidCharacter('x'), "double prefix"),
+        ];
+        for (input, error, description) in rejected {
+            assert_eq!(parse_hex_bytes(input), Err(error), "{description}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_hex_address() -> Result<(), Box<dyn Error>> {
+        let expected = Address::from_str("0x8d56e3e001132d84488dbacdbb01afb8c3171242")?;
+        let accepted = vec![
+            ("0x8d56e3e001132d84488DbacDbB01AfB8C3171242", "checksummed"),
+            ("0x8d56e3e001132d84488dbacdbb01afb8c3171242", "lowercase"),
+            ("0x8D56E3E001132D84488DBACDBB01AFB8C3171242", "uppercase"),
+            ("8d56e3e001132d84488DbacDbB01AfB8C3171242", "bare"),
+            (" 0x8d56e3e001132d84488DbacDbB01AfB8C3171242 ", "surrounding whitespace"),
+        ];
+        for (input, description) in accepted {
+            assert_eq!(parse_hex_address(input), Ok(expected), "{description}");
+        }
+
+        let rejected = vec![
+            ("", HexError::InvalidLength { expected: 20, found: 0 }, "empty"),
+            ("0x123", HexError::OddLength(3), "odd length"),
+            ("0x1234", HexError::InvalidLength { expected: 20, found: 2 }, "too short"),
+            (
+                "0x8d56e3e001132d84488DbacDbB01AfB8C317124200",
+                HexError::InvalidLength { expected: 20, found: 21 },
+                "too long",
+            ),
+            (
+                "0xZZ56e3e001132d84488DbacDbB01AfB8C3171242",
+                HexError::InvalidCharacter('Z'),
+                "non-hex characters",
+            ),
+        ];
+        for (input, error, description) in rejected {
+            assert_eq!(parse_hex_address(input), Err(error), "{description}");
+        }
+        Ok(())
+    }
+}
@@ -1,23 +1,72 @@
 #![doc = include_str!("../README.md")]
 
+/// Handles optional API key authentication for compute-heavy routes.
+pub mod auth;
+
+/// Runs project build commands with a timeout and a cap on captured output.
+pub mod build;
+
 /// Contains methods and types for analyzing and comparing bytecode.
 pub mod bytecode;
 
+/// Defines the `RepoCloner` trait for cloning repositories to verify, so tests can substitute
+/// fixture repositories for git hosts.
+pub mod cloner;
+
+/// Defines the `ChainProvider` trait for abstracting RPC access to a single chain, so chains can be
+/// served by other backends and queries can be tested without a network.
+pub mod chain_provider;
+
+/// Defines the `BytecodeComparator` trait for comparing bytecode in a chain-specific way.
+pub mod comparator;
+
+/// Limits how many verifications run at once, queueing or rejecting the rest.
+pub mod concurrency;
+
 /// Handles all app configuration.
 pub mod config;
 
 /// Defines the `Framework` trait for abstracting over different development frameworks. Also
-/// contains an implementation for Foundry.
+/// contains implementations for Foundry and Hardhat.
 pub mod frameworks;
 
+/// Normalizes repository URLs for each git hosting provider and resolves the credentials to clone
+/// them with.
+pub mod git_host;
+
+/// Parses hex-encoded addresses and bytecode submitted by callers, whatever their prefix or case.
+pub mod input;
+
+/// Records counters, gauges, and histograms about verifications, exposed in the Prometheus format.
+pub mod metrics;
+
+/// Reports the stages a verification job goes through, so clients can stream its progress.
+pub mod progress;
+
 /// Contains methods and types for interacting with an Ethereum provider and comparing bytecode.
 pub mod provider;
 
+/// Limits how many requests each client can make to expensive routes.
+pub mod rate_limit;
+
+/// Caches cloned repositories on disk by commit, evicting the least recently used.
+pub mod repo_cache;
+
+/// Gives every request an ID that's logged with its events and returned to the caller.
+pub mod request_id;
+
 /// Defines the handlers for all API routes.
 pub mod routes;
 
 /// Handles the server startup, such as route configuration and middleware.
 pub mod startup;
 
+/// Persists successful verifications in a database, so they can be served later.
+pub mod storage;
+
 /// Handles logs and tracing.
 pub mod telemetry;
+
+/// Posts the results of verification jobs to the callback URLs callers provide, signed so
+/// receivers can check they came from us.
+pub mod webhook;
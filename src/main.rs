@@ -6,6 +6,7 @@
 //! documentation.
 use cove::{config, startup, telemetry};
 use std::net::TcpListener;
+use tokio::signal;
 
 /// Entrypoint for the application.
 #[tokio::main]
@@ -15,7 +16,27 @@ async fn main() -> hyper::Result<()> {
 
     let configuration = config::get_configuration().expect("Failed to read configuration.");
     let address = format!("{}:{}", configuration.application.host, configuration.application.port);
-    println!("Listening on {}", address);
+    tracing::info!(%address, "Listening");
     let listener = TcpListener::bind(address).expect("Unable to bind to port");
-    startup::run(listener)?.await
+    startup::run_until(listener, shutdown_signal())?.await
+}
+
+/// Completes when the process receives SIGINT or SIGTERM, which starts a graceful shutdown.
+async fn shutdown_signal() {
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await
+            }
+        }
+    };
+    tokio::select! {
+        _ = signal::ctrl_c() => {}
+        _ = terminate => {}
+    }
+    tracing::info!("Shutting down");
 }
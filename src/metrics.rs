@@ -0,0 +1,200 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// Upper bounds, in seconds, of the histogram buckets. Clones and builds take seconds to minutes,
+/// while RPC calls take milliseconds, so the buckets span both.
+const BUCKETS: [f64; 12] = [0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// The metrics recorded by the server, exposed in the Prometheus text format by `/metrics`.
+pub static METRICS: Metrics = Metrics {
+    verifications: Mutex::new(BTreeMap::new()),
+    jobs_in_flight: AtomicI64::new(0),
+    clone_duration: Histogram::new(),
+    build_duration: Histogram::new(),
+    rpc_duration: Histogram::new(),
+};
+
+/// Counters, gauges, and histograms describing the verifications the server has run.
+pub struct Metrics {
+    /// The number of verification jobs that finished, keyed by outcome: `success`, or the error
+    /// code the job failed with.
+    verifications: Mutex<BTreeMap<String, u64>>,
+    /// The number of verification jobs currently running.
+    jobs_in_flight: AtomicI64,
+    /// How long cloning repositories takes, including checking out the commit and submodules.
+    clone_duration: Histogram,
+    /// How long running a project's build commands takes.
+    build_duration: Histogram,
+    /// How long each RPC call takes, per attempt.
+    rpc_duration: Histogram,
+}
+
+impl Metrics {
+    /// Records that a verification job finished with the given outcome.
+    pub fn record_verification(&self, outcome: &str) {
+        if let Ok(mut verifications) = self.verifications.lock() {
+            *verifications.entry(outcome.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Records that a verification job started, returning a guard that records it finished when
+    /// dropped, so the gauge stays correct even if the job panics or is cancelled.
+    pub fn start_job(&self) -> JobInFlight<'_> {
+        self.jobs_in_flight.fetch_add(1, Ordering::Relaxed);
+        JobInFlight(self)
+    }
+
+    /// Records how long cloning a repository took.
+    pub fn observe_clone(&self, duration: Duration) {
+        self.clone_duration.observe(duration);
+    }
+
+    /// Records how long running a project's build commands took.
+    pub fn observe_build(&self, duration: Duration) {
+        self.build_duration.observe(duration);
+    }
+
+    /// Records how long an RPC call took.
+    pub fn observe_rpc(&self, duration: Duration) {
+        self.rpc_duration.observe(duration);
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        let _ = writeln!(
+            output,
+            "# HELP cove_verifications_total Verification jobs that finished, by outcome."
+        );
+        let _ = writeln!(output, "# TYPE cove_verifications_total counter");
+        if let Ok(verifications) = self.verifications.lock() {
+            for (outcome, count) in verifications.iter() {
+                let _ =
+                    writeln!(output, "cove_verifications_total{{outcome=\"{outcome}\"}} {count}");
+            }
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP cove_verification_jobs_in_flight Verification jobs currently running."
+        );
+        let _ = writeln!(output, "# TYPE cove_verification_jobs_in_flight gauge");
+        let in_flight = self.jobs_in_flight.load(Ordering::Relaxed);
+        let _ = writeln!(output, "cove_verification_jobs_in_flight {in_flight}");
+
+        self.clone_duration.render(
+            &mut output,
+            "cove_clone_duration_seconds",
+            "Time taken to clone a repository and check out the commit.",
+        );
+        self.build_duration.render(
+            &mut output,
+            "cove_build_duration_seconds",
+            "Time taken to run a project's build commands.",
+        );
+        self.rpc_duration.render(
+            &mut output,
+            "cove_rpc_duration_seconds",
+            "Time taken by each RPC call attempt.",
+        );
+        output
+    }
+}
+
+/// Decrements the in-flight jobs gauge when dropped.
+pub struct JobInFlight<'a>(&'a Metrics);
+
+impl Drop for JobInFlight<'_> {
+    fn drop(&mut self) {
+        self.0.jobs_in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A histogram of durations with fixed buckets, updated without locking.
+pub struct Histogram {
+    /// The number of observations in each bucket of `BUCKETS`, followed by the number above the
+    /// largest bucket. These are not cumulative, unlike in the rendered output.
+    buckets: [AtomicU64; BUCKETS.len() + 1],
+    /// The sum of all observations, in microseconds.
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    /// Returns a histogram with no observations.
+    const fn new() -> Self {
+        /// A bucket with no observations, used to initialize the array.
+        #[allow(clippy::declare_interior_mutable_const)]
+        const EMPTY: AtomicU64 = AtomicU64::new(0);
+        Self { buckets: [EMPTY; BUCKETS.len() + 1], sum_micros: AtomicU64::new(0) }
+    }
+
+    /// Records a single observation.
+    fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        let index = BUCKETS.iter().position(|bound| secs <= *bound).unwrap_or(BUCKETS.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        let micros = duration.as_micros().try_into().unwrap_or(u64::MAX);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    /// Appends the histogram to `output` in the Prometheus text exposition format.
+    fn render(&self, output: &mut String, name: &str, help: &str) {
+        let _ = writeln!(output, "# HELP {name} {help}");
+        let _ = writeln!(output, "# TYPE {name} histogram");
+        let mut count = 0;
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            count += bucket.load(Ordering::Relaxed);
+            let bound = BUCKETS.get(index).map_or("+Inf".to_string(), f64::to_string);
+            let _ = writeln!(output, "{name}_bucket{{le=\"{bound}\"}} {count}");
+        }
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(output, "{name}_sum {sum}");
+        let _ = writeln!(output, "{name}_count {count}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics {
+            verifications: Mutex::new(BTreeMap::new()),
+            jobs_in_flight: AtomicI64::new(0),
+            clone_duration: Histogram::new(),
+            build_duration: Histogram::new(),
+            rpc_duration: Histogram::new(),
+        };
+        metrics.record_verification("success");
+        metrics.record_verification("NO_BYTECODE_MATCH");
+        metrics.record_verification("success");
+        metrics.observe_build(Duration::from_millis(300));
+        metrics.observe_build(Duration::from_secs(700));
+        let job = metrics.start_job();
+
+        let output = metrics.render();
+        assert!(output.contains("cove_verifications_total{outcome=\"success\"} 2\n"));
+        assert!(output.contains("cove_verifications_total{outcome=\"NO_BYTECODE_MATCH\"} 1\n"));
+        assert!(output.contains("cove_verification_jobs_in_flight 1\n"));
+
+        // Buckets are cumulative, and observations above the largest bucket are only in `+Inf`.
+        assert!(output.contains("cove_build_duration_seconds_bucket{le=\"0.1\"} 0\n"));
+        assert!(output.contains("cove_build_duration_seconds_bucket{le=\"0.5\"} 1\n"));
+        assert!(output.contains("cove_build_duration_seconds_bucket{le=\"600\"} 1\n"));
+        assert!(output.contains("cove_build_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(output.contains("cove_build_duration_seconds_sum 700.3\n"));
+        assert!(output.contains("cove_build_duration_seconds_count 2\n"));
+        assert!(output.contains("cove_clone_duration_seconds_count 0\n"));
+
+        drop(job);
+        assert!(metrics.render().contains("cove_verification_jobs_in_flight 0\n"));
+    }
+}
@@ -0,0 +1,95 @@
+use ethers::types::Chain;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::watch;
+
+/// A stage of a verification job, reported to clients streaming the job's progress.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// The repository is being cloned and the commit checked out.
+    Cloning,
+    /// The project is being built with a build command.
+    Building {
+        /// The profile the project is built with, if the framework has profiles.
+        profile: Option<String>,
+    },
+    /// The build artifacts are being compared against the on-chain bytecode.
+    Comparing,
+    /// The source matched the contract on a chain.
+    Matched {
+        /// The chain the contract matched on.
+        chain: Chain,
+    },
+    /// The job finished. This is always the last event.
+    Result {
+        /// The final status of the job, as returned by `verify_status`.
+        status: Value,
+    },
+}
+
+impl ProgressEvent {
+    /// Returns the name of the stage, which is the event name sent to clients.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgressEvent::Cloning => "cloning",
+            ProgressEvent::Building { .. } => "building",
+            ProgressEvent::Comparing => "comparing",
+            ProgressEvent::Matched { .. } => "matched",
+            ProgressEvent::Result { .. } => "result",
+        }
+    }
+}
+
+/// The progress of a single verification job. Every event reported so far is kept, so clients that
+/// start streaming after the job started still receive all of them, in order.
+pub struct VerificationProgress {
+    /// The events reported so far, which subscribers are notified of as they're added.
+    events: watch::Sender<Vec<ProgressEvent>>,
+}
+
+impl Default for VerificationProgress {
+    fn default() -> Self {
+        Self { events: watch::channel(Vec::new()).0 }
+    }
+}
+
+impl VerificationProgress {
+    /// Reports that the job reached a new stage.
+    pub fn report(&self, event: ProgressEvent) {
+        tracing::debug!(stage = event.name(), "Verification progress");
+        self.events.send_modify(|events| events.push(event));
+    }
+
+    /// Reports that the job finished with the given status, serialized as for `verify_status`.
+    pub fn finish(&self, status: Value) {
+        self.report(ProgressEvent::Result { status });
+    }
+
+    /// Returns a receiver of every event reported so far, which is notified as events are added.
+    pub fn subscribe(&self) -> watch::Receiver<Vec<ProgressEvent>> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_subscribers_receive_earlier_events() {
+        let progress = VerificationProgress::default();
+        progress.report(ProgressEvent::Cloning);
+
+        // Subscribing after an event was reported still receives it.
+        let mut events = progress.subscribe();
+        assert_eq!(*events.borrow_and_update(), vec![ProgressEvent::Cloning]);
+
+        progress.report(ProgressEvent::Matched { chain: Chain::Mainnet });
+        progress.finish(json!({ "status": "pending" }));
+        assert!(events.has_changed().unwrap_or_default());
+        let names: Vec<_> = events.borrow_and_update().iter().map(ProgressEvent::name).collect();
+        assert_eq!(names, ["cloning", "matched", "result"]);
+    }
+}
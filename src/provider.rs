@@ -1,24 +1,83 @@
 use crate::{
-    bytecode::{creation_code_equality_check, deployed_code_equality_check, MatchType},
+    bytecode::{Libraries, MatchOutcome, MatchType},
+    chain_provider::ChainProvider,
+    comparator::{ComparatorChain, MatchQuality},
     frameworks::framework::Framework,
+    metrics::METRICS,
 };
 use ethers::{
-    providers::{Http, Middleware, Provider},
-    types::{Address, BlockNumber, Bytes, Chain, Transaction, TxHash, U256},
+    providers::{Http, Provider, ProviderError},
+    types::{
+        Action, Address, BlockId, BlockNumber, Bytes, Chain, Res, Trace, Transaction, TxHash, H256,
+        U256,
+    },
+};
+use futures::{future, Future};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION},
+    Url,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{hash_map::RandomState, HashMap, HashSet},
+    env,
+    error::Error,
+    fs,
+    hash::{BuildHasher, Hasher},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
-use futures::future;
-use std::{collections::HashMap, env, error::Error, path::PathBuf, str::FromStr, sync::Arc};
 
 /// Contract creation data.
+#[derive(Debug, Clone)]
 pub struct ContractCreation {
     /// The transaction hash of the contract creation transaction.
     pub tx_hash: TxHash,
-    /// The block number of the contract creation transaction.
-    pub block: BlockNumber,
+    /// The block of the contract creation transaction. After being returned from the
+    /// `MultiChainProvider` this is always a `BlockId::Number(BlockNumber::Number(_))`.
+    pub block: BlockId,
     /// The creation code of the contract.
     pub creation_code: Bytes,
 }
 
+/// The minimum length, in bytes, of creation code accepted from a provider. Creation code has to
+/// at least copy the runtime code into memory and return it, so anything shorter means the provider
+/// returned bad data, e.g. because of a reorg or a gap in its archive data.
+pub const MIN_CREATION_CODE_BYTES: usize = 10;
+
+/// Returns why `creation`'s code can't be trusted if it's empty or shorter than
+/// `MIN_CREATION_CODE_BYTES`. Unlike the creation code not being found, this means the provider
+/// returned bad data and the lookup should be retried.
+pub fn check_creation_code(creation: &ContractCreation) -> Result<(), String> {
+    let tx_hash = creation.tx_hash;
+    match creation.creation_code.len() {
+        0 => Err(format!("creation code in transaction {tx_hash:?} is empty")),
+        length if length < MIN_CREATION_CODE_BYTES => {
+            Err(format!("creation code in transaction {tx_hash:?} is only {length} bytes"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Why looking up a contract's creation code on a chain was inconclusive.
+#[derive(Debug)]
+enum CreationLookupError {
+    /// The provider returned empty or truncated creation code, and no archive node returned
+    /// trustworthy code instead.
+    Suspicious(String),
+    /// An RPC call failed, so whether the chain has creation code for the contract is unknown.
+    Rpc(String),
+}
+
+/// Returns the message of `error` if it's from a failed RPC call, as opposed to the creation
+/// transaction not being found or not being supported.
+fn rpc_error(error: &(dyn Error + Send + Sync + 'static)) -> Option<String> {
+    error.downcast_ref::<ProviderError>().map(ToString::to_string)
+}
+
 /// Match data for a given artifact.
 #[derive(Debug, Default, Clone)]
 pub struct ContractMatch {
@@ -26,6 +85,42 @@ pub struct ContractMatch {
     pub artifact: PathBuf,
     /// The type of match for that artifact against the expected code.
     pub match_type: MatchType,
+    /// The name of the comparator that matched the code, such as `exact` or `metadata-stripped`.
+    pub comparator: Option<&'static str>,
+    /// The quality of the match, derived from the comparator that matched the code.
+    pub quality: MatchQuality,
+    /// The ABI-encoded constructor arguments appended to the expected creation code, if any. This
+    /// is always `None` for deployed code matches.
+    pub constructor_args: Option<Bytes>,
+    /// The addresses of the libraries the artifact was linked against to match the expected code.
+    pub libraries: Libraries,
+    /// Other artifacts that matched the expected code as well as `artifact` did. If this isn't
+    /// empty, the match is ambiguous.
+    pub candidates: Vec<PathBuf>,
+}
+
+/// The outcome of comparing one artifact's creation code against the creation code on a chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactOutcome {
+    /// The chain the expected creation code is from.
+    pub chain: Chain,
+    /// Path to the artifact.
+    pub artifact: PathBuf,
+    /// Why the artifact did or didn't match.
+    pub outcome: MatchOutcome,
+}
+
+/// Returns the outcome that came closest to matching without matching, so it can be reported when
+/// nothing matched. Ties are broken by chain ID and then artifact path, so the result is
+/// deterministic.
+pub fn best_near_miss(outcomes: &[ArtifactOutcome]) -> Option<&ArtifactOutcome> {
+    outcomes.iter().filter(|o| o.outcome != MatchOutcome::Matched).min_by(|a, b| {
+        b.outcome
+            .closeness()
+            .cmp(&a.outcome.closeness())
+            .then_with(|| u64::from(a.chain).cmp(&u64::from(b.chain)))
+            .then_with(|| a.artifact.cmp(&b.artifact))
+    })
 }
 
 // ==============================
@@ -34,39 +129,18 @@ pub struct ContractMatch {
 
 /// Create a provider for the given chain.
 pub fn provider_from_chain(chain: Chain) -> Arc<Provider<Http>> {
-    match chain {
-        // Mainnet + Testnets.
-        Chain::Mainnet => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Goerli => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Sepolia => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        // Other chains.
-        Chain::Optimism => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Arbitrum => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Polygon => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::XDai => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Avalanche => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        _ => panic!("Unsupported chain"),
-    }
+    Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
 }
 
-/// Return the RPC provider URL for the given chain.
+/// Return the RPC provider URL for the given chain. Chains with a public RPC endpoint fall back to
+/// it when their environment variable isn't set, but public endpoints are rate limited, so a
+/// dedicated provider should be configured in production.
 pub fn provider_url_from_chain(chain: Chain) -> String {
+    /// Returns the URL in the environment variable, or `default` if it's unset or empty.
+    fn url_or_default(var: &str, default: &str) -> String {
+        env::var(var).ok().filter(|url| !url.is_empty()).unwrap_or_else(|| default.to_string())
+    }
+
     match chain {
         // Mainnet + Testnets.
         Chain::Mainnet => env::var("MAINNET_RPC_URL").unwrap(),
@@ -74,17 +148,311 @@ pub fn provider_url_from_chain(chain: Chain) -> String {
         Chain::Sepolia => env::var("SEPOLIA_RPC_URL").unwrap(),
         // Other chains.
         Chain::Optimism => env::var("OPTIMISM_RPC_URL").unwrap(),
-        Chain::Arbitrum => env::var("ARBITRUM_ONE_RPC_URL").unwrap(),
-        Chain::Polygon => env::var("POLYGON_RPC_URL").unwrap(),
+        Chain::Arbitrum => url_or_default("ARBITRUM_ONE_RPC_URL", "https://arb1.arbitrum.io/rpc"),
+        Chain::Polygon => url_or_default("POLYGON_RPC_URL", "https://polygon-rpc.com"),
+        Chain::Base => url_or_default("BASE_RPC_URL", "https://mainnet.base.org"),
         Chain::XDai => env::var("GNOSIS_CHAIN_RPC_URL").unwrap(),
         Chain::Avalanche => env::var("AVALANCHE_RPC_URL").unwrap(),
+        #[cfg(feature = "zksync")]
+        Chain::ZkSync => url_or_default("ZKSYNC_RPC_URL", "https://mainnet.era.zksync.io"),
         _ => panic!("Unsupported chain"),
     }
 }
 
-/// Return the runtime code at the given address using the given provider.
-pub async fn contract_runtime_code(provider: &Arc<Provider<Http>>, address: Address) -> Bytes {
-    provider.get_code(address, None).await.unwrap()
+/// Chains users deploy to that can't be verified on yet, as their chain ID and name. Chains are
+/// identified by ethers' `Chain` everywhere, which has no variant for these in the version of
+/// ethers used here, so supporting them needs an ethers upgrade.
+pub const UNSUPPORTED_CHAINS: [(u64, &str); 1] = [(81457, "blast")];
+
+/// Returns the message explaining that the chain, given by name or chain ID, isn't supported. The
+/// chains in `UNSUPPORTED_CHAINS` are called out, so callers know they aren't simply misspelled.
+pub fn unsupported_chain_message(chain: &Value) -> String {
+    let unsupported = UNSUPPORTED_CHAINS.iter().find(|(id, name)| match chain {
+        Value::Number(number) => number.as_u64() == Some(*id),
+        Value::String(chain) => chain.eq_ignore_ascii_case(name) || *chain == id.to_string(),
+        _ => false,
+    });
+    match unsupported {
+        Some((id, name)) => format!(
+            "Chain {name} ({id}) is not supported yet, since this server's version of ethers \
+             doesn't support it"
+        ),
+        None => format!("Chain {chain} is not supported"),
+    }
+}
+
+/// Returns the chains the default `MultiChainProvider` connects to. zkSync Era is only included
+/// with the `zksync` feature, since its contracts aren't EVM bytecode. Blast isn't included, see
+/// `UNSUPPORTED_CHAINS`.
+pub fn default_chains() -> Vec<Chain> {
+    #[allow(unused_mut)]
+    let mut chains = vec![
+        Chain::Arbitrum,
+        Chain::Base,
+        Chain::Goerli,
+        Chain::Mainnet,
+        Chain::Optimism,
+        Chain::Polygon,
+        Chain::Sepolia,
+        Chain::XDai,
+        Chain::Avalanche,
+    ];
+    #[cfg(feature = "zksync")]
+    chains.push(Chain::ZkSync);
+    chains
+}
+
+/// Returns true if contracts on the chain are created by a transaction whose input is the creation
+/// code, as on EVM chains. On zkSync Era, the creation transaction calls the `ContractDeployer`
+/// system contract with the hash of the bytecode instead, so it can't be compared to artifacts.
+pub fn has_evm_creation_code(chain: Chain) -> bool {
+    !matches!(chain, Chain::ZkSync | Chain::ZkSyncTestnet)
+}
+
+/// The storage slot holding the implementation address of an EIP-1967 proxy, i.e.
+/// `bytes32(uint256(keccak256('eip1967.proxy.implementation')) - 1)`.
+pub const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// The runtime code of an EIP-1167 minimal proxy before the implementation address.
+const EIP1167_PREFIX: [u8; 10] = [0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+
+/// The runtime code of an EIP-1167 minimal proxy after the implementation address.
+const EIP1167_SUFFIX: [u8; 15] =
+    [0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3];
+
+/// Connection details for a single chain's RPC provider.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChainConfig {
+    /// The chain to connect to.
+    pub chain: Chain,
+    /// The URL of the chain's RPC provider.
+    pub rpc_url: String,
+    /// Optional value of the `Authorization` header sent with each RPC request, for providers that
+    /// don't support authenticating via the URL.
+    pub auth_header: Option<String>,
+    /// Optional Etherscan-style block explorer API, used to find the creation transaction when the
+    /// caller doesn't provide it.
+    pub explorer: Option<ExplorerConfig>,
+    /// Whether the RPC provider supports `trace_transaction`, which is used to find the creation
+    /// code of contracts deployed by factories we don't otherwise know how to decode. Defaults to
+    /// `false`, since many providers don't support tracing.
+    #[serde(default)]
+    pub trace_creations: bool,
+    /// Optional URL of an archive node for the chain, authenticated like `rpc_url`. Creation code
+    /// lookups are retried against it when `rpc_url` returns empty or truncated creation code.
+    pub archive_rpc_url: Option<String>,
+}
+
+/// Connection details for an Etherscan-style block explorer API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExplorerConfig {
+    /// The URL of the API, e.g. `https://api.etherscan.io/api`.
+    pub api_url: String,
+    /// Optional API key sent with each request.
+    pub api_key: Option<String>,
+}
+
+impl ExplorerConfig {
+    /// Returns the hash of the transaction that created the contract, using the explorer's
+    /// `getcontractcreation` API.
+    pub async fn creation_tx_hash(
+        &self,
+        address: Address,
+    ) -> Result<TxHash, Box<dyn Error + Send + Sync>> {
+        let mut url = Url::parse(&self.api_url)?;
+        url.query_pairs_mut()
+            .append_pair("module", "contract")
+            .append_pair("action", "getcontractcreation")
+            .append_pair("contractaddresses", &format!("{address:?}"));
+        if let Some(api_key) = &self.api_key {
+            url.query_pairs_mut().append_pair("apikey", api_key);
+        }
+
+        // The URL contains the API key, so it's stripped from errors.
+        let response = reqwest::get(url).await.map_err(|e| e.without_url())?;
+        let body = response.text().await.map_err(|e| e.without_url())?;
+        let body: Value = serde_json::from_str(&body)?;
+        let tx_hash = body["result"][0]["txHash"].as_str().ok_or_else(|| {
+            format!("Explorer did not return a creation transaction: {}", body["result"])
+        })?;
+        Ok(TxHash::from_str(tx_hash)?)
+    }
+}
+
+/// The contents of a chains config file.
+#[derive(Deserialize)]
+struct ChainsConfigFile {
+    /// The chains to connect to.
+    chains: Vec<ChainConfig>,
+}
+
+/// Environment variable containing the path to the chains config file.
+const CHAINS_CONFIG_PATH_ENV: &str = "CHAINS_CONFIG_PATH";
+
+/// Path of the chains config file, relative to the working directory, used if
+/// `CHAINS_CONFIG_PATH` is not set.
+const DEFAULT_CHAINS_CONFIG_PATH: &str = "chains.toml";
+
+/// Loads the chain configs from the TOML file at `CHAINS_CONFIG_PATH`, or from `chains.toml` if
+/// that isn't set. Returns `None` if there is no config file, in which case the default chains
+/// should be used.
+pub fn load_chain_configs() -> Result<Option<Vec<ChainConfig>>, Box<dyn Error + Send + Sync>> {
+    let path = match env::var(CHAINS_CONFIG_PATH_ENV).ok().filter(|path| !path.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = PathBuf::from(DEFAULT_CHAINS_CONFIG_PATH);
+            if !path.is_file() {
+                return Ok(None)
+            }
+            path
+        }
+    };
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read chains config {}: {e}", path.display()))?;
+    let config: ChainsConfigFile = toml::from_str(&content)?;
+    Ok(Some(config.chains))
+}
+
+/// Create a provider from the given chain config.
+pub fn provider_from_config(
+    config: &ChainConfig,
+) -> Result<Arc<Provider<Http>>, Box<dyn Error + Send + Sync>> {
+    let url = Url::parse(&config.rpc_url)?;
+    let mut headers = HeaderMap::new();
+    if let Some(auth_header) = &config.auth_header {
+        let mut value = HeaderValue::from_str(auth_header)?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+    let client = reqwest::Client::builder().default_headers(headers).build()?;
+    Ok(Arc::new(Provider::new(Http::new_with_client(url, client))))
+}
+
+/// Configuration for retrying RPC calls that fail with transient errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// The maximum number of times a call is attempted, including the first attempt.
+    pub max_attempts: u32,
+    /// The backoff before the first retry. This doubles after each retry.
+    pub initial_backoff: Duration,
+    /// The upper bound on the backoff between retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Returns the backoff before the given retry, where the first retry is `0`. This uses "full
+    /// jitter", i.e. a random duration between zero and the exponential backoff, so concurrent
+    /// callers that were rate limited at the same time don't all retry at the same time.
+    fn backoff(&self, retry: u32) -> Duration {
+        let exponential = self.initial_backoff.saturating_mul(2u32.saturating_pow(retry));
+        let max_backoff = exponential.min(self.max_backoff);
+        // `RandomState` is randomly seeded, so this is a cheap source of randomness.
+        let random = RandomState::new().build_hasher().finish();
+        let fraction = (random % 1_000) as f64 / 1_000.0;
+        max_backoff.mul_f64(fraction)
+    }
+}
+
+/// JSON-RPC error codes that indicate the request was rate limited.
+const RATE_LIMIT_ERROR_CODES: [i64; 2] = [429, -32005];
+
+/// Substrings of error messages that indicate a transient failure that may succeed if retried.
+const RETRYABLE_ERROR_MESSAGES: [&str; 8] = [
+    "429",
+    "too many requests",
+    "rate limit",
+    "timed out",
+    "timeout",
+    "502 bad gateway",
+    "503 service unavailable",
+    "connection reset",
+];
+
+/// Returns `true` if the error is likely transient, such as a rate limit or timeout, and the call
+/// should be retried. Other errors, such as invalid parameters, are permanent and retrying them
+/// would only delay the failure.
+pub fn is_retryable(error: &ProviderError) -> bool {
+    if let ProviderError::JsonRpcClientError(e) = error {
+        if let Some(response) = e.as_error_response() {
+            return RATE_LIMIT_ERROR_CODES.contains(&response.code)
+                || response.message.to_lowercase().contains("rate limit")
+        }
+    }
+    if let ProviderError::HTTPError(e) = error {
+        if e.is_timeout() {
+            return true
+        }
+    }
+    let message = error.to_string().to_lowercase();
+    RETRYABLE_ERROR_MESSAGES.iter().any(|retryable| message.contains(retryable))
+}
+
+/// Runs the given RPC call, retrying with exponential backoff if it fails with a retryable error.
+/// Returns the last error if all attempts fail or the error is not retryable.
+pub async fn with_retry<T, F, Fut>(config: &RetryConfig, mut call: F) -> Result<T, ProviderError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ProviderError>>,
+{
+    let mut retry = 0;
+    loop {
+        let started_at = Instant::now();
+        let result = call().await;
+        METRICS.observe_rpc(started_at.elapsed());
+        match result {
+            Ok(value) => return Ok(value),
+            Err(e) if retry + 1 < config.max_attempts && is_retryable(&e) => {
+                tokio::time::sleep(config.backoff(retry)).await;
+                retry += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Resolves the given block identifier to its block number. Block hashes and tags such as `latest`
+/// are resolved by fetching the block from the provider.
+pub async fn resolve_block_number(
+    provider: &dyn ChainProvider,
+    block: BlockId,
+    retry: &RetryConfig,
+) -> Result<u64, Box<dyn Error + Send + Sync>> {
+    if let BlockId::Number(BlockNumber::Number(number)) = block {
+        return Ok(number.as_u64())
+    }
+    let block = with_retry(retry, || provider.get_block(block)).await?.ok_or("Block not found")?;
+    let number = block.number.ok_or("Block is pending and has no number")?;
+    Ok(number.as_u64())
+}
+
+/// Returns the block number of the given block identifier if it is already a number.
+pub fn block_id_number(block: &BlockId) -> Option<u64> {
+    match block {
+        BlockId::Number(BlockNumber::Number(number)) => Some(number.as_u64()),
+        _ => None,
+    }
+}
+
+/// Return the runtime code at the given address using the given provider, as of `block` if given
+/// or the latest block otherwise.
+pub async fn contract_runtime_code(
+    provider: &dyn ChainProvider,
+    address: Address,
+    block: Option<BlockId>,
+) -> Result<Bytes, ProviderError> {
+    with_retry(&RetryConfig::default(), || provider.get_code(address, block)).await
 }
 
 // =============================
@@ -92,10 +460,19 @@ pub async fn contract_runtime_code(provider: &Arc<Provider<Http>>, address: Addr
 // =============================
 
 /// The response from a multi-chain provider's query.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ChainResponse<T> {
     /// A mapping from chain to the response for that chain.
     pub responses: HashMap<Chain, Option<T>>,
+    /// Why the query was inconclusive on each chain whose provider failed. These chains have no
+    /// response, but unlike other chains without one, that doesn't mean there's nothing there.
+    pub chain_errors: HashMap<Chain, String>,
+}
+
+impl<T> Default for ChainResponse<T> {
+    fn default() -> Self {
+        Self { responses: HashMap::new(), chain_errors: HashMap::new() }
+    }
 }
 
 impl<T> ChainResponse<T> {
@@ -110,12 +487,60 @@ impl<T> ChainResponse<T> {
     }
 }
 
+/// An in-memory cache of contract creation data that expires entries after a fixed duration.
+pub struct CreationCache {
+    /// How long an entry remains valid after it was inserted.
+    ttl: Duration,
+    /// The cached creation data and the time it was inserted, keyed by chain and contract address.
+    entries: Mutex<HashMap<(Chain, Address), (Instant, ContractCreation)>>,
+}
+
+impl CreationCache {
+    /// Create an empty cache whose entries expire after `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns the cached creation data for the contract, if present and not expired.
+    pub fn get(&self, chain: Chain, address: Address) -> Option<ContractCreation> {
+        let mut entries = self.entries.lock().ok()?;
+        match entries.get(&(chain, address)) {
+            Some((inserted, creation)) if inserted.elapsed() < self.ttl => Some(creation.clone()),
+            Some(_) => {
+                entries.remove(&(chain, address));
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Caches the creation data for the contract, replacing any existing entry.
+    pub fn insert(&self, chain: Chain, address: Address, creation: ContractCreation) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert((chain, address), (Instant::now(), creation));
+        }
+    }
+}
+
 /// A provider that performs the same queries or operations across multiple chains simultaneously.
 pub struct MultiChainProvider {
     /// The chains that this provider supports.
     pub chains: Vec<Chain>,
     /// The provider for each chain.
-    pub providers: HashMap<Chain, Arc<Provider<Http>>>,
+    pub providers: HashMap<Chain, Arc<dyn ChainProvider>>,
+    /// Optional cache of creation code lookups, so repeated verifications of the same contract
+    /// don't repeat the RPC calls. This is shared with providers scoped to a single chain.
+    creation_cache: Option<Arc<CreationCache>>,
+    /// How RPC calls that fail with transient errors are retried.
+    retry_config: RetryConfig,
+    /// The block explorer API for each chain that has one configured, used to find creation
+    /// transactions that the RPC provider can't.
+    explorers: HashMap<Chain, ExplorerConfig>,
+    /// The chains whose RPC provider supports tracing transactions.
+    tracing_chains: HashSet<Chain>,
+    /// The archive node provider for each chain that has one configured, used to retry creation
+    /// code lookups that return suspicious code.
+    archive_providers: HashMap<Chain, Arc<dyn ChainProvider>>,
 }
 
 impl Default for MultiChainProvider {
@@ -127,238 +552,718 @@ impl Default for MultiChainProvider {
 impl MultiChainProvider {
     /// Create a new `MultiChainProvider` with all supported chains.
     pub fn new() -> Self {
-        let chains = vec![
-            Chain::Arbitrum,
-            Chain::Goerli,
-            Chain::Mainnet,
-            Chain::Optimism,
-            Chain::Polygon,
-            Chain::Sepolia,
-            Chain::XDai,
-            Chain::Avalanche,
-        ];
+        let chains = default_chains();
 
         let providers = chains
             .iter()
-            .map(|chain| (*chain, provider_from_chain(*chain)))
+            .map(|chain| (*chain, provider_from_chain(*chain) as Arc<dyn ChainProvider>))
             .collect::<HashMap<_, _>>();
 
-        Self { chains, providers }
+        Self {
+            chains,
+            providers,
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        }
+    }
+
+    /// Create a new `MultiChainProvider` with all supported chains that caches creation code
+    /// lookups for `ttl`.
+    pub fn with_cache(ttl: Duration) -> Self {
+        Self::new().cache_for(ttl)
+    }
+
+    /// Create a new `MultiChainProvider` for the chains in the given configs. Each chain may only
+    /// be configured once.
+    pub fn from_config(configs: Vec<ChainConfig>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut chains = Vec::with_capacity(configs.len());
+        let mut providers: HashMap<Chain, Arc<dyn ChainProvider>> =
+            HashMap::with_capacity(configs.len());
+        let mut explorers = HashMap::new();
+        let mut tracing_chains = HashSet::new();
+        let mut archive_providers: HashMap<Chain, Arc<dyn ChainProvider>> = HashMap::new();
+        for config in &configs {
+            if providers.insert(config.chain, provider_from_config(config)?).is_some() {
+                return Err(format!("Chain {} is configured more than once", config.chain).into())
+            }
+            if let Some(explorer) = &config.explorer {
+                explorers.insert(config.chain, explorer.clone());
+            }
+            if config.trace_creations {
+                tracing_chains.insert(config.chain);
+            }
+            if let Some(archive_rpc_url) = &config.archive_rpc_url {
+                let archive = ChainConfig { rpc_url: archive_rpc_url.clone(), ..config.clone() };
+                archive_providers.insert(config.chain, provider_from_config(&archive)?);
+            }
+            chains.push(config.chain);
+        }
+        Ok(Self {
+            chains,
+            providers,
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers,
+            tracing_chains,
+            archive_providers,
+        })
+    }
+
+    /// Sets how RPC calls that fail with transient errors are retried.
+    pub fn retry_with(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = retry_config;
+        self
+    }
+
+    /// Enables caching of creation code lookups for `ttl`, replacing any existing cache.
+    pub fn cache_for(mut self, ttl: Duration) -> Self {
+        self.creation_cache = Some(Arc::new(CreationCache::new(ttl)));
+        self
+    }
+
+    /// Returns a provider that only queries the given chain, sharing this provider's creation code
+    /// cache and retry config. Returns `None` if the chain isn't configured.
+    pub fn scoped_to(&self, chain: Chain) -> Option<Self> {
+        let provider = self.providers.get(&chain)?.clone();
+        Some(Self {
+            chains: vec![chain],
+            providers: HashMap::from([(chain, provider)]),
+            creation_cache: self.creation_cache.clone(),
+            retry_config: self.retry_config,
+            explorers: self.explorers.get(&chain).map(|e| (chain, e.clone())).into_iter().collect(),
+            tracing_chains: self.tracing_chains.iter().copied().filter(|c| *c == chain).collect(),
+            archive_providers: self
+                .archive_providers
+                .get(&chain)
+                .map(|archive| (chain, archive.clone()))
+                .into_iter()
+                .collect(),
+        })
     }
 
     /// Given an address, return the creation code at that address for each supported chain.
+    /// Returns an error if no chain has creation code for the address but some chain returned
+    /// empty or truncated creation code, since that means a provider is missing data rather than
+    /// the contract not existing.
     pub async fn get_creation_code(
         &self,
         address: Address,
         creation_tx_hashes: Option<HashMap<Chain, TxHash>>,
     ) -> Result<ChainResponse<ContractCreation>, Box<dyn Error + Send + Sync>> {
         /// Given an address, return the creation code at that address for the chain specified by
-        /// the provider.
-        async fn find_creation_code(
-            provider: &Arc<Provider<Http>>,
+        /// the provider. If that fails and the chain has a block explorer configured, the explorer
+        /// is asked for the creation transaction instead. Returns an error if an RPC call failed,
+        /// since the chain may have creation code for the address that couldn't be fetched.
+        async fn lookup_creation_code(
+            provider: &dyn ChainProvider,
             address: Address,
             creation_tx_hash: Option<TxHash>,
-        ) -> Option<ContractCreation> {
+            retry: &RetryConfig,
+            explorer: Option<&ExplorerConfig>,
+            trace: bool,
+        ) -> Result<Option<ContractCreation>, String> {
             let creation_data =
-                find_creation_data(provider, address, creation_tx_hash).await.ok()?;
-            Some(creation_data)
+                match find_creation_data(provider, address, creation_tx_hash, retry, trace).await {
+                    Ok(creation_data) => Ok(creation_data),
+                    Err(error) => match explorer {
+                        Some(explorer) => match explorer.creation_tx_hash(address).await {
+                            Ok(tx_hash) => {
+                                find_creation_data(provider, address, Some(tx_hash), retry, trace)
+                                    .await
+                            }
+                            Err(_) => Err(error),
+                        },
+                        None => Err(error),
+                    },
+                };
+            let mut creation_data = match creation_data {
+                Ok(creation_data) => creation_data,
+                Err(error) => return rpc_error(error.as_ref()).map_or(Ok(None), Err),
+            };
+
+            // Normalize the block to a number, so consumers don't need to handle block hashes.
+            let block_number = resolve_block_number(provider, creation_data.block, retry)
+                .await
+                .map_err(|e| format!("Failed to resolve the creation block: {e}"))?;
+            creation_data.block = BlockId::from(block_number);
+            Ok(Some(creation_data))
+        }
+
+        /// Like `lookup_creation_code`, but sanity checks the creation code that's found. If it's
+        /// empty or too short, the lookup is retried against the chain's archive node, and an
+        /// error is returned if there's no archive node or it returns suspicious code too.
+        async fn find_creation_code(
+            provider: &dyn ChainProvider,
+            archive: Option<&dyn ChainProvider>,
+            address: Address,
+            creation_tx_hash: Option<TxHash>,
+            retry: &RetryConfig,
+            explorer: Option<&ExplorerConfig>,
+            trace: bool,
+        ) -> Result<Option<ContractCreation>, CreationLookupError> {
+            let lookup =
+                lookup_creation_code(provider, address, creation_tx_hash, retry, explorer, trace);
+            let Some(creation) = lookup.await.map_err(CreationLookupError::Rpc)? else {
+                return Ok(None)
+            };
+            let Err(reason) = check_creation_code(&creation) else { return Ok(Some(creation)) };
+            tracing::warn!(?address, %reason, "Provider returned suspicious creation code");
+
+            let Some(archive) = archive else {
+                return Err(CreationLookupError::Suspicious(reason))
+            };
+            let tx_hash = Some(creation.tx_hash);
+            let creation = lookup_creation_code(archive, address, tx_hash, retry, None, trace)
+                .await
+                .map_err(|e| format!("{reason}, and the archive node failed: {e}"))
+                .and_then(|creation| {
+                    creation.ok_or_else(|| format!("{reason}, and the archive node didn't find it"))
+                })
+                .map_err(CreationLookupError::Suspicious)?;
+            check_creation_code(&creation).map_err(|archive_reason| {
+                CreationLookupError::Suspicious(format!(
+                    "{reason}, and on the archive node {archive_reason}"
+                ))
+            })?;
+            Ok(Some(creation))
         }
 
         let futures = self.providers.iter().map(|(chain, provider)| {
             let creation_tx_hash = creation_tx_hashes.as_ref().and_then(|h| h.get(chain)).cloned();
-            async move { (*chain, find_creation_code(provider, address, creation_tx_hash).await) }
+            let cache = self.creation_cache.as_ref();
+            let retry = &self.retry_config;
+            let explorer = self.explorers.get(chain);
+            let trace = self.tracing_chains.contains(chain);
+            let archive = self.archive_providers.get(chain).map(|archive| archive.as_ref());
+            async move {
+                if let Some(creation) = cache.and_then(|cache| cache.get(*chain, address)) {
+                    return (*chain, Ok(Some(creation)))
+                }
+
+                // Only successful lookups are cached, since a failed lookup may succeed when
+                // retried, e.g. with a different transaction hash.
+                let creation = find_creation_code(
+                    provider.as_ref(),
+                    archive,
+                    address,
+                    creation_tx_hash,
+                    retry,
+                    explorer,
+                    trace,
+                )
+                .await;
+                if let (Some(cache), Ok(Some(creation))) = (cache, &creation) {
+                    cache.insert(*chain, address, creation.clone());
+                }
+                (*chain, creation)
+            }
         });
-        let responses = future::join_all(futures).await.into_iter().collect::<HashMap<_, _>>();
-        Ok(ChainResponse { responses })
+        // Chains whose lookup failed have no creation code, but their errors are kept so callers
+        // know those chains were inconclusive. Only suspicious creation code fails the whole
+        // lookup, and only if no chain has creation code.
+        let mut response = ChainResponse::default();
+        let mut suspicious = Vec::new();
+        for (chain, result) in future::join_all(futures).await {
+            let creation = match result {
+                Ok(creation) => creation,
+                Err(CreationLookupError::Suspicious(reason)) => {
+                    suspicious.push(format!("{chain}: {reason}"));
+                    response.chain_errors.insert(chain, reason);
+                    None
+                }
+                Err(CreationLookupError::Rpc(error)) => {
+                    tracing::warn!(%chain, ?address, %error, "Failed to look up creation code");
+                    response.chain_errors.insert(chain, error);
+                    None
+                }
+            };
+            response.responses.insert(chain, creation);
+        }
+        if response.is_all_none() && !suspicious.is_empty() {
+            let msg =
+                format!("Provider returned suspicious creation code on {}", suspicious.join(", "));
+            return Err(msg.into())
+        }
+        Ok(response)
     }
 
-    /// Given an address, return the deployed code at that address for each supported chain.
+    /// Returns `known` with the hash of the transaction `deployer` sent with `nonce` added for each
+    /// chain that's missing from it and has that transaction. This finds plain `CREATE`
+    /// deployments from an externally owned account when their transaction hash isn't known, since
+    /// the contract's address is derived from the deployer and nonce. Finding the transaction
+    /// queries historical state, so each chain's archive node is used if it has one configured.
+    pub async fn find_creation_tx_hashes(
+        &self,
+        deployer: Address,
+        nonce: u64,
+        known: Option<HashMap<Chain, TxHash>>,
+    ) -> HashMap<Chain, TxHash> {
+        let mut tx_hashes = known.unwrap_or_default();
+        let futures = self
+            .providers
+            .iter()
+            .filter(|(chain, _)| !tx_hashes.contains_key(chain))
+            .map(|(chain, provider)| {
+                let provider = self.archive_providers.get(chain).unwrap_or(provider).as_ref();
+                let retry = &self.retry_config;
+                async move {
+                    let tx_hash = find_transaction_by_nonce(provider, deployer, nonce, retry).await;
+                    if let Err(error) = &tx_hash {
+                        tracing::warn!(%chain, ?deployer, nonce, %error, "Deployment scan failed");
+                    }
+                    (*chain, tx_hash.ok().flatten())
+                }
+            });
+        for (chain, tx_hash) in future::join_all(futures).await {
+            if let Some(tx_hash) = tx_hash {
+                tx_hashes.insert(chain, tx_hash);
+            }
+        }
+        tx_hashes
+    }
+
+    /// Given an address, return the deployed code at that address for each supported chain, as of
+    /// `block` if given or the latest block otherwise. Returns an error if no chain has code at the
+    /// address but the code couldn't be fetched on some chain, since the contract may be deployed
+    /// there. An `Ok` response with no code on any chain means the address is an EOA or has no
+    /// contract deployed.
     pub async fn get_deployed_code(
         &self,
         address: Address,
+        block: Option<u64>,
     ) -> Result<ChainResponse<Bytes>, Box<dyn Error>> {
-        /// Given an address, return the deployed code at that address for the chain specified by
-        /// the given provider.
-        async fn find_deployed_code(
-            provider: &Arc<Provider<Http>>,
-            address: Address,
-        ) -> Option<Bytes> {
-            let code = provider.get_code(address, None).await.ok()?;
-            if code.is_empty() {
+        let block = block.map(BlockId::from);
+        let futures = self.providers.iter().map(|(chain, provider)| async move {
+            let code = fetch_deployed_code(provider.as_ref(), address, block, &self.retry_config);
+            (*chain, code.await)
+        });
+        let mut response = ChainResponse::default();
+        for (chain, result) in future::join_all(futures).await {
+            let code = result.unwrap_or_else(|e| {
+                response.chain_errors.insert(chain, e.to_string());
                 None
-            } else {
-                Some(code)
+            });
+            response.responses.insert(chain, code);
+        }
+        if response.is_all_none() && !response.chain_errors.is_empty() {
+            let failures: Vec<String> =
+                response.chain_errors.iter().map(|(chain, e)| format!("{chain}: {e}")).collect();
+            return Err(format!("Failed to fetch code on {}", failures.join(", ")).into())
+        }
+        Ok(response)
+    }
+
+    /// Given an address for each chain, return the deployed code at that chain's address. Chains
+    /// without an address have no deployed code. The errors of chains whose code couldn't be
+    /// fetched are added to those `addresses` already had, e.g. from looking up the addresses.
+    pub async fn get_deployed_code_at(
+        &self,
+        addresses: &ChainResponse<Address>,
+    ) -> ChainResponse<Bytes> {
+        let futures = self.providers.iter().map(|(chain, provider)| {
+            let address = addresses.responses.get(chain).copied().flatten();
+            async move {
+                let code = match address {
+                    Some(address) => {
+                        let retry = &self.retry_config;
+                        fetch_deployed_code(provider.as_ref(), address, None, retry).await
+                    }
+                    None => Ok(None),
+                };
+                (*chain, code)
             }
+        });
+        let mut response =
+            ChainResponse { chain_errors: addresses.chain_errors.clone(), ..Default::default() };
+        for (chain, result) in future::join_all(futures).await {
+            let code = result.unwrap_or_else(|e| {
+                let error = format!("Failed to fetch the implementation's code: {e}");
+                response.chain_errors.insert(chain, error);
+                None
+            });
+            response.responses.insert(chain, code);
+        }
+        response
+    }
+
+    /// Given an address, return the implementation address stored in its EIP-1967 implementation
+    /// slot for each supported chain. Chains where the slot is empty, i.e. the contract is not an
+    /// EIP-1967 proxy, have no implementation address. Chains where the slot couldn't be read have
+    /// none either, and their errors are kept, since the contract may be a proxy there.
+    pub async fn get_implementation_address(&self, address: Address) -> ChainResponse<Address> {
+        /// Reads the implementation slot of the address on the chain specified by the provider.
+        async fn find_implementation_address(
+            provider: &dyn ChainProvider,
+            address: Address,
+            retry: &RetryConfig,
+        ) -> Result<Option<Address>, ProviderError> {
+            let Ok(slot) = H256::from_str(EIP1967_IMPLEMENTATION_SLOT) else { return Ok(None) };
+            let value = with_retry(retry, || provider.get_storage_at(address, slot)).await?;
+            Ok(implementation_from_slot(value))
         }
 
         let futures = self.providers.iter().map(|(chain, provider)| async move {
-            (*chain, find_deployed_code(provider, address).await)
+            let retry = &self.retry_config;
+            (*chain, find_implementation_address(provider.as_ref(), address, retry).await)
         });
-        let responses = future::join_all(futures).await.into_iter().collect::<HashMap<_, _>>();
-        Ok(ChainResponse { responses })
+        let mut response = ChainResponse::default();
+        for (chain, result) in future::join_all(futures).await {
+            let implementation = result.unwrap_or_else(|e| {
+                let error = format!("Failed to read the implementation slot: {e}");
+                response.chain_errors.insert(chain, error);
+                None
+            });
+            response.responses.insert(chain, implementation);
+        }
+        response
+    }
+
+    /// Checks that each chain's provider returns the latest block number within `timeout`. Returns
+    /// the block number for each chain that responded, or a description of the failure. Failed
+    /// calls are not retried, so an unhealthy provider is reported quickly.
+    pub async fn check_providers(&self, timeout: Duration) -> HashMap<Chain, Result<u64, String>> {
+        let futures = self.providers.iter().map(|(chain, provider)| async move {
+            let status = match tokio::time::timeout(timeout, provider.get_block_number()).await {
+                Ok(Ok(block_number)) => Ok(block_number.as_u64()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err(format!("Timed out after {}ms", timeout.as_millis())),
+            };
+            (*chain, status)
+        });
+        future::join_all(futures).await.into_iter().collect()
     }
 
     /// Given the creation code data being compared against and the build artifacts from a project,
     /// compare the creation code against the expected creation code for each artifact and return
-    /// the best match found. It's possible that no match is found.
+    /// the best match found. It's possible that no match is found. If `contract_name` is given,
+    /// only artifacts for contracts with that name are compared. The outcome of comparing each
+    /// artifact on each chain is returned too, so callers can explain why nothing matched.
     pub fn compare_creation_code(
         &self,
         project: &impl Framework,
         creation_data: &ChainResponse<ContractCreation>,
-    ) -> ChainResponse<ContractMatch> {
-        /// Compares the creation code against the expected creation code for each artifact and
-        /// returns the best match.
-        fn compare(
-            project: &impl Framework,
-            expected_creation_code: &Bytes,
-        ) -> Option<ContractMatch> {
-            let artifacts = project.get_artifacts().unwrap();
-            if artifacts.is_empty() {
-                panic!("No artifacts found in project");
-            }
-
-            let mut best_artifact_match: Option<ContractMatch> = None;
-            for artifact in artifacts {
-                let found = match project.structure_found_creation_code(&artifact) {
-                    Ok(found) => found,
-                    Err(_) => continue,
-                };
-
-                let expected = match project.structure_expected_creation_code(
-                    &artifact,
-                    &found,
-                    expected_creation_code,
-                ) {
-                    Ok(expected) => expected,
-                    Err(_) => continue,
-                };
-
-                // If we have an exact match, return it. If we have a partial match, save it off.
-                // We'll return it if we don't find an exact match. Note that treats all partial
-                // matches equally and arbitrarily gives priority to the last one.
-                match creation_code_equality_check(&found, &expected) {
-                    MatchType::Full => {
-                        return Some(ContractMatch { artifact, match_type: MatchType::Full })
-                    }
-                    MatchType::Partial => {
-                        best_artifact_match =
-                            Some(ContractMatch { artifact, match_type: MatchType::Partial })
-                    }
-                    _ => {}
-                }
-            }
-            best_artifact_match
-        }
-
+        contract_name: Option<&str>,
+    ) -> (ChainResponse<ContractMatch>, Vec<ArtifactOutcome>) {
+        let mut outcomes = Vec::new();
         let responses = self
             .providers
             .keys()
             .map(|chain| {
-                let expected_creation_data = creation_data.responses.get(chain).unwrap();
-                if expected_creation_data.is_none() {
-                    return (*chain, None)
+                let expected_creation_data = creation_data
+                    .responses
+                    .get(chain)
+                    .and_then(|data| data.as_ref())
+                    .filter(|_| has_evm_creation_code(*chain));
+                match expected_creation_data {
+                    Some(data) => {
+                        let comparators = ComparatorChain::for_chain(*chain);
+                        let (contract_match, artifact_outcomes) = match_creation_code_with_outcomes(
+                            project,
+                            &data.creation_code,
+                            contract_name,
+                            &comparators,
+                        );
+                        outcomes.extend(artifact_outcomes.into_iter().map(
+                            |(artifact, outcome)| ArtifactOutcome {
+                                chain: *chain,
+                                artifact,
+                                outcome,
+                            },
+                        ));
+                        (*chain, contract_match)
+                    }
+                    None => (*chain, None),
                 }
-                let expected_creation_code =
-                    &expected_creation_data.as_ref().unwrap().creation_code;
-                (*chain, compare(project, expected_creation_code))
             })
             .collect::<HashMap<_, _>>();
 
-        ChainResponse { responses }
+        (ChainResponse { responses, ..Default::default() }, outcomes)
     }
 
     /// Given the deployed code being compared against and the build artifacts from a project,
     /// compare the deployed code against the expected deployed code for each artifact and return
-    /// the best match found. It's possible that no match is found.
+    /// the best match found. It's possible that no match is found. If `contract_name` is given,
+    /// only artifacts for contracts with that name are compared.
     pub fn compare_deployed_code(
         &self,
         project: &impl Framework,
         deployed_code: &ChainResponse<Bytes>,
+        contract_name: Option<&str>,
     ) -> ChainResponse<ContractMatch> {
-        /// Compares the deployed code against the expected deployed code for each artifact and
-        /// returns the best match.
-        fn compare(
-            project: &impl Framework,
-            expected_deployed_code: &Bytes,
-        ) -> Option<ContractMatch> {
-            let artifacts = project.get_artifacts().unwrap();
-            if artifacts.is_empty() {
-                panic!("No artifacts found in project");
-            }
-
-            let mut best_artifact_match: Option<ContractMatch> = None;
-            for artifact in artifacts {
-                let found = match project.structure_found_deployed_code(&artifact) {
-                    Ok(found) => found,
-                    Err(_) => continue,
-                };
-
-                let expected = match project
-                    .structure_expected_deployed_code(&found, expected_deployed_code)
-                {
-                    Ok(expected) => expected,
-                    Err(_) => continue,
-                };
-
-                // If we have an exact match, return it. If we have a partial match, save it off.
-                // We'll return it if we don't find an exact match. Note that treats all partial
-                // matches equally and arbitrarily gives priority to the last one.
-                match deployed_code_equality_check(&found, &expected) {
-                    MatchType::Full => {
-                        return Some(ContractMatch { artifact, match_type: MatchType::Full })
-                    }
-                    MatchType::Partial => {
-                        best_artifact_match =
-                            Some(ContractMatch { artifact, match_type: MatchType::Partial })
-                    }
-                    _ => {}
-                }
-            }
-            best_artifact_match
-        }
-
         let responses = self
             .providers
             .keys()
             .map(|chain| {
-                let expected_deployed_code = deployed_code.responses.get(chain).unwrap();
-                if expected_deployed_code.is_none() {
-                    return (*chain, None)
+                let expected_deployed_code =
+                    deployed_code.responses.get(chain).and_then(|code| code.as_ref());
+                match expected_deployed_code {
+                    Some(code) => {
+                        let comparators = ComparatorChain::for_chain(*chain);
+                        (*chain, match_deployed_code(project, code, contract_name, &comparators))
+                    }
+                    None => (*chain, None),
                 }
-                let expected_creation_code = &expected_deployed_code.as_ref().unwrap();
-                (*chain, compare(project, expected_creation_code))
             })
             .collect::<HashMap<_, _>>();
 
-        ChainResponse { responses }
+        ChainResponse { responses, ..Default::default() }
+    }
+}
+
+/// Compares the expected creation code against the creation code of each of the project's
+/// artifacts and returns the best match, if any. If `contract_name` is given, only artifacts for
+/// contracts with that name are compared. The expected code may come from any source, such as a
+/// creation transaction or a caller that already has the bytecode. The code is compared using
+/// `comparators`, which depend on the chain the code is from.
+pub fn match_creation_code(
+    project: &impl Framework,
+    expected_creation_code: &Bytes,
+    contract_name: Option<&str>,
+    comparators: &ComparatorChain,
+) -> Option<ContractMatch> {
+    match_creation_code_with_outcomes(project, expected_creation_code, contract_name, comparators).0
+}
+
+/// Like `match_creation_code`, but also returns the outcome of comparing each artifact, in the
+/// order they were compared. Artifacts whose creation code can't be read have no outcome.
+pub fn match_creation_code_with_outcomes(
+    project: &impl Framework,
+    expected_creation_code: &Bytes,
+    contract_name: Option<&str>,
+    comparators: &ComparatorChain,
+) -> (Option<ContractMatch>, Vec<(PathBuf, MatchOutcome)>) {
+    // If artifacts can't be read there is nothing to compare against, so there's no match.
+    let Ok(artifacts) = project.get_artifacts() else { return (None, Vec::new()) };
+
+    let mut matches = Vec::new();
+    let mut outcomes = Vec::new();
+    for artifact in artifacts {
+        if !artifact_has_name(&artifact, contract_name) {
+            continue
+        }
+        let found = match project.structure_found_creation_code(&artifact) {
+            Ok(found) => found,
+            Err(_) => continue,
+        };
+        // Fill in any library addresses from the expected code before comparing.
+        let (found, libraries) = found.link(expected_creation_code);
+
+        let expected = match project.structure_expected_creation_code(
+            &artifact,
+            &found,
+            expected_creation_code,
+        ) {
+            Ok(expected) => expected,
+            Err(_) if expected_creation_code.len() < found.leading_code.len() => {
+                let (found, expected) = (found.raw_code.len(), expected_creation_code.len());
+                outcomes.push((artifact, MatchOutcome::LengthMismatch { found, expected }));
+                continue
+            }
+            Err(_) => continue,
+        };
+
+        let comparison = comparators.compare_creation(&found, &expected);
+        outcomes.push((
+            artifact.clone(),
+            MatchOutcome::classify(&found, &expected, comparison.match_type),
+        ));
+        if comparison.match_type != MatchType::None {
+            let constructor_args = expected.constructor_args.clone();
+            matches.push(ContractMatch {
+                artifact,
+                match_type: comparison.match_type,
+                comparator: comparison.comparator,
+                quality: comparison.quality.unwrap_or_default(),
+                constructor_args,
+                libraries,
+                candidates: Vec::new(),
+            });
+        }
+    }
+    (best_match(matches), outcomes)
+}
+
+/// Compares the expected deployed code against the deployed code of each of the project's
+/// artifacts and returns the best match, if any. If `contract_name` is given, only artifacts for
+/// contracts with that name are compared. The code is compared using `comparators`, which depend on
+/// the chain the code is from.
+pub fn match_deployed_code(
+    project: &impl Framework,
+    expected_deployed_code: &Bytes,
+    contract_name: Option<&str>,
+    comparators: &ComparatorChain,
+) -> Option<ContractMatch> {
+    // If artifacts can't be read there is nothing to compare against, so there's no match.
+    let artifacts = project.get_artifacts().ok()?;
+
+    let mut matches = Vec::new();
+    for artifact in artifacts {
+        if !artifact_has_name(&artifact, contract_name) {
+            continue
+        }
+        let found = match project.structure_found_deployed_code(&artifact) {
+            Ok(found) => found,
+            Err(_) => continue,
+        };
+        // Fill in any library addresses from the expected code before comparing.
+        let (found, libraries) = found.link(expected_deployed_code);
+
+        let expected =
+            match project.structure_expected_deployed_code(&found, expected_deployed_code) {
+                Ok(expected) => expected,
+                Err(_) => continue,
+            };
+
+        let comparison = comparators.compare_deployed(&found, &expected);
+        if comparison.match_type != MatchType::None {
+            matches.push(ContractMatch {
+                artifact,
+                match_type: comparison.match_type,
+                comparator: comparison.comparator,
+                quality: comparison.quality.unwrap_or_default(),
+                constructor_args: None,
+                libraries,
+                candidates: Vec::new(),
+            });
+        }
+    }
+    best_match(matches)
+}
+
+/// Returns true if the artifact is for a contract named `contract_name`, or if no name is given.
+/// Artifacts are named after their contract, so this compares the file stem.
+fn artifact_has_name(artifact: &Path, contract_name: Option<&str>) -> bool {
+    contract_name.map_or(true, |name| artifact.file_stem().map_or(false, |stem| stem == name))
+}
+
+/// Returns the best of the matches for a single expected code. Full matches are preferred over
+/// partial matches, and among equally good matches the first full match or the last partial
+/// match is returned. The other artifacts that matched equally well are returned as the match's
+/// `candidates`, so callers can tell when the match is ambiguous.
+fn best_match(matches: Vec<ContractMatch>) -> Option<ContractMatch> {
+    let match_type = if matches.iter().any(|m| m.match_type == MatchType::Full) {
+        MatchType::Full
+    } else {
+        MatchType::Partial
+    };
+    let mut best: Vec<ContractMatch> =
+        matches.into_iter().filter(|m| m.match_type == match_type).collect();
+    let index = match match_type {
+        MatchType::Full => 0,
+        _ => best.len().checked_sub(1)?,
+    };
+    let mut best_match = best.remove(index);
+    best_match.candidates = best.into_iter().map(|m| m.artifact).collect();
+    Some(best_match)
+}
+
+/// Returns the deployed code at the address using the given provider, as of `block` if given or
+/// the latest block otherwise, or `None` if there is no code, i.e. `eth_getCode` returned empty
+/// bytes. Returns an error if the code could not be fetched.
+async fn fetch_deployed_code(
+    provider: &dyn ChainProvider,
+    address: Address,
+    block: Option<BlockId>,
+    retry: &RetryConfig,
+) -> Result<Option<Bytes>, ProviderError> {
+    let code = with_retry(retry, || provider.get_code(address, block)).await?;
+    Ok(if code.is_empty() { None } else { Some(code) })
+}
+
+/// Returns the address stored in an EIP-1967 implementation slot, or `None` if the slot is empty.
+/// Addresses are left-padded to 32 bytes in storage, so the address is the last 20 bytes.
+pub fn implementation_from_slot(value: H256) -> Option<Address> {
+    if value.is_zero() {
+        return None
     }
+    Some(Address::from_slice(&value.as_bytes()[12..]))
+}
+
+/// Returns the implementation address embedded in `code` if it's the runtime code of an EIP-1167
+/// minimal proxy, also known as a clone, or `None` otherwise. Clones have no source of their own,
+/// since their code is always the same 45 bytes apart from the address they delegate to.
+pub fn minimal_proxy_implementation(code: &[u8]) -> Option<Address> {
+    let code = code.strip_prefix(EIP1167_PREFIX.as_slice())?;
+    let address = code.strip_suffix(EIP1167_SUFFIX.as_slice())?;
+    (address.len() == Address::len_bytes()).then(|| Address::from_slice(address))
 }
 
 /// Given the transaction hash of a contract creation transaction, extracts the creation code from
-/// the transaction and returns the creation data. This feature is currently not supported.
+/// the transaction and returns the creation data. If `trace` is true, the transaction is traced to
+/// find contracts deployed by unknown factories. Finding creation data without a transaction hash
+/// is currently not supported.
 async fn find_creation_data(
-    provider: &Arc<Provider<Http>>,
+    provider: &dyn ChainProvider,
     address: Address,
     tx_hash: Option<TxHash>,
+    retry: &RetryConfig,
+    trace: bool,
 ) -> Result<ContractCreation, Box<dyn std::error::Error + Send + Sync>> {
     // If we have a transaction hash, use that to find the creation code.
     if let Some(tx_hash) = tx_hash {
-        let (creation_code, tx) = creation_code_from_tx_hash(provider, address, tx_hash).await?;
-        let block = BlockNumber::from(tx.block_number.unwrap());
+        let (creation_code, tx) =
+            creation_code_from_tx_hash(provider, address, tx_hash, retry, trace).await?;
+        let block = match (tx.block_number, tx.block_hash) {
+            (Some(number), _) => BlockId::from(number),
+            (None, Some(hash)) => BlockId::from(hash),
+            (None, None) => return Err("Transaction is still pending".into()),
+        };
         return Ok(ContractCreation { tx_hash, block, creation_code })
     }
 
     Err("Automatically finding creation data is currently not supported.".into())
 }
 
+/// Returns the hash of the transaction `sender` sent with `nonce`, or `None` if it hasn't sent one.
+/// An account's transaction count only grows, so the block that includes the transaction is the
+/// first block after which the count exceeds `nonce`, which is found by binary search.
+async fn find_transaction_by_nonce(
+    provider: &dyn ChainProvider,
+    sender: Address,
+    nonce: u64,
+    retry: &RetryConfig,
+) -> Result<Option<TxHash>, Box<dyn Error + Send + Sync>> {
+    let count_after = |block: u64| async move {
+        let block = BlockId::from(BlockNumber::Number(block.into()));
+        let count = with_retry(retry, || provider.get_transaction_count(sender, Some(block)));
+        count.await.map(|count| count.as_u64())
+    };
+
+    let latest = with_retry(retry, || provider.get_block_number()).await?.as_u64();
+    if count_after(latest).await? <= nonce {
+        return Ok(None)
+    }
+    let (mut low, mut high) = (0, latest);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if count_after(mid).await? > nonce {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+
+    let block = with_retry(retry, || provider.get_block_with_txs(BlockId::from(low)))
+        .await?
+        .ok_or_else(|| format!("Block {low} not found"))?;
+    let tx = block.transactions.iter().find(|tx| tx.from == sender && tx.nonce == nonce.into());
+    Ok(tx.map(|tx| tx.hash))
+}
+
 /// Given the transaction hash of a contract creation transaction, extracts the creation code from
-/// the transaction. This feature is currently not supported.
+/// the transaction. Contracts deployed by unknown factories are only supported if `trace` is true,
+/// since that requires the provider to support `trace_transaction`.
 async fn creation_code_from_tx_hash(
-    provider: &Arc<Provider<Http>>,
+    provider: &dyn ChainProvider,
     address: Address,
     tx_hash: TxHash,
+    retry: &RetryConfig,
+    trace: bool,
 ) -> Result<(Bytes, Transaction), Box<dyn std::error::Error + Send + Sync>> {
-    // TODO This is not currently supported, but the flow would be as follows:
+    // The flow is as follows:
     //   1. Fetch the transaction data.
     //   2. If `to` is None, this was a regular CREATE transaction so we can extract the creation
     //      code from the input data.
@@ -366,15 +1271,19 @@ async fn creation_code_from_tx_hash(
     //      if it's a known factory. If so, we'll know how to decode the transaction data to extract
     //      the creation code.
     //   4. If the `to` address is not a known factory, we trace the transaction to find the call
-    //      that deployed the contract. Infura now supports `trace_call` so we can use that here.
-    // Note that steps 1, 2, and 3 are implemented below. Step 4 is not implemented. Step 3 can also
-    // be expanded to support more factories, or it can be removed entirely and we can always trace.
-    let tx = provider.get_transaction(tx_hash).await?.ok_or("Transaction not found")?;
+    //      that deployed the contract. Not all providers support tracing, so this is only done if
+    //      enabled for the chain.
+    // Step 3 can be expanded to support more factories, or it can be removed entirely and we can
+    // always trace once tracing is widely supported.
+    let tx = with_retry(retry, || provider.get_transaction(tx_hash))
+        .await?
+        .ok_or("Transaction not found")?;
 
     // Regular CREATE transaction.
     if tx.to.is_none() {
-        let receipt =
-            provider.get_transaction_receipt(tx_hash).await?.ok_or("Receipt not found")?;
+        let receipt = with_retry(retry, || provider.get_transaction_receipt(tx_hash))
+            .await?
+            .ok_or("Receipt not found")?;
         if let Some(contract_address) = receipt.contract_address {
             if contract_address == address {
                 let creation_code = tx.input.clone();
@@ -409,17 +1318,48 @@ async fn creation_code_from_tx_hash(
             let creation_code = Bytes::from_iter(creation_code);
             return Ok((creation_code, tx))
         }
+
+        // Unknown factory, so trace the transaction to find the creation of our contract.
+        if trace {
+            let traces = with_retry(retry, || provider.trace_transaction(tx_hash)).await?;
+            if let Some(creation_code) = creation_code_from_traces(&traces, address) {
+                return Ok((creation_code, tx))
+            }
+        }
     }
     Err("Contract creation transaction not found. It may have been deployed by an unsupported factory, or the wrong transaction hash for this chain was provided.".into())
 }
 
+/// Returns the init code of the internal creation, i.e. `CREATE` or `CREATE2`, that deployed
+/// `address` in the given transaction traces.
+fn creation_code_from_traces(traces: &[Trace], address: Address) -> Option<Bytes> {
+    traces.iter().find_map(|trace| match (&trace.action, &trace.result) {
+        (Action::Create(create), Some(Res::Create(result))) if result.address == address => {
+            Some(create.init.clone())
+        }
+        _ => None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{chain_provider::MockChainProvider, frameworks::foundry::Foundry};
+    use axum::{
+        extract::Query,
+        routing::{get, post},
+        Json, Router,
+    };
     use dotenvy::dotenv;
+    use ethers::types::{Block, TransactionReceipt};
     use futures::future::try_join_all;
+    use serde_json::{json, Value};
+    use std::{
+        net::TcpListener,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
 
-    fn get_provider() -> Arc<Provider<Http>> {
+    fn get_provider() -> Arc<dyn ChainProvider> {
         if dotenv().is_err() {
             // We don't error since there's no `.env` file in CI.
             println!("WARNING: No .env file found, using default environment variables.");
@@ -427,9 +1367,1075 @@ mod tests {
         Arc::new(Provider::<Http>::try_from(env::var("GOERLI_RPC_URL").unwrap()).unwrap())
     }
 
+    /// Spawns a local JSON-RPC server that answers each request with `respond(method, call)`, where
+    /// `call` is the zero-based index of the request, and returns a provider connected to it along
+    /// with a counter of the requests it has received. An `Err` from `respond` is returned as a
+    /// JSON-RPC error.
+    async fn mock_provider(
+        respond: fn(&str, usize) -> Result<Value, Value>,
+    ) -> (Arc<dyn ChainProvider>, Arc<AtomicUsize>) {
+        mock_provider_with_latency(respond, Duration::ZERO).await
+    }
+
+    /// Like `mock_provider`, but waits for `latency` before answering each request.
+    async fn mock_provider_with_latency(
+        respond: fn(&str, usize) -> Result<Value, Value>,
+        latency: Duration,
+    ) -> (Arc<dyn ChainProvider>, Arc<AtomicUsize>) {
+        let (provider, calls, _) = spawn_mock_provider(respond, latency).await;
+        (provider, calls)
+    }
+
+    /// Like `mock_provider`, but returns the requests the server has received instead of a
+    /// counter, so tests can check the parameters of each RPC call.
+    async fn recording_mock_provider(
+        respond: fn(&str, usize) -> Result<Value, Value>,
+    ) -> (Arc<dyn ChainProvider>, Arc<Mutex<Vec<Value>>>) {
+        let (provider, _, requests) = spawn_mock_provider(respond, Duration::ZERO).await;
+        (provider, requests)
+    }
+
+    /// Like `mock_provider`, but answers each request with `respond(method, params)`, for calls
+    /// whose result depends on their parameters.
+    async fn params_mock_provider(
+        respond: fn(&str, &Value) -> Result<Value, Value>,
+    ) -> (Arc<dyn ChainProvider>, Arc<AtomicUsize>) {
+        let respond = move |method: &str, _, params: &Value| respond(method, params);
+        let (provider, calls, _) = spawn_mock_server(respond, Duration::ZERO).await;
+        (provider, calls)
+    }
+
+    /// Spawns the server behind `mock_provider`, which waits for `latency` before answering each
+    /// request, and returns a provider connected to it along with a counter of the requests it has
+    /// received and the requests themselves.
+    async fn spawn_mock_provider(
+        respond: fn(&str, usize) -> Result<Value, Value>,
+        latency: Duration,
+    ) -> (Arc<dyn ChainProvider>, Arc<AtomicUsize>, Arc<Mutex<Vec<Value>>>) {
+        let respond = move |method: &str, call, _: &Value| respond(method, call);
+        spawn_mock_server(respond, latency).await
+    }
+
+    /// Spawns a JSON-RPC server that waits for `latency`, then answers each request with
+    /// `respond(method, call, params)`.
+    async fn spawn_mock_server(
+        respond: impl Fn(&str, usize, &Value) -> Result<Value, Value> + Send + Sync + 'static,
+        latency: Duration,
+    ) -> (Arc<dyn ChainProvider>, Arc<AtomicUsize>, Arc<Mutex<Vec<Value>>>) {
+        let respond = Arc::new(respond);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler_calls = calls.clone();
+        let handler_requests = requests.clone();
+        let handler = move |Json(request): Json<Value>| async move {
+            tokio::time::sleep(latency).await;
+            let call = handler_calls.fetch_add(1, Ordering::SeqCst);
+            handler_requests.lock().unwrap().push(request.clone());
+            let id = &request["id"];
+            let method = request["method"].as_str().unwrap_or_default();
+            Json(match respond(method, call, &request["params"]) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+            })
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route("/", post(handler));
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+        let provider: Arc<dyn ChainProvider> = Arc::new(Provider::<Http>::try_from(url).unwrap());
+        (provider, calls, requests)
+    }
+
+    /// Responds to the RPC calls made when looking up the creation data for `MOCK_CONTRACT`, which
+    /// was deployed with a regular CREATE transaction.
+    fn mock_creation_rpc(method: &str, _call: usize) -> Result<Value, Value> {
+        creation_rpc_response(method, MOCK_CREATION_CODE)
+    }
+
+    /// Responds like `mock_creation_rpc`, but with `input` as the creation transaction's input.
+    fn creation_rpc_response(method: &str, input: &str) -> Result<Value, Value> {
+        let tx_hash = format!("{:?}", TxHash::repeat_byte(0x22));
+        let block_hash = format!("{:?}", TxHash::repeat_byte(0x33));
+        let deployer = format!("{:?}", Address::repeat_byte(0x44));
+        Ok(match method {
+            "eth_getTransactionByHash" => json!({
+                "hash": tx_hash, "nonce": "0x0", "blockHash": block_hash, "blockNumber": "0x10",
+                "transactionIndex": "0x0", "from": deployer, "to": null, "value": "0x0",
+                "gasPrice": "0x1", "gas": "0x5208", "input": input, "v": "0x1b",
+                "r": "0x1", "s": "0x1", "type": "0x0"
+            }),
+            "eth_getTransactionReceipt" => json!({
+                "transactionHash": tx_hash, "transactionIndex": "0x0", "blockHash": block_hash,
+                "blockNumber": "0x10", "from": deployer, "to": null, "cumulativeGasUsed": "0x1",
+                "gasUsed": "0x1", "contractAddress": MOCK_CONTRACT, "logs": [], "status": "0x1",
+                "logsBloom": format!("0x{}", "0".repeat(512)), "type": "0x0",
+                "effectiveGasPrice": "0x1"
+            }),
+            _ => Value::Null,
+        })
+    }
+
+    /// Responds to the RPC calls made when scanning for the transactions of
+    /// `Address::repeat_byte(0x44)`, which sent nonce 0 in block 10 and nonces 1 and 2 in block 42,
+    /// out of 100 blocks.
+    fn mock_nonce_rpc(method: &str, params: &Value) -> Result<Value, Value> {
+        let deployer = Address::repeat_byte(0x44);
+        let block = |index: usize| {
+            let number = params[index].as_str().unwrap_or_default().trim_start_matches("0x");
+            u64::from_str_radix(number, 16).unwrap_or_default()
+        };
+        Ok(match method {
+            "eth_blockNumber" => json!("0x64"),
+            "eth_getTransactionCount" => {
+                let count: u64 = match block(1) {
+                    0..=9 => 0,
+                    10..=41 => 1,
+                    _ => 3,
+                };
+                json!(format!("{count:#x}"))
+            }
+            "eth_getBlockByNumber" => {
+                let tx = |from, nonce: u64, hash: u8| Transaction {
+                    hash: TxHash::repeat_byte(hash),
+                    from,
+                    nonce: nonce.into(),
+                    ..Default::default()
+                };
+                let transactions = match block(0) {
+                    10 => vec![tx(deployer, 0, 0x10)],
+                    42 => vec![
+                        tx(Address::repeat_byte(0x55), 1, 0x41),
+                        tx(deployer, 1, 0x42),
+                        tx(deployer, 2, 0x43),
+                    ],
+                    _ => Vec::new(),
+                };
+                let number = Some(block(0).into());
+                serde_json::to_value(Block { number, transactions, ..Default::default() })
+                    .map_err(|e| json!({ "code": -32603, "message": e.to_string() }))?
+            }
+            _ => Value::Null,
+        })
+    }
+
+    /// The contract whose creation data is returned by `mock_creation_rpc`.
+    const MOCK_CONTRACT: &str = "0x8d56e3e001132d84488dbacdbb01afb8c3171242";
+
+    /// The creation code returned by `mock_creation_rpc`.
+    const MOCK_CREATION_CODE: &str = "0x6080604052348015600f57600080fd5b50";
+
+    #[tokio::test]
+    async fn test_creation_code_cache() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (provider, calls) = mock_provider(mock_creation_rpc).await;
+        let multi_chain_provider = MultiChainProvider {
+            chains: vec![Chain::Goerli],
+            providers: HashMap::from([(Chain::Goerli, provider)]),
+            creation_cache: Some(Arc::new(CreationCache::new(Duration::from_secs(60)))),
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let address = Address::from_str(MOCK_CONTRACT)?;
+        let tx_hashes = Some(HashMap::from([(Chain::Goerli, TxHash::repeat_byte(0x22))]));
+
+        // The first lookup fetches the transaction and its receipt.
+        let first = multi_chain_provider.get_creation_code(address, tx_hashes.clone()).await?;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let first = first.responses[&Chain::Goerli].clone().ok_or("Creation data not found")?;
+        assert_eq!(first.creation_code, Bytes::from_str(MOCK_CREATION_CODE)?);
+        assert_eq!(block_id_number(&first.block), Some(16));
+
+        // The second lookup is served from the cache without any new RPC calls.
+        let second = multi_chain_provider.get_creation_code(address, tx_hashes).await?;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let second = second.responses[&Chain::Goerli].clone().ok_or("Creation data not found")?;
+        assert_eq!(second.creation_code, first.creation_code);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_creation_tx_hashes() -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    {
+        let (provider, calls) = params_mock_provider(mock_nonce_rpc).await;
+        let deployer = Address::repeat_byte(0x44);
+        let retry = RetryConfig::default();
+
+        // Each transaction is found in the block that included it, including when the deployer sent
+        // several transactions in the same block.
+        let find = |nonce| find_transaction_by_nonce(&provider, deployer, nonce, &retry);
+        assert_eq!(find(0).await?, Some(TxHash::repeat_byte(0x10)));
+        assert_eq!(find(1).await?, Some(TxHash::repeat_byte(0x42)));
+        assert_eq!(find(2).await?, Some(TxHash::repeat_byte(0x43)));
+
+        // A nonce the deployer hasn't used yet is found not to exist without searching.
+        let before = calls.load(Ordering::SeqCst);
+        assert_eq!(find(3).await?, None);
+        assert_eq!(calls.load(Ordering::SeqCst) - before, 2);
+
+        // Chains that already have a creation transaction aren't scanned.
+        let multi_chain_provider = MultiChainProvider {
+            chains: vec![Chain::Goerli, Chain::Sepolia],
+            providers: HashMap::from([
+                (Chain::Goerli, provider.clone()),
+                (Chain::Sepolia, provider.clone()),
+            ]),
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let known = HashMap::from([(Chain::Sepolia, TxHash::repeat_byte(0x99))]);
+        let tx_hashes =
+            multi_chain_provider.find_creation_tx_hashes(deployer, 1, Some(known)).await;
+        assert_eq!(
+            tx_hashes,
+            HashMap::from([
+                (Chain::Goerli, TxHash::repeat_byte(0x42)),
+                (Chain::Sepolia, TxHash::repeat_byte(0x99)),
+            ])
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_creation_code_lookups_are_concurrent(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Each lookup makes two requests, so the slowest chain takes 1.2s, and looking up each
+        // chain in turn would take 2.4s.
+        let latencies = [
+            (Chain::Mainnet, Duration::from_millis(200)),
+            (Chain::Goerli, Duration::from_millis(400)),
+            (Chain::Sepolia, Duration::from_millis(600)),
+        ];
+        let mut providers = HashMap::new();
+        for (chain, latency) in latencies {
+            providers.insert(chain, mock_provider_with_latency(mock_creation_rpc, latency).await.0);
+        }
+        let multi_chain_provider = MultiChainProvider {
+            chains: latencies.iter().map(|(chain, _)| *chain).collect(),
+            providers,
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let tx_hashes = latencies.iter().map(|(chain, _)| (*chain, TxHash::repeat_byte(0x22)));
+
+        let start = Instant::now();
+        let creation = multi_chain_provider
+            .get_creation_code(Address::from_str(MOCK_CONTRACT)?, Some(tx_hashes.collect()))
+            .await?;
+        let elapsed = start.elapsed();
+        assert_eq!(creation.iter_entries().count(), 3);
+        assert!(elapsed >= Duration::from_millis(1200));
+        assert!(elapsed < Duration::from_millis(2000), "lookups ran sequentially: {elapsed:?}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_providers() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        /// Responds to `eth_blockNumber` with block 16.
+        fn block_number_rpc(method: &str, _: usize) -> Result<Value, Value> {
+            match method {
+                "eth_blockNumber" => Ok(json!("0x10")),
+                _ => Ok(Value::Null),
+            }
+        }
+
+        let timeout = Duration::from_millis(500);
+        let (mainnet, _) = mock_provider(block_number_rpc).await;
+        let (goerli, _) = mock_provider(block_number_rpc).await;
+        let mut provider = MultiChainProvider {
+            chains: vec![Chain::Mainnet, Chain::Goerli],
+            providers: HashMap::from([(Chain::Mainnet, mainnet), (Chain::Goerli, goerli)]),
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+
+        // All providers are healthy.
+        let statuses = provider.check_providers(timeout).await;
+        assert_eq!(statuses[&Chain::Mainnet], Ok(16));
+        assert_eq!(statuses[&Chain::Goerli], Ok(16));
+
+        // A provider that's down, and one that doesn't respond in time, are both reported.
+        let (slow, _) = mock_provider_with_latency(block_number_rpc, Duration::from_secs(5)).await;
+        let down: Arc<dyn ChainProvider> =
+            Arc::new(Provider::<Http>::try_from("http://127.0.0.1:1")?);
+        provider.providers.insert(Chain::Goerli, down);
+        provider.chains.push(Chain::Sepolia);
+        provider.providers.insert(Chain::Sepolia, slow);
+
+        let start = Instant::now();
+        let statuses = provider.check_providers(timeout).await;
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(statuses[&Chain::Mainnet], Ok(16));
+        assert!(statuses[&Chain::Goerli].is_err());
+        assert_eq!(statuses[&Chain::Sepolia], Err("Timed out after 500ms".to_string()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scoped_to() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (mainnet, mainnet_calls) = mock_provider(mock_creation_rpc).await;
+        let (goerli, goerli_calls) = mock_provider(mock_creation_rpc).await;
+        let multi_chain_provider = MultiChainProvider {
+            chains: vec![Chain::Mainnet, Chain::Goerli],
+            providers: HashMap::from([(Chain::Mainnet, mainnet), (Chain::Goerli, goerli)]),
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let address = Address::from_str(MOCK_CONTRACT)?;
+        let tx_hashes = HashMap::from([
+            (Chain::Mainnet, TxHash::repeat_byte(0x22)),
+            (Chain::Goerli, TxHash::repeat_byte(0x22)),
+        ]);
+
+        // A scoped provider only queries its chain.
+        let scoped = multi_chain_provider.scoped_to(Chain::Mainnet).ok_or("Mainnet not found")?;
+        assert_eq!(scoped.chains, vec![Chain::Mainnet]);
+        let creation = scoped.get_creation_code(address, Some(tx_hashes.clone())).await?;
+        assert_eq!(creation.responses.keys().collect::<Vec<_>>(), vec![&Chain::Mainnet]);
+        assert!(mainnet_calls.load(Ordering::SeqCst) > 0);
+        assert_eq!(goerli_calls.load(Ordering::SeqCst), 0);
+
+        // Without scoping, every chain is queried.
+        let creation = multi_chain_provider.get_creation_code(address, Some(tx_hashes)).await?;
+        assert_eq!(creation.iter_entries().count(), 2);
+        assert!(goerli_calls.load(Ordering::SeqCst) > 0);
+
+        // Chains that aren't configured can't be scoped to.
+        assert!(multi_chain_provider.scoped_to(Chain::Optimism).is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_explorer_creation_fallback(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // A mock Etherscan API that returns the creation transaction from `mock_creation_rpc`.
+        let explorer_calls = Arc::new(Mutex::new(Vec::new()));
+        let handler_calls = explorer_calls.clone();
+        let handler = move |Query(query): Query<HashMap<String, String>>| {
+            let handler_calls = handler_calls.clone();
+            async move {
+                handler_calls.lock().unwrap().push(query);
+                let tx_hash = format!("{:?}", TxHash::repeat_byte(0x22));
+                Json(json!({
+                    "status": "1",
+                    "message": "OK",
+                    "result": [{
+                        "contractAddress": MOCK_CONTRACT,
+                        "contractCreator": format!("{:?}", Address::repeat_byte(0x44)),
+                        "txHash": tx_hash,
+                    }]
+                }))
+            }
+        };
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let explorer_url = format!("http://{}/api", listener.local_addr()?);
+        let app = Router::new().route("/api", get(handler));
+        tokio::spawn(axum::Server::from_tcp(listener)?.serve(app.into_make_service()));
+
+        let (provider, _) = mock_provider(mock_creation_rpc).await;
+        let explorer = ExplorerConfig { api_url: explorer_url, api_key: Some("secret".into()) };
+        let multi_chain_provider = MultiChainProvider {
+            chains: vec![Chain::Goerli],
+            providers: HashMap::from([(Chain::Goerli, provider)]),
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::from([(Chain::Goerli, explorer)]),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let address = Address::from_str(MOCK_CONTRACT)?;
+
+        // Without a transaction hash, the creation transaction is found with the explorer.
+        let creation = multi_chain_provider.get_creation_code(address, None).await?;
+        let creation = creation.responses[&Chain::Goerli].clone().ok_or("Creation not found")?;
+        assert_eq!(creation.tx_hash, TxHash::repeat_byte(0x22));
+        assert_eq!(creation.creation_code, Bytes::from_str(MOCK_CREATION_CODE)?);
+        assert_eq!(block_id_number(&creation.block), Some(16));
+        {
+            let calls = explorer_calls.lock().unwrap();
+            assert_eq!(calls.len(), 1);
+            assert_eq!(calls[0]["module"], "contract");
+            assert_eq!(calls[0]["action"], "getcontractcreation");
+            assert_eq!(calls[0]["contractaddresses"], MOCK_CONTRACT);
+            assert_eq!(calls[0]["apikey"], "secret");
+        }
+
+        // The explorer is only used when the RPC path yields nothing.
+        let tx_hashes = HashMap::from([(Chain::Goerli, TxHash::repeat_byte(0x22))]);
+        multi_chain_provider.get_creation_code(address, Some(tx_hashes)).await?;
+        assert_eq!(explorer_calls.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_suspicious_creation_code() -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    {
+        let (provider, _) = mock_provider(|method, _| creation_rpc_response(method, "0x")).await;
+        let (archive, archive_calls) = mock_provider(mock_creation_rpc).await;
+        let mut multi_chain_provider = MultiChainProvider {
+            chains: vec![Chain::Goerli],
+            providers: HashMap::from([(Chain::Goerli, provider)]),
+            creation_cache: Some(Arc::new(CreationCache::new(Duration::from_secs(60)))),
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let address = Address::from_str(MOCK_CONTRACT)?;
+        let tx_hashes = Some(HashMap::from([(Chain::Goerli, TxHash::repeat_byte(0x22))]));
+
+        // Empty creation code is an error rather than the creation code not being found.
+        let error = multi_chain_provider
+            .get_creation_code(address, tx_hashes.clone())
+            .await
+            .err()
+            .ok_or("Empty creation code was accepted")?;
+        assert!(error.to_string().contains("goerli: creation code in transaction"), "{error}");
+        assert!(error.to_string().contains("is empty"), "{error}");
+
+        // With an archive node configured, the lookup is retried against it.
+        multi_chain_provider.archive_providers = HashMap::from([(Chain::Goerli, archive)]);
+        let creation = multi_chain_provider.get_creation_code(address, tx_hashes).await?;
+        let creation = creation.responses[&Chain::Goerli].clone().ok_or("Creation not found")?;
+        assert_eq!(creation.creation_code, Bytes::from_str(MOCK_CREATION_CODE)?);
+        assert!(archive_calls.load(Ordering::SeqCst) > 0);
+
+        // Truncated creation code is also rejected.
+        let creation = ContractCreation {
+            tx_hash: TxHash::repeat_byte(0x22),
+            block: BlockId::from(16),
+            creation_code: Bytes::from_str("0x6080604052")?,
+        };
+        let reason = check_creation_code(&creation).err().ok_or("Truncated code was accepted")?;
+        assert!(reason.ends_with("is only 5 bytes"), "{reason}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_trace_factory_creation() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        /// Responds to the RPC calls made when looking up the creation data for `MOCK_CONTRACT`,
+        /// which was deployed with CREATE2 by a factory that isn't one of the known factories.
+        fn factory_rpc(method: &str, _call: usize) -> Result<Value, Value> {
+            let tx_hash = format!("{:?}", TxHash::repeat_byte(0x22));
+            let block_hash = format!("{:?}", TxHash::repeat_byte(0x33));
+            let deployer = format!("{:?}", Address::repeat_byte(0x44));
+            let factory = format!("{:?}", Address::repeat_byte(0x55));
+            Ok(match method {
+                "eth_getTransactionByHash" => json!({
+                    "hash": tx_hash, "nonce": "0x0", "blockHash": block_hash,
+                    "blockNumber": "0x10", "transactionIndex": "0x0", "from": deployer,
+                    "to": factory, "value": "0x0", "gasPrice": "0x1", "gas": "0x5208",
+                    "input": "0xdeadbeef", "v": "0x1b", "r": "0x1", "s": "0x1", "type": "0x0"
+                }),
+                "trace_transaction" => json!([
+                    {
+                        "action": {
+                            "callType": "call", "from": deployer, "to": factory, "gas": "0x1",
+                            "input": "0xdeadbeef", "value": "0x0"
+                        },
+                        "blockHash": block_hash, "blockNumber": 16,
+                        "result": { "gasUsed": "0x1", "output": "0x" },
+                        "subtraces": 1, "traceAddress": [], "transactionHash": tx_hash,
+                        "transactionPosition": 0, "type": "call"
+                    },
+                    {
+                        "action": {
+                            "from": factory, "gas": "0x1", "init": "0x6080604052", "value": "0x0"
+                        },
+                        "blockHash": block_hash, "blockNumber": 16,
+                        "result": { "address": MOCK_CONTRACT, "code": "0x6080", "gasUsed": "0x1" },
+                        "subtraces": 0, "traceAddress": [0], "transactionHash": tx_hash,
+                        "transactionPosition": 0, "type": "create"
+                    }
+                ]),
+                _ => Value::Null,
+            })
+        }
+
+        let (provider, calls) = mock_provider(factory_rpc).await;
+        let address = Address::from_str(MOCK_CONTRACT)?;
+        let tx_hash = TxHash::repeat_byte(0x22);
+        let retry = RetryConfig::default();
+
+        // With tracing enabled, the init code is found in the factory's internal creation.
+        let creation = find_creation_data(&provider, address, Some(tx_hash), &retry, true).await?;
+        assert_eq!(creation.creation_code, Bytes::from_str("0x6080604052")?);
+        assert_eq!(block_id_number(&creation.block), Some(16));
+
+        // With tracing disabled, contracts from unknown factories aren't supported.
+        let calls_before = calls.load(Ordering::SeqCst);
+        assert!(find_creation_data(&provider, address, Some(tx_hash), &retry, false)
+            .await
+            .is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), calls_before + 1, "transaction was traced");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_from_config() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        /// Responds to `eth_getCode` with non-empty code.
+        fn mock_code_rpc(method: &str, _call: usize) -> Result<Value, Value> {
+            match method {
+                "eth_getCode" => Ok(json!("0x6080")),
+                _ => Ok(Value::Null),
+            }
+        }
+
+        let (mainnet, mainnet_calls) = mock_provider(mock_code_rpc).await;
+        let (goerli, goerli_calls) = mock_provider(mock_code_rpc).await;
+        let config = format!(
+            r#"
+            [[chains]]
+            chain = "mainnet"
+            rpc_url = "{}"
+
+            [[chains]]
+            chain = "goerli"
+            rpc_url = "{}"
+            auth_header = "Bearer secret"
+            "#,
+            mainnet.url(),
+            goerli.url()
+        );
+        let configs = toml::from_str::<ChainsConfigFile>(&config)?.chains;
+        assert_eq!(configs[1].auth_header.as_deref(), Some("Bearer secret"));
+
+        let provider = MultiChainProvider::from_config(configs.clone())?;
+        assert_eq!(provider.chains, vec![Chain::Mainnet, Chain::Goerli]);
+
+        // Both chains' providers are reachable.
+        let address = Address::from_str(MOCK_CONTRACT)?;
+        let code = provider.get_deployed_code(address, None).await.map_err(|e| e.to_string())?;
+        assert_eq!(code.iter_entries().count(), 2);
+        assert_eq!(mainnet_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(goerli_calls.load(Ordering::SeqCst), 1);
+
+        // Configuring the same chain twice is an error.
+        let duplicated = vec![configs[0].clone(), configs[0].clone()];
+        assert!(MultiChainProvider::from_config(duplicated).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_deployed_code_with_duplicate_contracts() -> Result<(), Box<dyn Error>> {
+        // Two contracts with identical code, and one with different code.
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\n")?;
+        let write_artifact = |name: &str, code: &str| -> Result<(), Box<dyn Error>> {
+            let dir = project_dir.path().join("out").join(format!("{name}.sol"));
+            fs::create_dir_all(&dir)?;
+            let artifact = json!({
+                "abi": [],
+                "bytecode": { "object": code, "linkReferences": {} },
+                "deployedBytecode": {
+                    "object": code,
+                    "linkReferences": {},
+                    "immutableReferences": {}
+                },
+                "metadata": {
+                    "settings": { "metadata": { "bytecodeHash": "none", "appendCBOR": false } },
+                    "sources": { format!("src/{name}.sol"): {} }
+                }
+            });
+            fs::write(dir.join(format!("{name}.json")), artifact.to_string())?;
+            Ok(())
+        };
+        write_artifact("Token", "0x6080604052")?;
+        write_artifact("TokenCopy", "0x6080604052")?;
+        write_artifact("Other", "0x6080604000")?;
+        // A test contract with the same name and code as `Token`.
+        let test_dir = project_dir.path().join("out").join("Token.t.sol");
+        fs::create_dir_all(&test_dir)?;
+        let token = fs::read_to_string(project_dir.path().join("out/Token.sol/Token.json"))?;
+        fs::write(test_dir.join("Token.json"), token)?;
+
+        let project = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+        let provider = MultiChainProvider {
+            chains: vec![Chain::Goerli],
+            providers: HashMap::from([(
+                Chain::Goerli,
+                Arc::new(Provider::<Http>::try_from("http://127.0.0.1:1")?)
+                    as Arc<dyn ChainProvider>,
+            )]),
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let deployed_code = ChainResponse {
+            responses: HashMap::from([(Chain::Goerli, Some(Bytes::from_str("0x6080604052")?))]),
+            ..Default::default()
+        };
+
+        // Both copies match, so the match is ambiguous.
+        let matches = provider.compare_deployed_code(&project, &deployed_code, None);
+        let contract_match = matches.responses[&Chain::Goerli].as_ref().ok_or("No match")?;
+        let mut matched = vec![contract_match.artifact.clone()];
+        matched.extend(contract_match.candidates.clone());
+        let mut names: Vec<_> = matched.iter().filter_map(|path| path.file_stem()).collect();
+        names.sort();
+        assert_eq!(names, vec!["Token", "TokenCopy"]);
+
+        // Naming the contract only compares its artifacts.
+        let matches = provider.compare_deployed_code(&project, &deployed_code, Some("TokenCopy"));
+        let contract_match = matches.responses[&Chain::Goerli].as_ref().ok_or("No match")?;
+        assert_eq!(contract_match.artifact.file_stem().ok_or("No file stem")?, "TokenCopy");
+        assert!(contract_match.candidates.is_empty());
+
+        // Test contracts aren't compared, so they don't collide with the contract they test.
+        let matches = provider.compare_deployed_code(&project, &deployed_code, Some("Token"));
+        let contract_match = matches.responses[&Chain::Goerli].as_ref().ok_or("No match")?;
+        assert_eq!(contract_match.artifact, project_dir.path().join("out/Token.sol/Token.json"));
+        assert!(contract_match.candidates.is_empty());
+
+        // A name that doesn't match the code finds nothing.
+        let matches = provider.compare_deployed_code(&project, &deployed_code, Some("Other"));
+        assert!(matches.is_all_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_creation_code_outcomes() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\n")?;
+        let write_artifact = |name: &str, code: &str| -> Result<(), Box<dyn Error>> {
+            let dir = project_dir.path().join("out").join(format!("{name}.sol"));
+            fs::create_dir_all(&dir)?;
+            let artifact = json!({
+                "abi": [],
+                "bytecode": { "object": code, "linkReferences": {} },
+                "deployedBytecode": {
+                    "object": code,
+                    "linkReferences": {},
+                    "immutableReferences": {}
+                },
+                "metadata": {
+                    "settings": { "metadata": { "bytecodeHash": "none", "appendCBOR": false } },
+                    "sources": { format!("src/{name}.sol"): {} }
+                }
+            });
+            fs::write(dir.join(format!("{name}.json")), artifact.to_string())?;
+            Ok(())
+        };
+        write_artifact("Token", "0x6080604052")?;
+        write_artifact("Other", "0x6080604000")?;
+        write_artifact("Longer", "0x60806040526000")?;
+
+        let project = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+        let provider = MultiChainProvider {
+            chains: vec![Chain::Goerli],
+            providers: HashMap::from([(
+                Chain::Goerli,
+                Arc::new(Provider::<Http>::try_from("http://127.0.0.1:1")?)
+                    as Arc<dyn ChainProvider>,
+            )]),
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let creation_data = ChainResponse {
+            responses: HashMap::from([(
+                Chain::Goerli,
+                Some(ContractCreation {
+                    tx_hash: TxHash::repeat_byte(0x11),
+                    block: BlockId::from(1),
+                    creation_code: Bytes::from_str("0x6080604052")?,
+                }),
+            )]),
+            ..Default::default()
+        };
+
+        let (matches, outcomes) = provider.compare_creation_code(&project, &creation_data, None);
+        assert!(matches.responses[&Chain::Goerli].is_some());
+        let outcome_of = |name: &str| {
+            outcomes
+                .iter()
+                .find(|o| o.artifact.file_stem().map_or(false, |s| s == name))
+                .map(|o| o.outcome)
+        };
+        assert_eq!(outcome_of("Token"), Some(MatchOutcome::Matched));
+        assert_eq!(outcome_of("Other"), Some(MatchOutcome::PrefixMismatchAt(4)));
+        assert_eq!(
+            outcome_of("Longer"),
+            Some(MatchOutcome::LengthMismatch { found: 7, expected: 5 })
+        );
+
+        // The closest artifact that didn't match is the best near-miss.
+        let near_miss = best_near_miss(&outcomes).ok_or("No near-miss")?;
+        assert_eq!(near_miss.artifact.file_stem().ok_or("No file stem")?, "Other");
+        assert_eq!(near_miss.chain, Chain::Goerli);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_zksync_deployed_code() -> Result<(), Box<dyn Error>> {
+        // zksolc artifacts, whose code is a whole, odd number of 32-byte words.
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\n")?;
+        let token_code = format!("0x{}", "00".repeat(31) + "01" + &"02".repeat(64));
+        let other_code = format!("0x{}", "00".repeat(31) + "01" + &"03".repeat(64));
+        for (name, code) in [("Token", &token_code), ("Other", &other_code)] {
+            let dir = project_dir.path().join("out").join(format!("{name}.sol"));
+            fs::create_dir_all(&dir)?;
+            let artifact = json!({
+                "abi": [],
+                "bytecode": { "object": code, "linkReferences": {} },
+                "deployedBytecode": {
+                    "object": code,
+                    "linkReferences": {},
+                    "immutableReferences": {}
+                },
+                "metadata": {
+                    "settings": { "metadata": { "bytecodeHash": "none" } },
+                    "sources": { format!("src/{name}.sol"): {} }
+                }
+            });
+            fs::write(dir.join(format!("{name}.json")), artifact.to_string())?;
+        }
+
+        let project = Foundry::new(project_dir.path()).map_err(|e| e.to_string())?;
+        let provider = MultiChainProvider {
+            chains: vec![Chain::ZkSync],
+            providers: HashMap::from([(
+                Chain::ZkSync,
+                Arc::new(Provider::<Http>::try_from("http://127.0.0.1:1")?)
+                    as Arc<dyn ChainProvider>,
+            )]),
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+
+        // The artifact whose bytecode hash matches the on-chain code hash is found.
+        let deployed_code = ChainResponse {
+            responses: HashMap::from([(Chain::ZkSync, Some(Bytes::from_str(&token_code)?))]),
+            ..Default::default()
+        };
+        let matches = provider.compare_deployed_code(&project, &deployed_code, None);
+        let contract_match = matches.responses[&Chain::ZkSync].as_ref().ok_or("No match")?;
+        assert_eq!(contract_match.artifact.file_stem().ok_or("No file stem")?, "Token");
+        assert_eq!(contract_match.match_type, MatchType::Full);
+        assert_eq!(contract_match.comparator, Some("zksync-code-hash"));
+        assert!(contract_match.candidates.is_empty());
+
+        // zkSync creation transactions don't contain the creation code, so they're never compared.
+        let creation_data = ChainResponse {
+            responses: HashMap::from([(
+                Chain::ZkSync,
+                Some(ContractCreation {
+                    tx_hash: TxHash::repeat_byte(0x22),
+                    block: BlockId::from(16),
+                    creation_code: Bytes::from_str(&token_code)?,
+                }),
+            )]),
+            ..Default::default()
+        };
+        let (matches, outcomes) = provider.compare_creation_code(&project, &creation_data, None);
+        assert!(matches.is_all_none());
+        assert!(outcomes.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deployed_code_without_code(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        /// Responds to `eth_getCode` with empty code, as for an EOA or an undeployed contract.
+        fn no_code_rpc(method: &str, _call: usize) -> Result<Value, Value> {
+            match method {
+                "eth_getCode" => Ok(json!("0x")),
+                _ => Ok(Value::Null),
+            }
+        }
+        /// Fails every request.
+        fn failing_rpc(_method: &str, _call: usize) -> Result<Value, Value> {
+            Err(json!({ "code": -32000, "message": "internal error" }))
+        }
+
+        let (no_code, _) = mock_provider(no_code_rpc).await;
+        let (failing, _) = mock_provider(failing_rpc).await;
+        let provider = |providers: Vec<(Chain, Arc<dyn ChainProvider>)>| MultiChainProvider {
+            chains: providers.iter().map(|(chain, _)| *chain).collect(),
+            providers: providers.into_iter().collect(),
+            creation_cache: None,
+            retry_config: RetryConfig { max_attempts: 1, ..RetryConfig::default() },
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let address = Address::from_str(MOCK_CONTRACT)?;
+
+        // Empty code on every chain is a successful response with no code.
+        let code = provider(vec![(Chain::Goerli, no_code.clone())])
+            .get_deployed_code(address, None)
+            .await
+            .map_err(|e| e.to_string())?;
+        assert!(code.is_all_none());
+
+        // If a chain couldn't be checked, the contract may be deployed there, so that's an error.
+        let result = provider(vec![(Chain::Goerli, no_code), (Chain::Sepolia, failing)])
+            .get_deployed_code(address, None)
+            .await;
+        assert!(result.map_err(|e| e.to_string()).unwrap_err().contains("sepolia"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deployed_code_at_block(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        /// Responds to `eth_getCode` with some code.
+        fn code_rpc(method: &str, _call: usize) -> Result<Value, Value> {
+            match method {
+                "eth_getCode" => Ok(json!("0x6080")),
+                _ => Ok(Value::Null),
+            }
+        }
+
+        let (rpc, requests) = recording_mock_provider(code_rpc).await;
+        let provider = MultiChainProvider {
+            chains: vec![Chain::Goerli],
+            providers: HashMap::from([(Chain::Goerli, rpc)]),
+            creation_cache: None,
+            retry_config: RetryConfig { max_attempts: 1, ..RetryConfig::default() },
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        let address = Address::from_str(MOCK_CONTRACT)?;
+
+        // The code is fetched at the requested block, and at the latest block by default.
+        provider.get_deployed_code(address, Some(16)).await.map_err(|e| e.to_string())?;
+        provider.get_deployed_code(address, None).await.map_err(|e| e.to_string())?;
+        let requests = requests.lock().unwrap();
+        let blocks: Vec<&Value> = requests.iter().map(|request| &request["params"][1]).collect();
+        assert_eq!(blocks, vec![&json!("0x10"), &json!("latest")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_chain_message() {
+        // Blast is called out by name or chain ID, since ethers has no `Chain` for it.
+        for chain in [json!("blast"), json!("Blast"), json!(81457), json!("81457")] {
+            let message = unsupported_chain_message(&chain);
+            assert!(message.starts_with("Chain blast (81457) is not supported yet"), "{message}");
+        }
+        assert!(UNSUPPORTED_CHAINS.iter().all(|(id, _)| Chain::try_from(*id).is_err()));
+
+        // Other chains are simply unsupported.
+        assert_eq!(
+            unsupported_chain_message(&json!("mystery")),
+            "Chain \"mystery\" is not supported"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_deployed_code_queries_new_chains(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        /// Responds to `eth_getCode` with some code.
+        fn code_rpc(method: &str, _call: usize) -> Result<Value, Value> {
+            match method {
+                "eth_getCode" => Ok(json!("0x6080")),
+                _ => Ok(Value::Null),
+            }
+        }
+
+        let chains = [Chain::Base, Chain::Arbitrum, Chain::Polygon];
+        let mut providers = HashMap::new();
+        let mut requests = Vec::new();
+        for chain in chains {
+            let (rpc, chain_requests) = recording_mock_provider(code_rpc).await;
+            providers.insert(chain, rpc);
+            requests.push((chain, chain_requests));
+        }
+        let provider = MultiChainProvider {
+            chains: chains.to_vec(),
+            providers,
+            creation_cache: None,
+            retry_config: RetryConfig { max_attempts: 1, ..RetryConfig::default() },
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+        assert!(chains.iter().all(|chain| default_chains().contains(chain)));
+
+        // Each chain is queried, and the code found on each is returned.
+        let address = Address::from_str(MOCK_CONTRACT)?;
+        let code = provider.get_deployed_code(address, None).await.map_err(|e| e.to_string())?;
+        for (chain, chain_requests) in requests {
+            let chain_requests = chain_requests.lock().unwrap();
+            assert_eq!(chain_requests.len(), 1, "{chain} was not queried once");
+            assert_eq!(chain_requests[0]["method"], "eth_getCode");
+            assert_eq!(code.responses[&chain], Some(Bytes::from_str("0x6080")?));
+        }
+
+        // zkSync creation transactions don't contain EVM creation code.
+        assert!(has_evm_creation_code(Chain::Base));
+        assert!(!has_evm_creation_code(Chain::ZkSync));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_retry() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        /// Rate limits the first two requests, then returns code.
+        fn flaky_rpc(_method: &str, call: usize) -> Result<Value, Value> {
+            match call {
+                0 | 1 => Err(json!({ "code": 429, "message": "Too many requests" })),
+                _ => Ok(json!("0x6080")),
+            }
+        }
+
+        /// Rejects every request with a permanent error.
+        fn invalid_params_rpc(_method: &str, _call: usize) -> Result<Value, Value> {
+            Err(json!({ "code": -32602, "message": "invalid argument 0: hex string has length 3" }))
+        }
+
+        let address = Address::from_str(MOCK_CONTRACT)?;
+        let retry = RetryConfig {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(10),
+        };
+
+        // Transient errors are retried until the call succeeds.
+        let (provider, calls) = mock_provider(flaky_rpc).await;
+        let code = with_retry(&retry, || provider.get_code(address, None)).await?;
+        assert_eq!(code, Bytes::from_str("0x6080")?);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Retries stop once the maximum number of attempts is reached.
+        let (provider, calls) = mock_provider(flaky_rpc).await;
+        let retry_once = RetryConfig { max_attempts: 2, ..retry };
+        assert!(with_retry(&retry_once, || provider.get_code(address, None)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Permanent errors are not retried.
+        let (provider, calls) = mock_provider(invalid_params_rpc).await;
+        assert!(with_retry(&retry, || provider.get_code(address, None)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimal_proxy_implementation() -> Result<(), Box<dyn std::error::Error>> {
+        // The runtime code OpenZeppelin's `Clones` library deploys for a clone of the mainnet USDC
+        // implementation.
+        let implementation = Address::from_str("0xa2327a938febf5fec13bacfb16ae10ecbc4cbdcf")?;
+        let clone = Bytes::from_str(
+            "0x363d3d373d3d3d363d73a2327a938febf5fec13bacfb16ae10ecbc4cbdcf5af43d82803e903d91602b57fd5bf3",
+        )?;
+        assert_eq!(minimal_proxy_implementation(&clone), Some(implementation));
+
+        // Code that only resembles a minimal proxy isn't one.
+        assert_eq!(minimal_proxy_implementation(&clone[..clone.len() - 1]), None);
+        assert_eq!(minimal_proxy_implementation(&[clone.as_ref(), &[0x00]].concat()), None);
+        assert_eq!(minimal_proxy_implementation(&Bytes::from_str("0x6080604052")?), None);
+        assert_eq!(minimal_proxy_implementation(&[]), None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_implementation_address(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        /// Mimics a transparent proxy, whose EIP-1967 implementation slot holds a padded address.
+        fn proxy_rpc(method: &str, _: usize) -> Result<Value, Value> {
+            match method {
+                "eth_getStorageAt" => Ok(json!(format!("0x{}{}", "0".repeat(24), "ab".repeat(20)))),
+                _ => Ok(Value::Null),
+            }
+        }
+
+        /// Mimics a regular contract, whose EIP-1967 implementation slot is empty.
+        fn empty_slot_rpc(method: &str, _: usize) -> Result<Value, Value> {
+            match method {
+                "eth_getStorageAt" => Ok(json!(format!("0x{}", "0".repeat(64)))),
+                _ => Ok(Value::Null),
+            }
+        }
+
+        let (mainnet, _) = mock_provider(proxy_rpc).await;
+        let (goerli, _) = mock_provider(empty_slot_rpc).await;
+        let provider = MultiChainProvider {
+            chains: vec![Chain::Mainnet, Chain::Goerli],
+            providers: HashMap::from([(Chain::Mainnet, mainnet), (Chain::Goerli, goerli)]),
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        };
+
+        let implementations =
+            provider.get_implementation_address(Address::from_str(MOCK_CONTRACT)?).await;
+        assert_eq!(implementations.responses[&Chain::Mainnet], Some(Address::repeat_byte(0xab)));
+        assert_eq!(implementations.responses[&Chain::Goerli], None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_creation_cache_expiry() -> Result<(), Box<dyn std::error::Error>> {
+        let address = Address::from_str(MOCK_CONTRACT)?;
+        let creation = ContractCreation {
+            tx_hash: TxHash::zero(),
+            block: BlockId::from(1u64),
+            creation_code: Bytes::from_str("0x6080")?,
+        };
+
+        let cache = CreationCache::new(Duration::from_secs(60));
+        cache.insert(Chain::Goerli, address, creation.clone());
+        assert!(cache.get(Chain::Goerli, address).is_some());
+        assert!(cache.get(Chain::Mainnet, address).is_none());
+
+        // Entries are not returned once they expire.
+        let cache = CreationCache::new(Duration::ZERO);
+        cache.insert(Chain::Goerli, address, creation);
+        assert!(cache.get(Chain::Goerli, address).is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_block_number() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let retry = RetryConfig::default();
+
+        // Block numbers are returned as-is without querying the provider.
+        let offline_provider = Provider::<Http>::try_from("http://127.0.0.1:1")?;
+        let block = BlockId::from(12345u64);
+        assert_eq!(resolve_block_number(&offline_provider, block, &retry).await?, 12345);
+
+        // Block hashes are resolved to the block's number.
+        let provider = get_provider();
+        let tx_hash =
+            TxHash::from_str("0x005c7b8f0ccbd49ff8892ec0ef27058b79d9a1ed6592faaa44699cccce1aa350")?;
+        let tx = provider.get_transaction(tx_hash).await?.ok_or("Transaction not found")?;
+        let block_hash = tx.block_hash.ok_or("Missing block hash")?;
+        let expected = tx.block_number.ok_or("Missing block number")?.as_u64();
+        assert_eq!(
+            resolve_block_number(&provider, BlockId::Hash(block_hash), &retry).await?,
+            expected
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_find_creation_data() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let provider = get_provider();
+        let retry = RetryConfig::default();
 
         #[rustfmt::skip]
         let test_cases = vec![
@@ -443,8 +2449,14 @@ mod tests {
             async move {
                 let contract_addr = Address::from_str(contract)?;
                 let expected_tx_hash = TxHash::from_str(tx_hash)?;
-                let creation_data =
-                    find_creation_data(&provider, contract_addr, Some(expected_tx_hash)).await?;
+                let creation_data = find_creation_data(
+                    &provider,
+                    contract_addr,
+                    Some(expected_tx_hash),
+                    &retry,
+                    false,
+                )
+                .await?;
                 assert_eq!(creation_data.tx_hash, expected_tx_hash, "{name}");
                 Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
             }
@@ -453,4 +2465,125 @@ mod tests {
         try_join_all(tasks).await?;
         Ok(())
     }
+
+    /// Returns a provider that queries each chain through its mock, without caching, explorers,
+    /// or archive nodes.
+    fn mock_chains(mocks: Vec<(Chain, MockChainProvider)>) -> MultiChainProvider {
+        let providers = mocks
+            .into_iter()
+            .map(|(chain, mock)| (chain, Arc::new(mock) as Arc<dyn ChainProvider>))
+            .collect::<HashMap<_, _>>();
+        MultiChainProvider {
+            chains: providers.keys().copied().collect(),
+            providers,
+            creation_cache: None,
+            retry_config: RetryConfig::default(),
+            explorers: HashMap::new(),
+            tracing_chains: HashSet::new(),
+            archive_providers: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queries_with_mock_chains() -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    {
+        let address = Address::from_str(MOCK_CONTRACT)?;
+        let implementation = Address::repeat_byte(0x11);
+        let deployer = Address::repeat_byte(0x44);
+        let runtime_code = Bytes::from_str("0x6080")?;
+        let creation_code = Bytes::from_str(MOCK_CREATION_CODE)?;
+        let (create_hash, call_hash) = (TxHash::repeat_byte(0x10), TxHash::repeat_byte(0x20));
+
+        // The deployer creates the contract with its first transaction, in block 7, and then calls
+        // it in block 9.
+        let create = Transaction {
+            hash: create_hash,
+            from: deployer,
+            nonce: 0u64.into(),
+            block_number: Some(7u64.into()),
+            input: creation_code.clone(),
+            ..Default::default()
+        };
+        let call = Transaction {
+            hash: call_hash,
+            from: deployer,
+            to: Some(address),
+            nonce: 1u64.into(),
+            block_number: Some(9u64.into()),
+            ..Default::default()
+        };
+        let receipt = TransactionReceipt { contract_address: Some(address), ..Default::default() };
+        let slot = H256::from_str(EIP1967_IMPLEMENTATION_SLOT)?;
+        let goerli = MockChainProvider {
+            block_number: 10,
+            code: HashMap::from([(address, runtime_code.clone())]),
+            storage: HashMap::from([((address, slot), H256::from(implementation))]),
+            blocks: HashMap::from([(7, vec![create]), (9, vec![call])]),
+            receipts: HashMap::from([(create_hash, receipt)]),
+            ..Default::default()
+        };
+        let mainnet =
+            MockChainProvider { error: Some("node is down".into()), ..Default::default() };
+        let provider = mock_chains(vec![(Chain::Goerli, goerli), (Chain::Mainnet, mainnet)]);
+
+        // Transactions are found by their sender and nonce on the chains that are up.
+        let tx_hashes = provider.find_creation_tx_hashes(deployer, 0, None).await;
+        assert_eq!(tx_hashes, HashMap::from([(Chain::Goerli, create_hash)]));
+        let tx_hashes = provider.find_creation_tx_hashes(deployer, 1, None).await;
+        assert_eq!(tx_hashes, HashMap::from([(Chain::Goerli, call_hash)]));
+        assert!(provider.find_creation_tx_hashes(deployer, 2, None).await.is_empty());
+
+        // The creation code is read from the creation transaction, and the chain that's down has
+        // none rather than failing the lookup, with its error kept so it's known to be
+        // inconclusive.
+        let tx_hashes =
+            HashMap::from([(Chain::Goerli, create_hash), (Chain::Mainnet, create_hash)]);
+        let creation = provider.get_creation_code(address, Some(tx_hashes)).await?;
+        let goerli_creation = creation.responses[&Chain::Goerli].as_ref().ok_or("No creation")?;
+        assert_eq!(goerli_creation.creation_code, creation_code);
+        assert_eq!(block_id_number(&goerli_creation.block), Some(7));
+        assert!(creation.responses[&Chain::Mainnet].is_none());
+        assert_eq!(creation.chain_errors.keys().collect::<Vec<_>>(), vec![&Chain::Mainnet]);
+        assert!(creation.chain_errors[&Chain::Mainnet].contains("node is down"));
+
+        // A transaction that didn't create the contract isn't mistaken for its creation, and isn't
+        // an error either.
+        let tx_hashes = HashMap::from([(Chain::Goerli, call_hash)]);
+        let creation = provider.get_creation_code(address, Some(tx_hashes)).await?;
+        assert!(creation.is_all_none());
+        assert!(creation.chain_errors.is_empty());
+
+        // Deployed code and proxy implementations are read from the chain that's up.
+        let code = provider.get_deployed_code(address, None).await.map_err(|e| e.to_string())?;
+        assert_eq!(code.responses[&Chain::Goerli], Some(runtime_code.clone()));
+        assert_eq!(code.responses[&Chain::Mainnet], None);
+        assert!(code.chain_errors[&Chain::Mainnet].contains("node is down"));
+        let implementations = provider.get_implementation_address(address).await;
+        assert_eq!(implementations.responses[&Chain::Goerli], Some(implementation));
+        assert_eq!(implementations.responses[&Chain::Mainnet], None);
+        assert!(implementations.chain_errors[&Chain::Mainnet].contains("node is down"));
+        assert!(!implementations.chain_errors.contains_key(&Chain::Goerli));
+
+        // Code at those addresses keeps the errors from looking them up, and adds the errors from
+        // fetching the code.
+        let implementation_code = provider.get_deployed_code_at(&implementations).await;
+        assert_eq!(implementation_code.responses[&Chain::Mainnet], None);
+        assert!(implementation_code.chain_errors[&Chain::Mainnet].contains("implementation slot"));
+        let addresses = |chain| ChainResponse {
+            responses: HashMap::from([(chain, Some(address))]),
+            ..Default::default()
+        };
+        let implementation_code = provider.get_deployed_code_at(&addresses(Chain::Goerli)).await;
+        assert_eq!(implementation_code.responses[&Chain::Goerli], Some(runtime_code));
+        assert!(implementation_code.chain_errors.is_empty());
+        let implementation_code = provider.get_deployed_code_at(&addresses(Chain::Mainnet)).await;
+        let error = &implementation_code.chain_errors[&Chain::Mainnet];
+        assert!(error.contains("implementation's code"), "{error}");
+
+        // The chain that's down is reported as unhealthy.
+        let statuses = provider.check_providers(Duration::from_secs(1)).await;
+        assert_eq!(statuses[&Chain::Goerli], Ok(10));
+        assert!(matches!(&statuses[&Chain::Mainnet], Err(e) if e.contains("node is down")));
+        Ok(())
+    }
 }
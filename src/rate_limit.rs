@@ -0,0 +1,136 @@
+use crate::{auth::ApiKeyIdentity, routes::VerifyError};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Environment variable used to override how many requests to rate limited routes each client may
+/// make per minute. Setting it to `0` disables rate limiting.
+const RATE_LIMIT_ENV_VAR: &str = "RATE_LIMIT_PER_MINUTE";
+
+/// Default number of requests to rate limited routes each client may make per minute.
+const DEFAULT_RATE_LIMIT: u32 = 60;
+
+/// The length of each rate limit window.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Number of tracked clients above which clients whose window has ended are forgotten, so memory
+/// use stays bounded.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Limits how many requests each client can make per window, so a single client can't saturate
+/// the server. Clients are identified by the rate limit bucket of their API key if they sent one,
+/// and by their IP address otherwise.
+pub struct RateLimiter {
+    /// The number of requests each client may make per window. `0` disables rate limiting.
+    limit: u32,
+    /// The length of each window.
+    window: Duration,
+    /// When each client's current window started, and how many requests they've made in it.
+    clients: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Returns a rate limiter allowing `limit` requests per client per `window`.
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self { limit, window, clients: Mutex::default() }
+    }
+
+    /// Returns a rate limiter allowing the number of requests per minute read from
+    /// `RATE_LIMIT_PER_MINUTE`, or 60 if it's not set to a valid number.
+    pub fn from_env() -> Self {
+        let limit = std::env::var(RATE_LIMIT_ENV_VAR)
+            .ok()
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(DEFAULT_RATE_LIMIT);
+        Self::new(limit, RATE_LIMIT_WINDOW)
+    }
+
+    /// Returns true if requests are rate limited.
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    /// Records a request from `client` at `now`. Returns `Ok` if the request is within the limit,
+    /// or how long until the client's window ends otherwise.
+    fn check(&self, client: &str, now: Instant) -> Result<(), Duration> {
+        let Ok(mut clients) = self.clients.lock() else { return Ok(()) };
+        if clients.len() >= MAX_TRACKED_CLIENTS {
+            clients.retain(|_, (started_at, _)| now.duration_since(*started_at) < self.window);
+        }
+
+        let (started_at, count) = clients.entry(client.to_string()).or_insert((now, 0));
+        if now.duration_since(*started_at) >= self.window {
+            *started_at = now;
+            *count = 0;
+        }
+        if *count >= self.limit {
+            return Err(self.window.saturating_sub(now.duration_since(*started_at)))
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+/// Middleware that rejects requests from clients that exceeded their rate limit with a `429` and
+/// a `Retry-After` header. This must run after `require_api_key`, so clients with an API key are
+/// limited by their key rather than their IP address.
+pub async fn rate_limit<B>(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if !limiter.is_enabled() {
+        return next.run(request).await
+    }
+    let client = match request.extensions().get::<ApiKeyIdentity>() {
+        Some(identity) => format!("key:{}", identity.bucket),
+        None => format!("ip:{}", address.ip()),
+    };
+    match limiter.check(&client, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            tracing::warn!(%client, "Rate limit exceeded");
+            // Round up, so clients that wait this long are always allowed again.
+            let retry_after_secs =
+                retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            let msg = format!("Too many requests, try again in {retry_after_secs}s");
+            let mut response = VerifyError::RateLimited(msg).into_response();
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        // Each client gets its own limit.
+        assert_eq!(limiter.check("ip:1.2.3.4", start), Ok(()));
+        assert_eq!(limiter.check("ip:1.2.3.4", start + Duration::from_secs(10)), Ok(()));
+        assert_eq!(limiter.check("key:partner", start + Duration::from_secs(10)), Ok(()));
+
+        // Once it's exceeded, requests are rejected until the window ends.
+        let retry_after = limiter.check("ip:1.2.3.4", start + Duration::from_secs(15));
+        assert_eq!(retry_after, Err(Duration::from_secs(45)));
+        assert_eq!(limiter.check("ip:1.2.3.4", start + Duration::from_secs(60)), Ok(()));
+
+        // A limit of zero disables rate limiting.
+        assert!(!RateLimiter::new(0, Duration::from_secs(60)).is_enabled());
+    }
+}
@@ -0,0 +1,321 @@
+use crate::routes::temp_root;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
+use walkdir::WalkDir;
+
+/// Environment variable used to set the directory cloned repositories are cached in.
+const REPO_CACHE_DIR_ENV_VAR: &str = "REPO_CACHE_DIR";
+
+/// Environment variable used to override the maximum total size, in megabytes, of the cached
+/// repositories.
+const REPO_CACHE_MAX_MB_ENV_VAR: &str = "REPO_CACHE_MAX_MB";
+
+/// Default maximum total size of the cached repositories, in megabytes.
+const DEFAULT_REPO_CACHE_MAX_MB: u64 = 4 * 1024;
+
+/// Name of the directory, under the temp root, that repositories are cached in unless
+/// `REPO_CACHE_DIR` is set. This doesn't start with the prefix of temporary build directories, so
+/// the cache isn't swept up with them.
+const DEFAULT_REPO_CACHE_DIR: &str = "cove_repo_cache";
+
+/// Prefix of the directories entries are written to before they're moved into place, and moved to
+/// before they're removed, so a partially written or removed entry is never read.
+const STAGING_PREFIX: &str = ".staging-";
+
+/// The cache shared by every verification, created from the environment on first use.
+static REPO_CACHE: OnceLock<Option<Arc<RepoCache>>> = OnceLock::new();
+
+/// Identifies a cached repository. Entries are keyed by the full hash of the commit that was
+/// checked out rather than the branch or tag requested, so an entry's contents never change.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RepoCacheKey {
+    /// The normalized URL of the repository.
+    pub repo_url: String,
+    /// The full hash of the commit that was checked out.
+    pub commit: String,
+    /// Whether the repository's submodules were cloned too, since that changes the working tree.
+    pub submodules: bool,
+}
+
+impl RepoCacheKey {
+    /// Returns the name of the entry's directory, which is a hash of the key so URLs don't need to
+    /// be escaped.
+    fn dir_name(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.repo_url.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.commit.to_ascii_lowercase().as_bytes());
+        hasher.update([self.submodules as u8]);
+        hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+/// A cached repository.
+#[derive(Debug, Clone, Copy)]
+struct CacheEntry {
+    /// The total size of the entry's files, in bytes.
+    size: u64,
+    /// When the entry was last restored or inserted.
+    last_used: SystemTime,
+}
+
+/// An on-disk cache of cloned repositories with their commit checked out, so verifying another
+/// contract from the same commit copies the working tree instead of cloning it again. When the
+/// cache grows past its maximum size, the least recently used entries are removed. Entries are
+/// never modified once they're inserted, and are copied to a scratch directory to be built.
+pub struct RepoCache {
+    /// The directory entries are stored in, one subdirectory per entry.
+    dir: PathBuf,
+    /// The maximum total size of the entries, in bytes.
+    max_bytes: u64,
+    /// Each entry, keyed by its directory name.
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl RepoCache {
+    /// Returns a cache stored in `dir`, creating it if it doesn't exist. Entries left by earlier
+    /// runs are kept, ordered by when they were inserted, and anything left mid-write is removed.
+    pub fn new(dir: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let mut entries = HashMap::new();
+        for entry in fs::read_dir(&dir)?.filter_map(Result::ok) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(STAGING_PREFIX) {
+                let _ = fs::remove_dir_all(entry.path());
+                continue
+            }
+            let last_used = entry.metadata()?.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.insert(name, CacheEntry { size: dir_size(&entry.path()), last_used });
+        }
+        let cache = Self { dir, max_bytes, entries: Mutex::new(entries) };
+        cache.evict();
+        Ok(cache)
+    }
+
+    /// Returns a cache stored in `REPO_CACHE_DIR`, or a directory under the temp root if it isn't
+    /// set, holding up to `REPO_CACHE_MAX_MB` megabytes. Returns `None` if `REPO_CACHE_MAX_MB` is
+    /// 0, which disables the cache, or the directory can't be created.
+    pub fn from_env() -> Option<Self> {
+        let megabytes = std::env::var(REPO_CACHE_MAX_MB_ENV_VAR)
+            .ok()
+            .and_then(|mb| mb.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_REPO_CACHE_MAX_MB);
+        if megabytes == 0 {
+            return None
+        }
+        let dir = std::env::var_os(REPO_CACHE_DIR_ENV_VAR)
+            .filter(|dir| !dir.is_empty())
+            .map_or_else(|| temp_root().join(DEFAULT_REPO_CACHE_DIR), PathBuf::from);
+        match Self::new(dir.clone(), megabytes.saturating_mul(1024 * 1024)) {
+            Ok(cache) => Some(cache),
+            Err(error) => {
+                tracing::warn!(dir = %dir.display(), %error, "Failed to open the repository cache");
+                None
+            }
+        }
+    }
+
+    /// Returns the cache shared by every verification, or `None` if it's disabled.
+    pub fn global() -> Option<Arc<Self>> {
+        REPO_CACHE.get_or_init(|| Self::from_env().map(Arc::new)).clone()
+    }
+
+    /// Copies the cached repository for `key` into `dest`, which must be empty, and returns true.
+    /// Returns false if there's no entry for `key`, or it couldn't be copied, such as because it
+    /// was evicted while being copied, in which case `dest` is emptied again.
+    pub fn restore(&self, key: &RepoCacheKey, dest: &Path) -> bool {
+        let name = key.dir_name();
+        {
+            let Ok(mut entries) = self.entries.lock() else { return false };
+            let Some(entry) = entries.get_mut(&name) else { return false };
+            entry.last_used = SystemTime::now();
+        }
+        match copy_tree(&self.dir.join(&name), dest) {
+            Ok(()) => true,
+            Err(error) => {
+                tracing::warn!(%error, "Failed to restore the repository from the cache");
+                clear_dir(dest);
+                false
+            }
+        }
+    }
+
+    /// Caches a copy of the repository at `src`, which must have `key`'s commit checked out and
+    /// not have been built yet. Existing entries are left as they are, and if the cache is then
+    /// larger than its maximum size the least recently used entries are removed. Failures are
+    /// logged rather than returned, since the cache is only an optimization.
+    pub fn insert(&self, key: &RepoCacheKey, src: &Path) {
+        let name = key.dir_name();
+        if self.entries.lock().map_or(true, |entries| entries.contains_key(&name)) {
+            return
+        }
+        let size = dir_size(src);
+        if size > self.max_bytes {
+            return
+        }
+
+        // The copy is written to a staging directory first, so other requests never see a
+        // partial entry.
+        let staging = self.dir.join(format!("{STAGING_PREFIX}{name}-{}", uuid::Uuid::new_v4()));
+        let result = fs::create_dir(&staging)
+            .and_then(|_| copy_tree(src, &staging))
+            .and_then(|_| fs::rename(&staging, self.dir.join(&name)));
+        if let Err(error) = result {
+            // Another request may have inserted the same entry first, which is fine.
+            tracing::debug!(%error, "Failed to cache the repository");
+            let _ = fs::remove_dir_all(&staging);
+            return
+        }
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(name, CacheEntry { size, last_used: SystemTime::now() });
+        }
+        self.evict();
+    }
+
+    /// Removes the least recently used entries until the cache is no larger than its maximum size.
+    fn evict(&self) {
+        let Ok(mut entries) = self.entries.lock() else { return };
+        let mut total: u64 = entries.values().map(|entry| entry.size).sum();
+        while total > self.max_bytes {
+            let Some((name, entry)) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(name, entry)| (name.clone(), *entry))
+            else {
+                break
+            };
+            entries.remove(&name);
+            total -= entry.size;
+            // The entry is moved aside before it's removed, so it disappears all at once.
+            let evicted = self.dir.join(format!("{STAGING_PREFIX}{name}"));
+            if fs::rename(self.dir.join(&name), &evicted).is_ok() {
+                let _ = fs::remove_dir_all(evicted);
+            }
+            tracing::debug!(entry = %name, size = entry.size, "Evicted repository from the cache");
+        }
+    }
+}
+
+/// Returns the total size, in bytes, of the files under `dir`.
+fn dir_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Recursively copies the contents of `from` into `to`, which must exist. Symlinks are copied as
+/// symlinks rather than followed, so links out of the repository aren't copied into it.
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(from).min_depth(1) {
+        let entry = entry?;
+        let relative =
+            entry.path().strip_prefix(from).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let target = to.join(relative);
+        let file_type = entry.file_type();
+        if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        } else if file_type.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes everything in `dir`, leaving the directory itself.
+fn clear_dir(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let _ = if path.is_dir() && !path.is_symlink() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(commit: &str) -> RepoCacheKey {
+        RepoCacheKey {
+            repo_url: "https://github.com/ScopeLift/cove-test-repo".to_string(),
+            commit: commit.to_string(),
+            submodules: true,
+        }
+    }
+
+    /// Creates a repository-like directory with a file of `size` bytes and a symlink.
+    fn working_tree(size: usize) -> Result<tempfile::TempDir, Box<dyn std::error::Error>> {
+        let tree = tempfile::tempdir()?;
+        fs::create_dir_all(tree.path().join("src"))?;
+        fs::write(tree.path().join("src").join("Counter.sol"), vec![b'a'; size])?;
+        std::os::unix::fs::symlink("src/Counter.sol", tree.path().join("Link.sol"))?;
+        Ok(tree)
+    }
+
+    #[test]
+    fn test_restore_and_insert() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let cache = RepoCache::new(dir.path().join("cache"), 1000)?;
+        let tree = working_tree(100)?;
+
+        // Nothing is restored until the commit is inserted.
+        let dest = tempfile::tempdir()?;
+        assert!(!cache.restore(&key("aa"), dest.path()));
+        cache.insert(&key("aa"), tree.path());
+        assert!(cache.restore(&key("aa"), dest.path()));
+        assert_eq!(fs::read(dest.path().join("src").join("Counter.sol"))?, vec![b'a'; 100]);
+        assert_eq!(fs::read_link(dest.path().join("Link.sol"))?, Path::new("src/Counter.sol"));
+
+        // Entries are per commit and per submodule setting.
+        let dest = tempfile::tempdir()?;
+        assert!(!cache.restore(&key("bb"), dest.path()));
+        assert!(!cache.restore(&RepoCacheKey { submodules: false, ..key("aa") }, dest.path()));
+
+        // Entries outlive the cache that inserted them, so they survive restarts.
+        drop(cache);
+        let cache = RepoCache::new(dir.path().join("cache"), 1000)?;
+        assert!(cache.restore(&key("aa"), dest.path()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let cache = RepoCache::new(dir.path().to_path_buf(), 250)?;
+        let tree = working_tree(100)?;
+        let restored = |commit| -> Result<bool, Box<dyn std::error::Error>> {
+            Ok(cache.restore(&key(commit), tempfile::tempdir()?.path()))
+        };
+
+        cache.insert(&key("aa"), tree.path());
+        cache.insert(&key("bb"), tree.path());
+        // Using the older entry makes the other the least recently used.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(restored("aa")?);
+        cache.insert(&key("cc"), tree.path());
+        assert!(restored("aa")?);
+        assert!(!restored("bb")?);
+        assert!(restored("cc")?);
+
+        // Repositories larger than the whole cache aren't cached.
+        cache.insert(&key("dd"), working_tree(300)?.path());
+        assert!(!restored("dd")?);
+        assert!(restored("aa")?);
+        Ok(())
+    }
+}
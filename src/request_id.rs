@@ -0,0 +1,117 @@
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::future::Future;
+use tracing::{Instrument, Span};
+use uuid::Uuid;
+
+/// Header that callers may send a request ID in, and that the ID is returned in.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Maximum length of a request ID accepted from a caller. Longer IDs are replaced with a generated
+/// one, so callers can't bloat every log line of their request.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    /// The ID of the request being handled, set for the duration of the request by
+    /// `set_request_id`.
+    static REQUEST_ID: String;
+}
+
+/// Returns the ID of the request being handled, or `None` outside of a request, such as in a
+/// background task that wasn't started with `in_current_request`.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Returns true if `id` can be used as a request ID: non-empty, at most `MAX_REQUEST_ID_LEN`
+/// characters, and only printable ASCII, so it's safe to log and echo back.
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Middleware that gives every request an ID, so the logs of each stage of a verification can be
+/// correlated. The caller's `X-Request-Id` is used if it's valid, otherwise a UUID is generated.
+/// The ID is set on the request's header, so the request's span records it, and is available to
+/// handlers through `current_request_id` while the request is handled.
+pub async fn set_request_id<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let caller_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string);
+    let request_id = caller_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    // The ID is printable ASCII, so it's always a valid header value.
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        request.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    REQUEST_ID.scope(request_id, next.run(request)).await
+}
+
+/// Returns the span a request is handled in, which records its ID so every event logged while
+/// handling it, including by background jobs started with `in_current_request`, includes the ID.
+pub fn make_request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+        request_id,
+        headers = ?request.headers(),
+    )
+}
+
+/// Wraps `future` so it runs in the current request's span and with its request ID, for background
+/// work started by a request, such as a verification job, which otherwise would lose both once it's
+/// spawned.
+pub fn in_current_request<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let request_id = current_request_id();
+    async move {
+        match request_id {
+            Some(request_id) => REQUEST_ID.scope(request_id, future).await,
+            None => future.await,
+        }
+    }
+    .instrument(Span::current())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_request_id() {
+        assert!(is_valid_request_id("3f2b8c1e-trace"));
+        assert!(is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN)));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+        assert!(!is_valid_request_id("has spaces"));
+        assert!(!is_valid_request_id("line\nbreak"));
+    }
+
+    #[tokio::test]
+    async fn test_in_current_request() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(current_request_id(), None);
+
+        // A job started by a request keeps its ID once it's spawned.
+        let job = REQUEST_ID
+            .scope("abc".to_string(), async { in_current_request(async { current_request_id() }) })
+            .await;
+        assert_eq!(tokio::spawn(job).await?.as_deref(), Some("abc"));
+
+        // Without it, spawned tasks don't have the ID.
+        let task = REQUEST_ID
+            .scope("abc".to_string(), async { tokio::spawn(async { current_request_id() }) })
+            .await;
+        assert_eq!(task.await?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use crate::startup::AppState;
+use axum::{extract::State, Json};
+use ethers::types::Chain;
+use serde::{Deserialize, Serialize};
+
+/// A chain the server verifies contracts on, returned by the `/supported-chains` route.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportedChain {
+    /// The chain's ID, e.g. `1` for mainnet.
+    pub chain_id: u64,
+    /// The name used to refer to the chain in requests, e.g. `mainnet` in `creationTxHashes`.
+    pub name: String,
+    /// A human-readable name for the chain, e.g. `Ethereum`.
+    pub display_name: String,
+}
+
+/// Returns the chains the server's provider is configured for, in the order they're configured.
+/// Since this reads the provider, it always matches the chains verification actually queries.
+pub async fn supported_chains(State(state): State<AppState>) -> Json<Vec<SupportedChain>> {
+    let chains = state
+        .provider
+        .chains
+        .iter()
+        .map(|chain| SupportedChain {
+            chain_id: u64::from(*chain),
+            name: chain.to_string(),
+            display_name: display_name(*chain),
+        })
+        .collect();
+    Json(chains)
+}
+
+/// Returns a human-readable name for the chain. Chains without a well-known name use their
+/// identifier with each word capitalized, e.g. `base-goerli` becomes `Base Goerli`.
+fn display_name(chain: Chain) -> String {
+    let name = match chain {
+        Chain::Mainnet => "Ethereum",
+        Chain::Arbitrum => "Arbitrum One",
+        Chain::XDai => "Gnosis",
+        Chain::Avalanche => "Avalanche C-Chain",
+        Chain::Polygon => "Polygon",
+        Chain::Optimism => "Optimism",
+        Chain::Base => "Base",
+        Chain::ZkSync => "zkSync Era",
+        _ => {
+            return chain
+                .to_string()
+                .split('-')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars.next().map_or_else(String::new, |first| {
+                        first.to_uppercase().chain(chars).collect::<String>()
+                    })
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cloner::GitCloner,
+        provider::{ChainConfig, MultiChainProvider},
+    };
+    use std::{sync::Arc, time::Duration};
+
+    #[tokio::test]
+    async fn test_supported_chains() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let config = |chain| ChainConfig {
+            chain,
+            rpc_url: "http://127.0.0.1:8545".to_string(),
+            auth_header: None,
+            explorer: None,
+            trace_creations: false,
+            archive_rpc_url: None,
+        };
+        let provider =
+            MultiChainProvider::from_config(vec![config(Chain::Optimism), config(Chain::Sepolia)])?;
+        let state = AppState {
+            jobs: Arc::default(),
+            progress: Arc::default(),
+            in_flight: Arc::default(),
+            running: Arc::default(),
+            provider: Arc::new(provider),
+            cloner: Arc::new(GitCloner),
+            store: None,
+            verification_limiter: Arc::default(),
+            webhooks: Arc::default(),
+            job_retention: Duration::from_secs(60),
+        };
+
+        // Only the configured chains are returned, in the order they're configured.
+        let Json(chains) = supported_chains(State(state)).await;
+        assert_eq!(
+            chains,
+            vec![
+                SupportedChain {
+                    chain_id: 10,
+                    name: "optimism".to_string(),
+                    display_name: "Optimism".to_string(),
+                },
+                SupportedChain {
+                    chain_id: 11155111,
+                    name: "sepolia".to_string(),
+                    display_name: "Sepolia".to_string(),
+                },
+            ]
+        );
+        Ok(())
+    }
+}
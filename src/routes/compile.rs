@@ -0,0 +1,195 @@
+use crate::{
+    build::{build_failure_message, BuildLimits},
+    cloner::CloneRequest,
+    frameworks::{foundry::Foundry, framework::Framework, hardhat::Hardhat},
+    routes::verify::{
+        build_attempts, build_project, create_temp_dir, detect_framework, read_artifact,
+        redact_url_credentials, temp_root, BuildAttempt, BuildConfig, BuildFramework, VerifyError,
+    },
+    startup::AppState,
+};
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Data that a caller provides to compile a repository without verifying it against a deployment.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompileData {
+    /// The URL of the repository.
+    repo_url: String,
+    /// The commit to compile. This can be a full or short commit hash, a branch, or a tag.
+    repo_commit: String,
+    /// Optional, the name of a contract. If provided, only artifacts for contracts with this name
+    /// are returned.
+    contract_name: Option<String>,
+    /// Optional, the build configuration for the project. If omitted, the framework is detected
+    /// and every Foundry profile is built.
+    build_config: Option<BuildConfig>,
+    /// Optional, whether to recursively clone the repository's git submodules. Defaults to `true`.
+    update_submodules: Option<bool>,
+}
+
+/// A contract artifact produced by compiling the repository.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompiledArtifact {
+    /// The name of the contract.
+    pub name: String,
+    /// The path to the artifact, relative to the repository root.
+    pub path: PathBuf,
+    /// The build profile that produced the artifact, such as a Foundry profile. `None` if the
+    /// framework doesn't have profiles.
+    pub profile: Option<String>,
+    /// The length of the creation code in bytes. This is zero for interfaces and abstract
+    /// contracts, and `None` if the creation code couldn't be read, such as when it must be linked
+    /// against libraries.
+    pub creation_code_length: Option<usize>,
+    /// The full version of the compiler that produced the artifact, if the artifact includes its
+    /// metadata.
+    pub compiler_version: Option<String>,
+}
+
+/// Data returned for a successful compilation.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompileResult {
+    /// The URL of the repository.
+    pub repo_url: String,
+    /// The commit that was requested.
+    pub repo_commit: String,
+    /// The full commit hash that `repo_commit` resolved to.
+    pub resolved_commit: String,
+    /// The artifacts produced by every build that succeeded, sorted by name.
+    pub artifacts: Vec<CompiledArtifact>,
+    /// Every build command that was run, such as one per Foundry profile, in the order they were
+    /// given, with whether each succeeded and how long it took.
+    pub build_attempts: Vec<BuildAttempt>,
+}
+
+/// Clones and compiles a repository, returning the artifacts it produces without comparing them to
+/// any on-chain code. This lets developers check a repository builds the expected contracts before
+/// deploying them.
+///
+/// # Arguments
+///
+/// * `state` - The shared application state, which clones the repository.
+/// * `json` - The repository and commit to compile, and optionally the contract to return.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a `Response` with the `CompileResult`, or a `VerifyError` if the
+/// repository couldn't be cloned or built.
+pub async fn compile(
+    State(state): State<AppState>,
+    json: Result<Json<CompileData>, JsonRejection>,
+) -> Result<Response, VerifyError> {
+    let Json(json) = json?;
+
+    let temp_dir = create_temp_dir(&temp_root())?;
+    let project_path = temp_dir.path();
+    let resolved_commit = state
+        .cloner
+        .clone_repo(CloneRequest {
+            repo_url: &json.repo_url,
+            repo_commit: &json.repo_commit,
+            dest: project_path,
+            update_submodules: json.update_submodules.unwrap_or(true),
+            auth_token: None,
+        })
+        .await?;
+
+    let (requested, build_hint) = match json.build_config {
+        Some(config) => (config.framework, config.build_hint),
+        None => (BuildFramework::Foundry, None),
+    };
+    let (mut artifacts, build_attempts) = match detect_framework(project_path, &requested)? {
+        BuildFramework::Foundry => {
+            let project = Foundry::new(project_path).map_err(|e| {
+                VerifyError::BadRequest(format!("Failed to create Foundry project: {}", e))
+            })?;
+            compile_project(&project, build_hint, project_path).await?
+        }
+        BuildFramework::Hardhat => {
+            let project = Hardhat::new(project_path).map_err(|e| {
+                VerifyError::BadRequest(format!("Failed to create Hardhat project: {}", e))
+            })?;
+            compile_project(&project, build_hint, project_path).await?
+        }
+        framework => {
+            let msg = format!("Unsupported framework: {:?}", framework);
+            return Err(VerifyError::UnsupportedFramework(msg))
+        }
+    };
+
+    if let Some(contract_name) = &json.contract_name {
+        artifacts.retain(|artifact| &artifact.name == contract_name);
+        if artifacts.is_empty() {
+            let msg = format!("The repository compiled, but no contract is named {contract_name}");
+            return Err(VerifyError::NotFound(msg))
+        }
+    }
+
+    let result = CompileResult {
+        repo_url: redact_url_credentials(&json.repo_url),
+        repo_commit: json.repo_commit,
+        resolved_commit,
+        artifacts,
+        build_attempts,
+    };
+    Ok((StatusCode::OK, Json(result)).into_response())
+}
+
+/// Builds the project and returns the artifacts of every build that succeeded, sorted by name,
+/// along with the build commands that were run. Returns an error if no build succeeded.
+async fn compile_project<F: Framework>(
+    project: &F,
+    build_hint: Option<String>,
+    project_path: &Path,
+) -> Result<(Vec<CompiledArtifact>, Vec<BuildAttempt>), VerifyError> {
+    let build_limits = BuildLimits::from_env();
+    let builds = build_project(project, build_hint, &build_limits, None).await?;
+
+    let mut artifacts = Vec::new();
+    let mut any_succeeded = false;
+    let mut failed_builds = Vec::new();
+    for (_, build_result, project) in &builds {
+        match build_result {
+            Ok(output) if output.success() => any_succeeded = true,
+            Ok(output) => {
+                failed_builds.push(output);
+                continue
+            }
+            Err(_) => continue,
+        }
+        let paths = project
+            .get_artifacts()
+            .map_err(|e| VerifyError::ArtifactParse(format!("Failed to list artifacts: {e}")))?;
+        for path in paths {
+            let Some(name) = path.file_stem().map(|stem| stem.to_string_lossy().to_string()) else {
+                continue
+            };
+            // Artifacts that can't be parsed, such as other JSON files in the output directory,
+            // aren't contracts.
+            let Ok(artifact) = read_artifact(&path) else { continue };
+            artifacts.push(CompiledArtifact {
+                name,
+                path: path.strip_prefix(project_path).unwrap_or(&path).to_path_buf(),
+                profile: project.profile(),
+                creation_code_length: F::get_artifact_creation_code(&path).ok().map(|c| c.len()),
+                compiler_version: artifact.metadata.map(|metadata| metadata.compiler.version),
+            });
+        }
+    }
+
+    if !any_succeeded {
+        return Err(VerifyError::BuildFailed(build_failure_message(failed_builds)))
+    }
+    artifacts.sort_by(|a, b| (&a.name, &a.path).cmp(&(&b.name, &b.path)));
+    Ok((artifacts, build_attempts(&builds)))
+}
@@ -1,4 +1,7 @@
-use crate::provider::{contract_runtime_code, provider_from_chain, provider_url_from_chain};
+use crate::{
+    provider::{contract_runtime_code, provider_from_chain, provider_url_from_chain},
+    request_id::current_request_id,
+};
 use axum::{
     extract::Query,
     http,
@@ -8,6 +11,7 @@ use axum::{
 use ethers::types::{Address, Bytes, Chain};
 use heimdall::decompile::DecompileBuilder;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::str::FromStr;
 use tempfile::TempDir;
 
@@ -46,11 +50,33 @@ pub struct DecompiledResponse {
     pub solidity: String,
 }
 
-/// Error response for when a contract is not found.
-#[derive(Serialize)]
+/// JSON body returned for all errors.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
-    /// Error message.
-    pub error: String,
+    /// A stable, machine-readable error code, such as `NO_BYTECODE_MATCH` or `CLONE_FAILED`.
+    /// Clients should branch on this rather than on the message.
+    pub code: String,
+    /// A human-readable description of the error.
+    pub message: String,
+    /// Optional structured data about the error.
+    pub details: Option<Value>,
+    /// The ID of the request that failed, the same as its `X-Request-Id` response header, to
+    /// quote when reporting the error. Omitted for errors that aren't a request's response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Returns an error response with the given code and message, and no details, for the request
+    /// being handled.
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+            details: None,
+            request_id: current_request_id(),
+        }
+    }
 }
 
 /// Response for the contract route.
@@ -92,13 +118,22 @@ pub async fn contract(Query(contract_query): Query<ContractQuery>) -> impl IntoR
 
     // Otherwise, decompile and return what we can.
     let provider = provider_from_chain(chain_id);
-    let runtime_code = contract_runtime_code(&provider, address).await;
-    println!("runtime_code: {:?}", runtime_code);
+    let runtime_code = match contract_runtime_code(&*provider, address, None).await {
+        Ok(runtime_code) => runtime_code,
+        Err(e) => {
+            return ApiResponse::Error(ErrorResponse::new(
+                "PROVIDER_ERROR",
+                format!("Failed to fetch runtime code on chain ID {chain_id}: {e}"),
+            ))
+        }
+    };
+    tracing::debug!(?chain_id, %address, %runtime_code, "Fetched runtime code");
 
     if runtime_code == Bytes::from_str("0x").unwrap() {
-        return ApiResponse::Error(ErrorResponse {
-            error: format!("No runtime code for contract address {address} on chain ID {chain_id}"),
-        })
+        return ApiResponse::Error(ErrorResponse::new(
+            "NO_DEPLOYED_CODE",
+            format!("No runtime code for contract address {address} on chain ID {chain_id}"),
+        ))
     }
 
     let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,41 @@
+use crate::{bytecode::BytecodeDiff, input::parse_hex_bytes, routes::verify::VerifyError};
+use axum::{
+    extract::rejection::JsonRejection,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+/// Data that a caller provides to locate where two pieces of bytecode diverge.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffData {
+    /// The hex-encoded bytecode found by compiling the source, such as the best-matching artifact
+    /// reported by a failed verification, with or without a `0x` prefix.
+    found_code: String,
+    /// The hex-encoded bytecode it was expected to match, such as the contract's on-chain code,
+    /// with or without a `0x` prefix.
+    expected_code: String,
+}
+
+/// Compares compiled bytecode against the bytecode it was expected to match and reports where they
+/// first diverge. This helps debug verifications that fail with no match, e.g. by showing whether
+/// only the metadata differs or the code itself does.
+///
+/// # Arguments
+///
+/// * `json` - The found and expected bytecode to compare.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a `Response` with the `BytecodeDiff`, or a `VerifyError` if either
+/// side isn't valid hex.
+pub async fn diff(json: Result<Json<DiffData>, JsonRejection>) -> Result<Response, VerifyError> {
+    let Json(json) = json?;
+    let found = parse_hex_bytes(&json.found_code)
+        .map_err(|e| VerifyError::BadRequest(format!("Invalid found code: {e}")))?;
+    let expected = parse_hex_bytes(&json.expected_code)
+        .map_err(|e| VerifyError::BadRequest(format!("Invalid expected code: {e}")))?;
+    Ok((StatusCode::OK, Json(BytecodeDiff::new(&found, &expected))).into_response())
+}
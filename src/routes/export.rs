@@ -0,0 +1,211 @@
+use crate::{
+    routes::verify::{jobs_lock_poisoned, JobStatus, SuccessfulVerification, VerifyError},
+    startup::AppState,
+};
+use axum::{
+    extract::{rejection::PathRejection, Path as RoutePath, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ethers::types::{Address, Chain};
+use reqwest::multipart::{Form, Part};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Environment variable used to configure the Sourcify server that verified contracts are
+/// exported to.
+const SOURCIFY_URL_ENV_VAR: &str = "SOURCIFY_URL";
+
+/// The Sourcify server used if `SOURCIFY_URL` is not set.
+const DEFAULT_SOURCIFY_URL: &str = "https://sourcify.dev/server";
+
+/// The files and contract details submitted to Sourcify for a verified contract.
+#[derive(Debug, Clone)]
+pub struct SourcifySubmission {
+    /// The address of the verified contract.
+    pub address: Address,
+    /// The chain the contract was verified on.
+    pub chain: Chain,
+    /// The contract's metadata JSON, exactly as emitted by the compiler.
+    pub metadata: String,
+    /// The path and content of each source file used to compile the contract.
+    pub sources: Vec<(String, String)>,
+}
+
+impl SourcifySubmission {
+    /// Returns the submission for a successful verification. Fails if the artifact didn't include
+    /// the raw metadata, since Sourcify can't verify without it.
+    pub fn from_verification(verification: &SuccessfulVerification) -> Result<Self, VerifyError> {
+        let metadata = verification.raw_metadata.clone().ok_or_else(|| {
+            VerifyError::BadRequest(
+                "The verified artifact has no raw metadata, so it can't be exported".to_string(),
+            )
+        })?;
+        let sources = verification
+            .sources
+            .iter()
+            .map(|source| (source.path.to_string_lossy().to_string(), source.content.clone()))
+            .collect();
+        Ok(Self {
+            address: verification.contract_address,
+            chain: verification.chain,
+            metadata,
+            sources,
+        })
+    }
+
+    /// Returns the multipart form expected by Sourcify's `/verify` endpoint: the address and
+    /// chain ID as text fields, followed by `metadata.json` and each source file as `files`.
+    fn into_form(self) -> Form {
+        let mut form = Form::new()
+            .text("address", format!("{:?}", self.address))
+            .text("chain", u64::from(self.chain).to_string())
+            .part("files", Part::text(self.metadata).file_name("metadata.json"));
+        for (path, content) in self.sources {
+            form = form.part("files", Part::text(content).file_name(path));
+        }
+        form
+    }
+}
+
+/// Submits the verified contract to the Sourcify server at `sourcify_url`, returning Sourcify's
+/// response status and body.
+pub async fn submit_to_sourcify(
+    sourcify_url: &str,
+    submission: SourcifySubmission,
+) -> Result<(StatusCode, String), VerifyError> {
+    let url = format!("{}/verify", sourcify_url.trim_end_matches('/'));
+    let response = reqwest::Client::new()
+        .post(&url)
+        .multipart(submission.into_form())
+        .send()
+        .await
+        .map_err(|e| VerifyError::ExportFailed(format!("Failed to submit to Sourcify: {e}")))?;
+
+    let status = StatusCode::from_u16(response.status().as_u16())
+        .map_err(|e| VerifyError::ExportFailed(e.to_string()))?;
+    let body = response
+        .text()
+        .await
+        .map_err(|e| VerifyError::ExportFailed(format!("Failed to read Sourcify response: {e}")))?;
+    Ok((status, body))
+}
+
+/// Exports the result of a successful verification job to Sourcify. The Sourcify server is set by
+/// the `SOURCIFY_URL` environment variable, and defaults to the public instance.
+///
+/// # Arguments
+///
+/// * `state` - The shared application state, which tracks verification jobs.
+/// * `job_id` - The ID of the verification job to export, as returned by `verify`.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a `Response` with Sourcify's status code and response body, or a
+/// `VerifyError` if the job doesn't exist, hasn't succeeded, or couldn't be submitted.
+pub async fn export_sourcify(
+    State(state): State<AppState>,
+    job_id: Result<RoutePath<Uuid>, PathRejection>,
+) -> Result<Response, VerifyError> {
+    let RoutePath(job_id) = job_id?;
+    // The lock is released before submitting, so other requests aren't blocked on Sourcify.
+    let submission = {
+        let jobs = state.jobs.lock().map_err(|_| jobs_lock_poisoned())?;
+        match jobs.get(&job_id).map(Arc::as_ref) {
+            Some(JobStatus::Success { result }) => SourcifySubmission::from_verification(result)?,
+            Some(JobStatus::Pending) => {
+                let msg = format!("Verification job {job_id} has not finished");
+                return Err(VerifyError::BadRequest(msg))
+            }
+            Some(JobStatus::Failed { .. }) => {
+                let msg = format!("Verification job {job_id} failed, so there's nothing to export");
+                return Err(VerifyError::BadRequest(msg))
+            }
+            None => {
+                return Err(VerifyError::NotFound(format!("No verification job with ID {job_id}")))
+            }
+        }
+    };
+
+    let sourcify_url = std::env::var(SOURCIFY_URL_ENV_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| DEFAULT_SOURCIFY_URL.to_string());
+    let (status, body) = submit_to_sourcify(&sourcify_url, submission).await?;
+
+    // Sourcify responds with JSON, but anything else is passed through as a string.
+    let body = serde_json::from_str(&body).unwrap_or(Value::String(body));
+    Ok((status, Json(body)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderMap, routing::post, Router};
+    use serde_json::json;
+    use std::{net::TcpListener, str::FromStr};
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_submit_to_sourcify() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // A mock Sourcify server that forwards each request's content type and body.
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let handler = move |headers: HeaderMap, body: String| {
+            let sender = sender.clone();
+            async move {
+                let content_type = headers["content-type"].to_str().unwrap_or_default().to_string();
+                let _ = sender.send((content_type, body));
+                Json(json!({ "result": [{ "status": "perfect" }] }))
+            }
+        };
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let app = Router::new().route("/verify", post(handler));
+        tokio::spawn(axum::Server::from_tcp(listener)?.serve(app.into_make_service()));
+
+        let submission = SourcifySubmission {
+            address: Address::from_str("0x8d56e3e001132d84488dbacdbb01afb8c3171242")?,
+            chain: Chain::Goerli,
+            metadata: r#"{"compiler":{"version":"0.8.19+commit.7dd6d404"}}"#.to_string(),
+            sources: vec![
+                ("src/Counter.sol".to_string(), "contract Counter {}".to_string()),
+                ("src/Math.sol".to_string(), "library Math {}".to_string()),
+            ],
+        };
+        let (status, body) = submit_to_sourcify(&format!("{url}/"), submission).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_str::<Value>(&body)?["result"][0]["status"], "perfect");
+
+        // Split the multipart body into its parts, each of which is headers then content.
+        let (content_type, body) = receiver.recv().await.ok_or("No request received")?;
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .ok_or("Not a multipart request")?;
+        let parts: Vec<(&str, &str)> = body
+            .split(&format!("--{boundary}"))
+            .filter_map(|part| part.trim_start_matches("\r\n").split_once("\r\n\r\n"))
+            .map(|(headers, content)| (headers, content.trim_end_matches("\r\n")))
+            .collect();
+
+        let expected = [
+            ("name=\"address\"", "0x8d56e3e001132d84488dbacdbb01afb8c3171242"),
+            ("name=\"chain\"", "5"),
+            (
+                "name=\"files\"; filename=\"metadata.json\"",
+                r#"{"compiler":{"version":"0.8.19+commit.7dd6d404"}}"#,
+            ),
+            ("name=\"files\"; filename=\"src/Counter.sol\"", "contract Counter {}"),
+            ("name=\"files\"; filename=\"src/Math.sol\"", "library Math {}"),
+        ];
+        assert_eq!(parts.len(), expected.len());
+        for ((headers, content), (expected_disposition, expected_content)) in
+            parts.iter().zip(expected)
+        {
+            assert!(headers.contains(expected_disposition), "{headers}");
+            assert_eq!(*content, expected_content);
+        }
+        Ok(())
+    }
+}
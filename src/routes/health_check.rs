@@ -1,6 +1,65 @@
-use axum::http;
+use crate::startup::AppState;
+use axum::{extract::State, http, Json};
+use ethers::types::Chain;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+/// How long each chain's provider has to respond before the server is considered not ready.
+const READY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The readiness of the server, returned by the `/ready` route.
+#[derive(Serialize, Deserialize)]
+pub struct Readiness {
+    /// True if every chain's provider is healthy.
+    pub ready: bool,
+    /// The status of each chain's provider.
+    pub chains: HashMap<Chain, ChainStatus>,
+}
+
+/// The status of a single chain's provider.
+#[derive(Serialize, Deserialize)]
+pub struct ChainStatus {
+    /// True if the provider returned the latest block number in time.
+    pub healthy: bool,
+    /// The latest block number, if the provider is healthy.
+    pub block_number: Option<u64>,
+    /// Why the provider is unhealthy, if it is.
+    pub error: Option<String>,
+}
 
 /// Health check route that returns a 200 OK status code if the server is running.
 pub async fn health_check() -> http::StatusCode {
     http::StatusCode::OK
 }
+
+/// Liveness probe that returns a 200 OK status code if the server is running. This is the same as
+/// `health_check`, but at the path load balancers conventionally expect.
+pub async fn health() -> http::StatusCode {
+    http::StatusCode::OK
+}
+
+/// Readiness probe that checks every chain's provider responds with the latest block number.
+/// Returns a 200 OK status code if they all do, otherwise a 503 Service Unavailable status code.
+/// Either way the body contains the status of each chain.
+pub async fn ready(State(state): State<AppState>) -> (http::StatusCode, Json<Readiness>) {
+    let statuses = state.provider.check_providers(READY_TIMEOUT).await;
+    let chains: HashMap<Chain, ChainStatus> = statuses
+        .into_iter()
+        .map(|(chain, status)| {
+            let status = match status {
+                Ok(block_number) => {
+                    ChainStatus { healthy: true, block_number: Some(block_number), error: None }
+                }
+                Err(error) => {
+                    ChainStatus { healthy: false, block_number: None, error: Some(error) }
+                }
+            };
+            (chain, status)
+        })
+        .collect();
+
+    let ready = chains.values().all(|status| status.healthy);
+    let status_code =
+        if ready { http::StatusCode::OK } else { http::StatusCode::SERVICE_UNAVAILABLE };
+    (status_code, Json(Readiness { ready, chains }))
+}
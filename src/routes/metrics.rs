@@ -0,0 +1,11 @@
+use crate::metrics::METRICS;
+use axum::http::header;
+
+/// The content type of the Prometheus text exposition format.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Returns the server's metrics in the Prometheus text exposition format, for scraping by
+/// Prometheus or any compatible collector.
+pub async fn metrics() -> ([(header::HeaderName, &'static str); 1], String) {
+    ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], METRICS.render())
+}
@@ -1,15 +1,52 @@
+/// Route for listing the chains the server verifies contracts on.
+pub mod chains;
+
+/// Route for compiling a repository without verifying it against a deployment.
+pub mod compile;
+
 /// This route is intended to return data for a contract that was previously verified, and for
-/// unverified contracts falls back to decompiling the bytecode with heimdall. However, Cove does
-/// not currently persist verification results in a database. As a result, this route will always
-/// decompile the bytecode with heimdall.
+/// unverified contracts falls back to decompiling the bytecode with heimdall. However, this route
+/// does not yet read the verifications saved by `verified`, so it will always decompile the
+/// bytecode with heimdall.
 pub mod contract;
 
-/// Health check route that returns a 200 OK status code if the server is running.
+/// Route for locating where compiled bytecode diverges from the bytecode it was expected to match.
+pub mod diff;
+
+/// Route for exporting verified contracts to Sourcify.
+pub mod export;
+
+/// Health check routes: liveness probes that return a 200 OK status code if the server is running,
+/// and a readiness probe that also checks each chain's provider is reachable.
 pub mod health_check;
 
+/// Route for exposing server metrics in the Prometheus text format.
+pub mod metrics;
+
+/// Route for returning the saved verification of a contract.
+pub mod verified;
+
 /// Route for verifying a contract.
 pub mod verify;
 
+/// Route for streaming the progress of a verification job over Server-Sent Events.
+pub mod verify_stream;
+
+/// Route for verifying several contracts deployed from the same repository and commit at once.
+pub mod verify_batch;
+
+/// Route for checking a repository compiles to given bytecode, without a deployed contract.
+pub mod verify_bytecode;
+
+pub use chains::*;
+pub use compile::*;
 pub use contract::*;
+pub use diff::*;
+pub use export::*;
 pub use health_check::*;
+pub use metrics::*;
+pub use verified::*;
 pub use verify::*;
+pub use verify_batch::*;
+pub use verify_bytecode::*;
+pub use verify_stream::*;
@@ -0,0 +1,96 @@
+use crate::{
+    routes::verify::{parse_contract_address, VerifyError},
+    startup::AppState,
+    storage::StoredVerification,
+};
+use axum::{
+    extract::{rejection::PathRejection, Path as RoutePath, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use ethers::types::Chain;
+use std::str::FromStr;
+
+/// Returns the saved verification of the contract at `address` on `chain`. The chain may be given
+/// by name, such as `mainnet`, or by ID, such as `1`. The response has an `ETag` of the commit the
+/// contract was verified against, and a `304 Not Modified` is returned if it matches the request's
+/// `If-None-Match` header.
+///
+/// # Arguments
+///
+/// * `state` - The shared application state, which holds the verification store.
+/// * `headers` - The request headers, which may include `If-None-Match`.
+/// * `path` - The chain and address of the contract.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a `Response` with the `StoredVerification`, or a `VerifyError` if
+/// the chain or address is invalid, or the contract hasn't been verified.
+pub async fn verified(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    path: Result<RoutePath<(String, String)>, PathRejection>,
+) -> Result<Response, VerifyError> {
+    let RoutePath((chain, address)) = path?;
+    let chain = parse_chain(&chain)?;
+    let address = parse_contract_address(&address)?;
+
+    let not_found = || VerifyError::NotFound(format!("No verification of {address:?} on {chain}"));
+    let Some(store) = state.store.as_ref() else { return Err(not_found()) };
+    let verification: StoredVerification = store
+        .get(chain, address)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to read verification from the database");
+            VerifyError::InternalServerError("Failed to read the verification".to_string())
+        })?
+        .ok_or_else(not_found)?;
+
+    let etag = format!("\"{}\"", verification.repo_commit);
+    let etag_header =
+        HeaderValue::from_str(&etag).map_err(|_| "Invalid commit in the saved verification")?;
+    let if_none_match = headers.get(header::IF_NONE_MATCH).and_then(|value| value.to_str().ok());
+    if if_none_match.map_or(false, |value| etag_matches(value, &etag)) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag_header)]).into_response())
+    }
+    Ok((StatusCode::OK, [(header::ETAG, etag_header)], Json(verification)).into_response())
+}
+
+/// Returns true if the `If-None-Match` header value matches `etag`. The value is either `*` or a
+/// comma-separated list of entity tags, which match regardless of whether they're weak.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Parses a chain given by name or by ID.
+fn parse_chain(chain: &str) -> Result<Chain, VerifyError> {
+    let by_id = chain.parse::<u64>().ok().and_then(|id| Chain::try_from(id).ok());
+    by_id
+        .or_else(|| Chain::from_str(chain).ok())
+        .ok_or_else(|| VerifyError::UnsupportedChain(format!("Unknown chain: {chain:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chain() {
+        assert_eq!(parse_chain("mainnet").ok(), Some(Chain::Mainnet));
+        assert_eq!(parse_chain("10").ok(), Some(Chain::Optimism));
+        assert!(parse_chain("not-a-chain").is_err());
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = "\"b268862cf1ccf495d6dc20a86c41940dfb386d9b\"";
+        assert!(etag_matches(etag, etag));
+        assert!(etag_matches("*", etag));
+        assert!(etag_matches(&format!("\"other\", W/{etag}"), etag));
+        assert!(!etag_matches("\"other\"", etag));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{
+    metrics::METRICS,
+    routes::verify::{
+        save_verification, validate_request, verification_timeout, verify_contracts, JobStatus,
+        VerifyData, VerifyError,
+    },
+    startup::AppState,
+};
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ethers::types::{Chain, TxHash};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Maximum number of contracts a single batch may verify.
+const MAX_BATCH_CONTRACTS: usize = 20;
+
+/// Fields of a `/verify` request that differ between the contracts in a batch, so must be given
+/// in each entry of `contracts` rather than once for the batch.
+const PER_CONTRACT_FIELDS: [&str; 6] =
+    ["contractAddress", "contractName", "creationTxHashes", "deployer", "nonce", "block"];
+
+/// Fields of a `/verify` request that batches don't support. Every contract is verified against
+/// the one `repoCommit`, and results are returned in the response rather than posted to a callback.
+const UNSUPPORTED_FIELDS: [&str; 2] = ["repoCommits", "callbackUrl"];
+
+/// A contract to verify as part of a batch.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchContract {
+    /// The address of the contract to verify.
+    contract_address: String,
+    /// Optional, the name of the contract. If provided, only artifacts for contracts with this
+    /// name are compared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contract_name: Option<String>,
+    /// Optional, the transaction hashes that created the contract, keyed by chain.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    creation_tx_hashes: Option<HashMap<Chain, TxHash>>,
+    /// Optional, the account that deployed the contract with a plain `CREATE`, used with `nonce`
+    /// to find the creation transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deployer: Option<String>,
+    /// Optional, the nonce of the deployer's transaction that created the contract.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<u64>,
+    /// Optional, the block number to fetch the deployed code at. Defaults to the latest block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block: Option<u64>,
+}
+
+/// Data that a caller provides to verify several contracts deployed from the same repository and
+/// commit.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyBatchData {
+    /// The contracts to verify, at most `MAX_BATCH_CONTRACTS`.
+    contracts: Vec<BatchContract>,
+    /// The rest of the request, shared by every contract, in the same format as a `/verify`
+    /// request: `repoUrl`, `repoCommit`, `buildConfig`, and any of the optional fields that aren't
+    /// given per contract.
+    #[serde(flatten)]
+    shared: Map<String, Value>,
+}
+
+impl VerifyBatchData {
+    /// Returns the `/verify` request for each contract, made of the contract's fields and the
+    /// shared ones. Returns a `BadRequest` error if the batch is empty or too large, or a field is
+    /// given in the wrong place or isn't supported.
+    fn requests(&self) -> Result<Vec<VerifyData>, VerifyError> {
+        if self.contracts.is_empty() {
+            return Err(VerifyError::BadRequest("At least one contract is required".to_string()))
+        }
+        if self.contracts.len() > MAX_BATCH_CONTRACTS {
+            let count = self.contracts.len();
+            let msg = format!(
+                "At most {MAX_BATCH_CONTRACTS} contracts are allowed, but {count} were given"
+            );
+            return Err(VerifyError::BadRequest(msg))
+        }
+        if let Some(field) = PER_CONTRACT_FIELDS.iter().find(|f| self.shared.contains_key(**f)) {
+            let msg = format!("{field} must be given for each contract in contracts");
+            return Err(VerifyError::BadRequest(msg))
+        }
+        if let Some(field) = UNSUPPORTED_FIELDS.iter().find(|f| self.shared.contains_key(**f)) {
+            let msg = format!("{field} is not supported when verifying a batch of contracts");
+            return Err(VerifyError::BadRequest(msg))
+        }
+
+        self.contracts
+            .iter()
+            .map(|contract| {
+                let mut fields = self.shared.clone();
+                let contract_fields = serde_json::to_value(contract)
+                    .map_err(|e| VerifyError::InternalServerError(e.to_string()))?;
+                if let Value::Object(contract_fields) = contract_fields {
+                    fields.extend(contract_fields);
+                }
+                serde_json::from_value(Value::Object(fields)).map_err(|e| {
+                    let msg = format!("Invalid request for {}: {e}", contract.contract_address);
+                    VerifyError::BadRequest(msg)
+                })
+            })
+            .collect()
+    }
+}
+
+/// The result of verifying one contract of a batch.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchVerification {
+    /// The address of the contract, as given in the request.
+    pub contract_address: String,
+    /// Whether the contract was verified, in the same format as `verify_status` returns a
+    /// finished job: the verification result on success, and otherwise the error.
+    #[serde(flatten)]
+    pub status: JobStatus,
+}
+
+/// The results of verifying a batch of contracts.
+#[derive(Serialize, Deserialize)]
+pub struct BatchVerificationResponse {
+    /// The result for each contract, in the order they were requested.
+    pub results: Vec<BatchVerification>,
+}
+
+/// Verifies several contracts deployed from the same repository and commit, such as a protocol's
+/// deployment, cloning and building the repository once rather than once per contract. Unlike
+/// `/verify`, this runs synchronously and returns each contract's result, since the build dominates
+/// the time taken either way.
+///
+/// # Arguments
+///
+/// * `state` - The shared application state, which limits how many verifications run at once.
+/// * `json` - The contracts to verify, and the repository and build options they share.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a `Response` with the result for each contract, or a
+/// `VerifyError` if the inputs were invalid or the repository couldn't be cloned or built. A
+/// contract that fails to verify doesn't fail the others, and its error is in its result.
+pub async fn verify_batch(
+    State(state): State<AppState>,
+    json: Result<Json<VerifyBatchData>, JsonRejection>,
+) -> Result<Response, VerifyError> {
+    let Json(json) = json?;
+    let requests = json.requests()?;
+    for request in &requests {
+        validate_request(request, &state.provider)?;
+    }
+    // A batch builds once, so it takes a single place in the queue.
+    let ticket = match state.verification_limiter.admit() {
+        Ok(ticket) => ticket,
+        Err(rejected) => return Ok(rejected.into_response()),
+    };
+    let _permit = ticket.start().await;
+
+    let timeout = verification_timeout();
+    let verification = verify_contracts(&requests, &state.provider, state.cloner.as_ref());
+    let Ok(results) = tokio::time::timeout(timeout, verification).await else {
+        tracing::warn!(timeout_secs = timeout.as_secs(), "Batch verification timed out");
+        let msg = format!("Verification did not finish within {}s", timeout.as_secs());
+        return Err(VerifyError::Timeout(msg))
+    };
+
+    let mut verifications = Vec::new();
+    for (contract, result) in json.contracts.iter().zip(results?) {
+        METRICS.record_verification(result.as_ref().map_or_else(|e| e.code(), |_| "success"));
+        if let (Some(store), Ok(verification)) = (&state.store, &result) {
+            save_verification(store.as_ref(), verification).await;
+        }
+        verifications.push(BatchVerification {
+            contract_address: contract.contract_address.clone(),
+            status: JobStatus::from_result(result),
+        });
+    }
+    let response = BatchVerificationResponse { results: verifications };
+    Ok((StatusCode::OK, Json(response)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_requests() -> Result<(), Box<dyn std::error::Error>> {
+        let batch = |body: Value| serde_json::from_value::<VerifyBatchData>(body);
+        let shared = json!({
+            "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+            "repoCommit": "main",
+            "buildConfig": { "framework": "foundry" },
+        });
+        let with_contracts = |contracts: Value| {
+            let mut body = shared.clone();
+            body["contracts"] = contracts;
+            body
+        };
+
+        // Each contract's fields are combined with the shared ones.
+        let contracts = json!([
+            { "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242" },
+            {
+                "contractAddress": "0xB264f440D77528320c74E215d5d18885060813fC",
+                "contractName": "CounterWithImmutables",
+                "block": 100,
+            },
+        ]);
+        let requests =
+            batch(with_contracts(contracts))?.requests().map_err(|e| e.message().to_string())?;
+        assert_eq!(requests.len(), 2);
+        let debug = format!("{:?}", requests[1]);
+        assert!(debug.contains("CounterWithImmutables"));
+        assert!(debug.contains("cove-test-repo"));
+
+        // Empty batches, and fields given in the wrong place or unsupported, are rejected.
+        let err = batch(with_contracts(json!([])))?.requests().err().ok_or("Empty batch")?;
+        assert!(matches!(err, VerifyError::BadRequest(_)));
+        let mut body = with_contracts(json!([{ "contractAddress": "0x01" }]));
+        body["contractName"] = json!("Counter");
+        let err = batch(body)?.requests().err().ok_or("Shared contract name")?;
+        assert!(err.message().contains("contractName"));
+        let mut body = with_contracts(json!([{ "contractAddress": "0x01" }]));
+        body["callbackUrl"] = json!("https://example.com/callback");
+        let err = batch(body)?.requests().err().ok_or("Callback URL")?;
+        assert!(err.message().contains("callbackUrl"));
+        let contracts = (0..=MAX_BATCH_CONTRACTS).map(|_| json!({ "contractAddress": "0x01" }));
+        let body = with_contracts(contracts.collect());
+        let err = batch(body)?.requests().err().ok_or("Too many contracts")?;
+        assert!(matches!(err, VerifyError::BadRequest(_)));
+        Ok(())
+    }
+}
@@ -0,0 +1,363 @@
+use crate::{
+    build::{build_failure_message, BuildLimits},
+    bytecode::{Libraries, MatchType},
+    cloner::CloneRequest,
+    comparator::{ComparatorChain, MatchQuality},
+    frameworks::{foundry::Foundry, framework::Framework, hardhat::Hardhat},
+    input::parse_hex_bytes,
+    provider::{match_creation_code, match_deployed_code, ContractMatch},
+    routes::verify::{
+        artifact_label, build_project, create_temp_dir, detect_framework,
+        ensure_artifacts_within_limit, redact_url_credentials, temp_root, BuildConfig,
+        BuildFramework, MatchedCode, VerifyError,
+    },
+    startup::AppState,
+};
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ethers::types::Bytes;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Data that a caller provides to check a repository compiles to the given bytecode, without a
+/// deployed contract.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyBytecodeData {
+    /// The URL of the repository.
+    repo_url: String,
+    /// The commit to compile. This can be a full or short commit hash, a branch, or a tag.
+    repo_commit: String,
+    /// The hex-encoded bytecode to compare against, with or without a `0x` prefix. This is
+    /// compared as creation code first, including any constructor arguments appended to it,
+    /// and then as deployed code.
+    bytecode: String,
+    /// Optional, the name of the contract the bytecode is expected to be. If provided, only
+    /// artifacts for contracts with this name are compared.
+    contract_name: Option<String>,
+    /// Optional, the build configuration for the project. If omitted, the framework is detected
+    /// and every Foundry profile is built.
+    build_config: Option<BuildConfig>,
+    /// Optional, whether to recursively clone the repository's git submodules. Defaults to `true`.
+    update_submodules: Option<bool>,
+}
+
+/// Data returned when the repository compiles to the given bytecode.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BytecodeVerification {
+    /// The URL of the repository.
+    pub repo_url: String,
+    /// The commit that was requested.
+    pub repo_commit: String,
+    /// The full commit hash that `repo_commit` resolved to.
+    pub resolved_commit: String,
+    /// The name of the contract that matched.
+    pub contract_name: String,
+    /// The path to the matching artifact, relative to the repository root.
+    pub artifact: String,
+    /// Whether the bytecode matched the artifact's creation code or deployed code.
+    pub matched_code: MatchedCode,
+    /// Whether the metadata hash matched too (`Full`), or only the code before it (`Partial`).
+    pub match_type: MatchType,
+    /// The name of the comparator that matched the bytecode, such as `exact` or
+    /// `metadata-stripped`.
+    pub comparator: Option<String>,
+    /// How strong the match is, such as whether it required stripping the metadata.
+    pub match_quality: MatchQuality,
+    /// The build profile that produced the matching artifact, such as a Foundry profile. `None`
+    /// if the framework doesn't have profiles.
+    pub profile: Option<String>,
+    /// The build command that produced the matching artifact.
+    pub build_command: String,
+    /// The ABI-encoded constructor arguments appended to the bytecode, if it matched as creation
+    /// code and had any.
+    pub constructor_args: Option<Bytes>,
+    /// The addresses of the libraries the bytecode was linked against, keyed by fully qualified
+    /// library name.
+    pub libraries: Libraries,
+}
+
+/// The artifact that matched the bytecode, and the build that produced it.
+struct BytecodeMatch {
+    /// The match found, including the path to the artifact.
+    contract_match: ContractMatch,
+    /// Whether the bytecode matched as creation code or deployed code.
+    matched_code: MatchedCode,
+    /// The build profile that produced the artifact.
+    profile: Option<String>,
+    /// The build command that produced the artifact.
+    build_command: String,
+}
+
+/// Clones and compiles a repository, then compares the artifacts against the caller's bytecode
+/// using the same comparison as `/verify`, returning the artifact that matched. This lets
+/// auditors confirm a source tree produces bytecode they already have, before or without it being
+/// deployed.
+///
+/// # Arguments
+///
+/// * `state` - The shared application state, which clones the repository.
+/// * `json` - The repository and commit to compile, and the bytecode to compare against.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a `Response` with the `BytecodeVerification`, or a `VerifyError`
+/// if the repository couldn't be built or no artifact matched.
+pub async fn verify_bytecode(
+    State(state): State<AppState>,
+    json: Result<Json<VerifyBytecodeData>, JsonRejection>,
+) -> Result<Response, VerifyError> {
+    let Json(json) = json?;
+    let bytecode = parse_hex_bytes(&json.bytecode)
+        .map_err(|e| VerifyError::BadRequest(format!("Invalid bytecode: {e}")))?;
+    if bytecode.is_empty() {
+        return Err(VerifyError::BadRequest("The bytecode must not be empty".to_string()))
+    }
+
+    let temp_dir = create_temp_dir(&temp_root())?;
+    let project_path = temp_dir.path();
+    let resolved_commit = state
+        .cloner
+        .clone_repo(CloneRequest {
+            repo_url: &json.repo_url,
+            repo_commit: &json.repo_commit,
+            dest: project_path,
+            update_submodules: json.update_submodules.unwrap_or(true),
+            auth_token: None,
+        })
+        .await?;
+
+    let (requested, build_hint) = match json.build_config {
+        Some(config) => (config.framework, config.build_hint),
+        None => (BuildFramework::Foundry, None),
+    };
+    let contract_name = json.contract_name.as_deref();
+    let bytecode_match = match detect_framework(project_path, &requested)? {
+        BuildFramework::Foundry => {
+            let project = Foundry::new(project_path).map_err(|e| {
+                VerifyError::BadRequest(format!("Failed to create Foundry project: {}", e))
+            })?;
+            build_and_match_bytecode(&project, build_hint, contract_name, &bytecode).await?
+        }
+        BuildFramework::Hardhat => {
+            let project = Hardhat::new(project_path).map_err(|e| {
+                VerifyError::BadRequest(format!("Failed to create Hardhat project: {}", e))
+            })?;
+            build_and_match_bytecode(&project, build_hint, contract_name, &bytecode).await?
+        }
+        framework => {
+            let msg = format!("Unsupported framework: {:?}", framework);
+            return Err(VerifyError::UnsupportedFramework(msg))
+        }
+    };
+
+    let BytecodeMatch { contract_match, matched_code, profile, build_command } = bytecode_match;
+    let artifact = &contract_match.artifact;
+    let result = BytecodeVerification {
+        repo_url: redact_url_credentials(&json.repo_url),
+        repo_commit: json.repo_commit,
+        resolved_commit,
+        contract_name: artifact.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+        artifact: relative_path(artifact, project_path),
+        matched_code,
+        match_type: contract_match.match_type,
+        comparator: contract_match.comparator.map(str::to_string),
+        match_quality: contract_match.quality,
+        profile,
+        build_command,
+        constructor_args: contract_match.constructor_args,
+        libraries: contract_match.libraries,
+    };
+    Ok((StatusCode::OK, Json(result)).into_response())
+}
+
+/// Builds the project and compares each build's artifacts against the bytecode, first as creation
+/// code and then as deployed code. Builds are checked in order, and the first full match is
+/// returned, or the first partial match if there is no full match. Returns an error if no build
+/// succeeded, nothing matched, or the match is ambiguous.
+async fn build_and_match_bytecode<F: Framework>(
+    project: &F,
+    build_hint: Option<String>,
+    contract_name: Option<&str>,
+    bytecode: &Bytes,
+) -> Result<BytecodeMatch, VerifyError> {
+    let build_limits = BuildLimits::from_env();
+    let builds = build_project(project, build_hint, &build_limits, None).await?;
+
+    let mut best: Option<BytecodeMatch> = None;
+    let mut any_succeeded = false;
+    let mut failed_builds = Vec::new();
+    for (build_command, build_result, project) in &builds {
+        match build_result {
+            Ok(output) if output.success() => {
+                any_succeeded = true;
+                ensure_artifacts_within_limit(project)?;
+            }
+            Ok(output) => {
+                failed_builds.push(output);
+                continue
+            }
+            Err(_) => continue,
+        }
+
+        let comparators = ComparatorChain::evm();
+        let found = match match_creation_code(project, bytecode, contract_name, &comparators) {
+            Some(contract_match) => Some((contract_match, MatchedCode::Creation)),
+            None => match_deployed_code(project, bytecode, contract_name, &comparators)
+                .map(|contract_match| (contract_match, MatchedCode::Runtime)),
+        };
+        let Some((contract_match, matched_code)) = found else { continue };
+        if !contract_match.candidates.is_empty() {
+            let mut candidates: Vec<String> = std::iter::once(&contract_match.artifact)
+                .chain(&contract_match.candidates)
+                .map(|artifact| artifact_label(artifact))
+                .collect();
+            candidates.sort();
+            candidates.dedup();
+            let msg = format!(
+                "Multiple contracts match the bytecode: {}. Set contractName to choose one",
+                candidates.join(", ")
+            );
+            return Err(VerifyError::AmbiguousMatch(msg))
+        }
+
+        let is_better = best.as_ref().map_or(true, |best| {
+            best.contract_match.match_type != MatchType::Full
+                && contract_match.match_type == MatchType::Full
+        });
+        if is_better {
+            best = Some(BytecodeMatch {
+                contract_match,
+                matched_code,
+                profile: project.profile(),
+                build_command: build_command.clone(),
+            });
+        }
+    }
+
+    if !any_succeeded {
+        return Err(VerifyError::BuildFailed(build_failure_message(failed_builds)))
+    }
+    best.ok_or_else(|| {
+        VerifyError::NoMatch("No contract in the repository matches the bytecode".to_string())
+    })
+}
+
+/// Returns the path relative to the project root as a string.
+fn relative_path(path: &Path, project_path: &Path) -> String {
+    path.strip_prefix(project_path).unwrap_or(path).to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        build::{install_solc_versions, run_build_commands},
+        routes::verify::read_artifact,
+    };
+    use std::{error::Error, fs};
+
+    #[tokio::test]
+    async fn test_build_and_match_bytecode() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "[profile.default]\nsrc = 'src'\nsolc_version = '0.7.6'\n",
+        )?;
+        fs::create_dir(project_dir.path().join("src"))?;
+        fs::write(
+            project_dir.path().join("src").join("Counter.sol"),
+            "pragma solidity 0.7.6;\ncontract Counter { uint256 public count; }\n\
+             contract Other { uint256 public other; }\n",
+        )?;
+        let project = Foundry::new(project_dir.path())?;
+        install_solc_versions(&project.pinned_solc_versions().map_err(|e| e.to_string())?).await?;
+
+        // Build once to get known-good bytecode for `Counter`.
+        let (commands, projects): (Vec<_>, Vec<_>) =
+            project.build_commands(None).map_err(|e| e.to_string())?.into_iter().unzip();
+        run_build_commands(commands, &BuildLimits::default()).await;
+        let artifact = projects[0]
+            .get_artifacts()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|artifact| artifact.file_stem().map_or(false, |stem| stem == "Counter"))
+            .ok_or("Missing Counter artifact")?;
+        let creation_code =
+            Foundry::get_artifact_creation_code(&artifact).map_err(|e| e.to_string())?;
+        let (deployed_code, _) =
+            Foundry::get_artifact_deployed_code(&artifact).map_err(|e| e.to_string())?;
+
+        // Both the creation code and deployed code match `Counter`.
+        let creation_match = build_and_match_bytecode(&project, None, None, &creation_code)
+            .await
+            .map_err(|e| e.message().to_string())?;
+        assert_eq!(creation_match.matched_code, MatchedCode::Creation);
+        assert_eq!(creation_match.contract_match.match_type, MatchType::Full);
+        assert!(creation_match.contract_match.artifact.ends_with("Counter.sol/Counter.json"));
+        assert!(creation_match.build_command.contains("forge build"));
+
+        let deployed_match = build_and_match_bytecode(&project, None, None, &deployed_code)
+            .await
+            .map_err(|e| e.message().to_string())?;
+        assert_eq!(deployed_match.matched_code, MatchedCode::Runtime);
+
+        // Restricting the match to another contract finds nothing.
+        let result = build_and_match_bytecode(&project, None, Some("Other"), &creation_code).await;
+        assert_eq!(result.err().map(|e| e.code()), Some("NO_BYTECODE_MATCH"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_build_and_match_via_ir_bytecode() -> Result<(), Box<dyn Error + Send + Sync>> {
+        // The IR pipeline produces different bytecode, so code compiled with it only matches when
+        // the profile that enables it is built.
+        let project_dir = tempfile::tempdir()?;
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "[profile.default]\nsrc = 'src'\nsolc_version = '0.8.19'\n\
+             [profile.ir]\nsrc = 'src'\nsolc_version = '0.8.19'\nvia_ir = true\n",
+        )?;
+        fs::create_dir(project_dir.path().join("src"))?;
+        fs::write(
+            project_dir.path().join("src").join("Counter.sol"),
+            "pragma solidity 0.8.19;\ncontract Counter {\n    uint256 public count;\n    \
+             function increment() external { count += 1; }\n}\n",
+        )?;
+        let project = Foundry::new(project_dir.path())?;
+        install_solc_versions(&project.pinned_solc_versions().map_err(|e| e.to_string())?).await?;
+
+        let (commands, projects): (Vec<_>, Vec<_>) = project
+            .build_commands(Some("ir".to_string()))
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .unzip();
+        run_build_commands(commands, &BuildLimits::default()).await;
+        let artifact = projects[0]
+            .get_artifacts()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|artifact| artifact.file_stem().map_or(false, |stem| stem == "Counter"))
+            .ok_or("Missing Counter artifact")?;
+        let metadata = read_artifact(&artifact).map_err(|e| e.message().to_string())?.metadata;
+        assert_eq!(metadata.and_then(|metadata| metadata.settings.via_ir), Some(true));
+        let creation_code =
+            Foundry::get_artifact_creation_code(&artifact).map_err(|e| e.to_string())?;
+
+        let hint = Some("default".to_string());
+        let result = build_and_match_bytecode(&project, hint, None, &creation_code).await;
+        assert_eq!(result.err().map(|e| e.code()), Some("NO_BYTECODE_MATCH"));
+
+        let via_ir_match = build_and_match_bytecode(&project, None, None, &creation_code)
+            .await
+            .map_err(|e| e.message().to_string())?;
+        assert_eq!(via_ir_match.profile.as_deref(), Some("ir"));
+        assert!(via_ir_match.build_command.contains("--via-ir"));
+        Ok(())
+    }
+}
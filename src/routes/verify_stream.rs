@@ -0,0 +1,78 @@
+use crate::{
+    progress::ProgressEvent,
+    routes::verify::{jobs_lock_poisoned, VerifyError},
+    startup::AppState,
+};
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::stream;
+use serde::Deserialize;
+use std::convert::Infallible;
+use uuid::Uuid;
+
+/// The query parameters of `verify_stream`.
+#[derive(Deserialize)]
+pub struct StreamQuery {
+    /// The ID of the job to stream, as returned by `verify`.
+    pub job_id: Uuid,
+}
+
+/// Streams the progress of a verification job as Server-Sent Events. Each event is named after the
+/// stage it reports, such as `cloning`, `building`, `comparing`, or `matched`, and its data is the
+/// stage as JSON. Events reported before the stream was opened are sent first, and the stream ends
+/// after the final `result` event, whose data holds the job status as returned by `verify_status`.
+///
+/// # Arguments
+///
+/// * `state` - The shared application state, which tracks the progress of verification jobs.
+/// * `query` - The query parameters, which hold the ID of the job.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the event stream, or a `VerifyError` if no job with the given ID
+/// exists.
+pub async fn verify_stream(
+    State(state): State<AppState>,
+    query: Result<Query<StreamQuery>, QueryRejection>,
+) -> Result<Response, VerifyError> {
+    let Query(StreamQuery { job_id }) = query?;
+    let progress = state.progress.lock().map_err(|_| jobs_lock_poisoned())?.get(&job_id).cloned();
+    let progress = progress
+        .ok_or_else(|| VerifyError::NotFound(format!("No verification job with ID {job_id}")))?;
+
+    // Send each event once, in the order reported, stopping after the result.
+    let events = stream::unfold(
+        (progress.subscribe(), 0, false),
+        |(mut receiver, sent, finished)| async move {
+            if finished {
+                return None
+            }
+            loop {
+                let next = receiver.borrow_and_update().get(sent).cloned();
+                if let Some(event) = next {
+                    let finished = matches!(event, ProgressEvent::Result { .. });
+                    return Some((
+                        Ok::<_, Infallible>(sse_event(&event)),
+                        (receiver, sent + 1, finished),
+                    ))
+                }
+                receiver.changed().await.ok()?;
+            }
+        },
+    );
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()).into_response())
+}
+
+/// Returns the Server-Sent Event for the progress event, named after its stage.
+fn sse_event(event: &ProgressEvent) -> Event {
+    let event_name = event.name();
+    Event::default()
+        .event(event_name)
+        .json_data(event)
+        .unwrap_or_else(|_| Event::default().event(event_name))
+}
@@ -1,35 +1,231 @@
-use crate::routes;
+use crate::{
+    auth::{require_api_key, ApiKeys, API_KEY_HEADER},
+    cloner::{GitCloner, RepoCloner},
+    concurrency::VerificationLimiter,
+    progress::VerificationProgress,
+    provider::{load_chain_configs, MultiChainProvider},
+    rate_limit::{rate_limit, RateLimiter},
+    request_id::{make_request_span, set_request_id, REQUEST_ID_HEADER},
+    routes::{self, InFlightJobs, JobStatus, RunningJobs},
+    storage::{store_from_env, VerificationStore},
+    webhook::WebhookSender,
+};
 use axum::{
-    routing::{get, post, IntoMakeService},
+    extract::connect_info::IntoMakeServiceWithConnectInfo,
+    middleware,
+    routing::{get, post},
     Router, Server,
 };
 use dotenvy::dotenv;
+use futures::Future;
 use headers::HeaderName;
 use hyper::{server::conn::AddrIncoming, Method};
-use std::net::TcpListener;
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use tower::ServiceBuilder;
 use tower_http::{
+    compression::CompressionLayer,
     cors::{Any, CorsLayer},
-    request_id::MakeRequestUuid,
-    trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
+    trace::{DefaultOnResponse, TraceLayer},
     ServiceBuilderExt,
 };
+use uuid::Uuid;
+
+/// How long creation code lookups are cached for. Creation data never changes once a contract is
+/// deployed, so this only bounds memory usage.
+const CREATION_CODE_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// State shared by all route handlers.
+#[derive(Clone)]
+pub struct AppState {
+    /// The status of each verification job, keyed by job ID. Jobs are kept in memory, so they are
+    /// lost when the server restarts, and finished jobs are removed after `job_retention`.
+    pub jobs: Arc<Mutex<HashMap<Uuid, Arc<JobStatus>>>>,
+    /// The progress reported by each verification job, keyed by job ID, so it can be streamed.
+    /// Jobs that share a verification share its progress, which is removed along with the job's
+    /// status.
+    pub progress: Arc<Mutex<HashMap<Uuid, Arc<VerificationProgress>>>>,
+    /// The verifications currently running, so identical requests can share them.
+    pub in_flight: Arc<InFlightJobs>,
+    /// The background tasks running verification jobs, so shutdown can wait for them.
+    pub running: Arc<RunningJobs>,
+    /// The provider shared by all verifications, so its creation code cache is reused across
+    /// requests.
+    pub provider: Arc<MultiChainProvider>,
+    /// Clones the repositories that contracts are verified against.
+    pub cloner: Arc<dyn RepoCloner>,
+    /// Where successful verifications are saved, or `None` if no database is configured.
+    pub store: Option<Arc<dyn VerificationStore>>,
+    /// Limits how many verifications run at once, shared by all requests.
+    pub verification_limiter: Arc<VerificationLimiter>,
+    /// Posts the final status of jobs to the callback URLs callers provided.
+    pub webhooks: Arc<WebhookSender>,
+    /// How long a finished job's status and progress are kept before they're removed, so they
+    /// don't accumulate in memory.
+    pub job_retention: Duration,
+}
+
+impl AppState {
+    /// Create the application state with no jobs. The provider connects to the chains in the
+    /// chains config file if there is one, otherwise to all supported chains using the RPC URLs
+    /// from the environment. Verifications are saved to the database in `DATABASE_URL`, if set, and
+    /// limited by `MAX_CONCURRENT_VERIFICATIONS` and `MAX_QUEUED_VERIFICATIONS`.
+    pub fn new() -> Self {
+        let provider = match load_chain_configs().expect("Failed to load chains config") {
+            Some(configs) => {
+                MultiChainProvider::from_config(configs).expect("Invalid chains config")
+            }
+            None => MultiChainProvider::new(),
+        };
+        Self::with(provider.cache_for(CREATION_CODE_CACHE_TTL), Arc::new(GitCloner))
+    }
+
+    /// Create the application state with no jobs, querying chains with `provider` and cloning
+    /// repositories with `cloner`, such as mocks in tests. Everything else is configured from the
+    /// environment, as in `new`.
+    pub fn with(provider: MultiChainProvider, cloner: Arc<dyn RepoCloner>) -> Self {
+        let store = store_from_env().expect("Invalid database URL");
+        Self {
+            jobs: Arc::default(),
+            progress: Arc::default(),
+            in_flight: Arc::default(),
+            running: Arc::default(),
+            provider: Arc::new(provider),
+            cloner,
+            store,
+            verification_limiter: Arc::new(VerificationLimiter::from_env()),
+            webhooks: Arc::new(WebhookSender::from_env()),
+            job_retention: job_retention(),
+        }
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Environment variable used to override how long, in seconds, shutdown waits for running
+/// verifications to finish before cancelling them.
+const SHUTDOWN_TIMEOUT_ENV_VAR: &str = "SHUTDOWN_TIMEOUT_SECS";
+
+/// Default time shutdown waits for running verifications to finish.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Returns how long shutdown waits for running verifications, read from `SHUTDOWN_TIMEOUT_SECS` if
+/// it's set to a valid number.
+fn shutdown_timeout() -> Duration {
+    std::env::var(SHUTDOWN_TIMEOUT_ENV_VAR)
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map_or(DEFAULT_SHUTDOWN_TIMEOUT, Duration::from_secs)
+}
+
+/// Environment variable used to override how long, in seconds, a finished job's status and
+/// progress are kept.
+const JOB_RETENTION_ENV_VAR: &str = "JOB_RETENTION_SECS";
+
+/// Default time a finished job's status and progress are kept.
+const DEFAULT_JOB_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Returns how long finished jobs are kept, read from `JOB_RETENTION_SECS` if it's set to a valid
+/// number.
+fn job_retention() -> Duration {
+    std::env::var(JOB_RETENTION_ENV_VAR)
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map_or(DEFAULT_JOB_RETENTION, Duration::from_secs)
+}
+
+/// The HTTP server returned by `run`.
+pub type AppServer = Server<AddrIncoming, IntoMakeServiceWithConnectInfo<Router, SocketAddr>>;
 
 /// Run the application on the given TcpListener and return the HTTP server instance.
-pub fn run(listener: TcpListener) -> hyper::Result<Server<AddrIncoming, IntoMakeService<Router>>> {
+pub fn run(listener: TcpListener) -> hyper::Result<AppServer> {
+    load_env();
+    serve(listener, AppState::default())
+}
+
+/// Run the application with the given state on the given TcpListener and return the HTTP server
+/// instance, so tests can serve the app with a mocked provider and cloner. The environment must
+/// already be loaded, since `AppState::with` reads it.
+pub fn run_with_state(listener: TcpListener, state: AppState) -> hyper::Result<AppServer> {
+    serve(listener, state)
+}
+
+/// Run the application on the given TcpListener until `shutdown` completes, then shut down
+/// gracefully. New connections are refused once `shutdown` completes, while open requests and
+/// running verification jobs are given until `SHUTDOWN_TIMEOUT_SECS` to finish. Jobs still running
+/// after that are cancelled, which kills their builds and removes their temporary directories.
+pub fn run_until(
+    listener: TcpListener,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> hyper::Result<impl Future<Output = hyper::Result<()>>> {
+    load_env();
+    let state = AppState::default();
+    let (in_flight, running) = (Arc::clone(&state.in_flight), Arc::clone(&state.running));
+    let server = serve(listener, state)?.with_graceful_shutdown(shutdown);
+    let timeout = shutdown_timeout();
+    Ok(async move {
+        let drain = async {
+            server.await?;
+            tracing::info!(jobs = running.len(), "Waiting for running verifications to finish");
+            running.wait_until_idle().await;
+            Ok::<_, hyper::Error>(())
+        };
+        match tokio::time::timeout(timeout, drain).await {
+            Ok(result) => result,
+            Err(_) => {
+                tracing::warn!(
+                    jobs = running.len(),
+                    timeout_secs = timeout.as_secs(),
+                    "Cancelling verifications that didn't finish before shutdown"
+                );
+                in_flight.clear();
+                running.abort_all();
+                running.wait_until_idle().await;
+                Ok(())
+            }
+        }
+    })
+}
+
+/// Loads environment variables from the `.env` file, if there is one.
+fn load_env() {
     if dotenv().is_err() {
         // We don't error since there's no `.env` file in CI.
-        println!("WARNING: No .env file found, using default environment variables.");
+        tracing::warn!("No .env file found, using default environment variables");
     }
+}
+
+/// Serves the application with the given state on the given TcpListener.
+fn serve(listener: TcpListener, state: AppState) -> hyper::Result<AppServer> {
+    // Remove temporary directories left behind by earlier runs, then keep removing stale ones.
+    tokio::spawn(routes::sweep_stale_temp_dirs_periodically());
 
+    // Run it with hyper on the given TcpListener.
+    // Connection info is needed to rate limit clients without an API key by IP address.
+    let app = app(state).into_make_service_with_connect_info::<SocketAddr>();
+    Ok(axum::Server::from_tcp(listener)?.serve(app))
+}
+
+/// Builds the router for every route, with its middleware, handling requests with the given
+/// state.
+pub fn app(state: AppState) -> Router {
     // Configure service to have request IDs show up correctly in logs produced by
     // `tower_http::trace::Trace`. Modified from: https://docs.rs/tower-http/latest/tower_http/request_id/index.html#using-trace
     let trace_layer = ServiceBuilder::new()
-        .set_x_request_id(MakeRequestUuid)
+        // Use the caller's request ID, or generate one, before the request's span is created.
+        .layer(middleware::from_fn(set_request_id))
         // Log requests and responses.
         .layer(
             TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::new().include_headers(true))
+                .make_span_with(make_request_span)
                 .on_response(DefaultOnResponse::new().include_headers(true)),
         )
         // Propagate the header to the response before the response reaches `TraceLayer`.
@@ -42,16 +238,46 @@ pub fn run(listener: TcpListener) -> hyper::Result<Server<AddrIncoming, IntoMake
         .allow_headers(vec![
             HeaderName::from_static("content-type"),
             HeaderName::from_static("authorization"),
-        ]);
+            HeaderName::from_static("if-none-match"),
+            HeaderName::from_static(API_KEY_HEADER),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .expose_headers(vec![HeaderName::from_static(REQUEST_ID_HEADER)]);
 
-    // Build our application with a single route.
-    let app = Router::new()
-        .route("/health_check", get(routes::health_check))
+    // Routes that clone, build, or decompile require an API key when keys are configured, and are
+    // rate limited per client, since they're expensive to run. Layers added later run first, so
+    // clients are authenticated before they're rate limited by key.
+    let api_keys = Arc::new(ApiKeys::from_env());
+    if !api_keys.is_enabled() {
+        tracing::warn!("No API keys configured, so all routes are open to everyone");
+    }
+    let rate_limiter = Arc::new(RateLimiter::from_env());
+    let protected = Router::new()
         .route("/verify", post(routes::verify))
+        .route("/verify-batch", post(routes::verify_batch))
+        .route("/verify-bytecode", post(routes::verify_bytecode))
+        .route("/compile", post(routes::compile))
         .route("/contract", get(routes::contract))
-        .layer(trace_layer)
-        .layer(cors_layer);
+        .route("/export/sourcify/:job_id", post(routes::export_sourcify))
+        .route_layer(middleware::from_fn_with_state(rate_limiter, rate_limit))
+        .route_layer(middleware::from_fn_with_state(api_keys, require_api_key));
 
-    // Run it with hyper on the given TcpListener.
-    Ok(axum::Server::from_tcp(listener)?.serve(app.into_make_service()))
+    // Build our application with its routes.
+    Router::new()
+        .route("/health_check", get(routes::health_check))
+        .route("/health", get(routes::health))
+        .route("/ready", get(routes::ready))
+        .route("/supported-chains", get(routes::supported_chains))
+        .route("/diff", post(routes::diff))
+        .route("/metrics", get(routes::metrics))
+        .route("/verify/stream", get(routes::verify_stream))
+        .route("/verify/:job_id", get(routes::verify_status))
+        .route("/verified/:chain/:address", get(routes::verified))
+        .merge(protected)
+        .with_state(state)
+        .layer(trace_layer)
+        .layer(cors_layer)
+        // Compress responses for clients that accept it, since verification results include the
+        // full ABI, sources, and AST and can be hundreds of kilobytes.
+        .layer(CompressionLayer::new())
 }
@@ -0,0 +1,65 @@
+/// Defines the `SqliteStore` struct which implements the `VerificationStore` trait.
+pub mod sqlite;
+
+use ethers::types::{Address, Chain};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlite::SqliteStore;
+use std::{error::Error, sync::Arc};
+
+/// Environment variable holding the URL of the database verification results are saved to, such
+/// as `sqlite://cove.db`. Results aren't saved if it's unset.
+const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
+
+/// Errors returned by a `VerificationStore`.
+pub type StorageError = Box<dyn Error + Send + Sync>;
+
+/// A successful verification as saved for a single chain and address.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredVerification {
+    /// The chain the contract was verified on.
+    pub chain: Chain,
+    /// The address of the verified contract.
+    pub address: Address,
+    /// The URL of the repository the contract was verified against, without credentials.
+    pub repo_url: String,
+    /// The full commit hash the contract was verified against.
+    pub repo_commit: String,
+    /// When the contract was verified, in seconds since the Unix epoch.
+    pub verified_at: u64,
+    /// The verification result, as returned by the verification job.
+    pub verification: Value,
+}
+
+/// Persists successful verifications, keyed by chain and address, so they can be served without
+/// verifying the contract again. Saving a verification for a chain and address that already has
+/// one replaces it.
+pub trait VerificationStore: Send + Sync {
+    /// Saves the verification, replacing any existing verification for its chain and address.
+    fn save<'a>(
+        &'a self,
+        verification: &'a StoredVerification,
+    ) -> BoxFuture<'a, Result<(), StorageError>>;
+
+    /// Returns the verification for the contract at `address` on `chain`, if there is one.
+    fn get(
+        &self,
+        chain: Chain,
+        address: Address,
+    ) -> BoxFuture<'_, Result<Option<StoredVerification>, StorageError>>;
+}
+
+/// Returns the store for the database in `DATABASE_URL`, or `None` if it's unset, in which case
+/// verifications aren't saved. Only SQLite is currently supported.
+pub fn store_from_env() -> Result<Option<Arc<dyn VerificationStore>>, StorageError> {
+    let Some(url) = std::env::var(DATABASE_URL_ENV_VAR).ok().filter(|url| !url.is_empty()) else {
+        return Ok(None)
+    };
+    if url.starts_with("sqlite:") {
+        return Ok(Some(Arc::new(SqliteStore::connect_lazy(&url)?)))
+    }
+    let scheme = url.split(':').next().unwrap_or_default();
+    Err(format!("Unsupported database {scheme:?} in {DATABASE_URL_ENV_VAR}, expected sqlite")
+        .into())
+}
@@ -0,0 +1,154 @@
+use crate::storage::{StorageError, StoredVerification, VerificationStore};
+use ethers::types::{Address, Chain};
+use futures::future::BoxFuture;
+use sqlx::{
+    migrate::Migrator,
+    sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions},
+    FromRow,
+};
+use std::str::FromStr;
+use tokio::sync::OnceCell;
+
+/// The migrations creating the database schema, embedded from the `migrations` directory.
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Stores verifications in a SQLite database.
+pub struct SqliteStore {
+    /// The connection pool, which connects when it's first used.
+    pool: SqlitePool,
+    /// Set once the migrations have run, so they run once before the database is first used.
+    migrated: OnceCell<()>,
+}
+
+/// A row of the `verifications` table.
+#[derive(FromRow)]
+struct VerificationRow {
+    /// The chain ID.
+    chain_id: i64,
+    /// The contract address, as lowercase hex with a `0x` prefix.
+    address: String,
+    /// The URL of the repository.
+    repo_url: String,
+    /// The full commit hash.
+    repo_commit: String,
+    /// When the contract was verified, in seconds since the Unix epoch.
+    verified_at: i64,
+    /// The verification result as JSON.
+    verification: String,
+}
+
+impl SqliteStore {
+    /// Returns a store for the database at `url`, such as `sqlite://cove.db`, creating the file if
+    /// it doesn't exist. The database isn't opened until the store is first used.
+    pub fn connect_lazy(url: &str) -> Result<Self, StorageError> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new().connect_lazy_with(options);
+        Ok(Self { pool, migrated: OnceCell::new() })
+    }
+
+    /// Runs any migrations that haven't been applied yet, the first time it's called.
+    async fn migrate(&self) -> Result<(), StorageError> {
+        self.migrated
+            .get_or_try_init(|| async { MIGRATOR.run(&self.pool).await.map(|_| ()) })
+            .await?;
+        Ok(())
+    }
+}
+
+impl VerificationStore for SqliteStore {
+    fn save<'a>(
+        &'a self,
+        verification: &'a StoredVerification,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            self.migrate().await?;
+            sqlx::query(
+                "INSERT INTO verifications
+                    (chain_id, address, repo_url, repo_commit, verified_at, verification)
+                VALUES (?, ?, ?, ?, ?, ?)
+                ON CONFLICT (chain_id, address) DO UPDATE SET
+                    repo_url = excluded.repo_url,
+                    repo_commit = excluded.repo_commit,
+                    verified_at = excluded.verified_at,
+                    verification = excluded.verification",
+            )
+            .bind(chain_id(verification.chain)?)
+            .bind(format!("{:?}", verification.address))
+            .bind(&verification.repo_url)
+            .bind(&verification.repo_commit)
+            .bind(i64::try_from(verification.verified_at)?)
+            .bind(verification.verification.to_string())
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn get(
+        &self,
+        chain: Chain,
+        address: Address,
+    ) -> BoxFuture<'_, Result<Option<StoredVerification>, StorageError>> {
+        Box::pin(async move {
+            self.migrate().await?;
+            let row: Option<VerificationRow> = sqlx::query_as(
+                "SELECT chain_id, address, repo_url, repo_commit, verified_at, verification
+                FROM verifications WHERE chain_id = ? AND address = ?",
+            )
+            .bind(chain_id(chain)?)
+            .bind(format!("{address:?}"))
+            .fetch_optional(&self.pool)
+            .await?;
+            let Some(row) = row else { return Ok(None) };
+
+            Ok(Some(StoredVerification {
+                chain: Chain::try_from(u64::try_from(row.chain_id)?)?,
+                address: Address::from_str(&row.address)?,
+                repo_url: row.repo_url,
+                repo_commit: row.repo_commit,
+                verified_at: u64::try_from(row.verified_at)?,
+                verification: serde_json::from_str(&row.verification)?,
+            }))
+        })
+    }
+}
+
+/// Returns the chain's ID as stored in the database. SQLite integers are signed.
+fn chain_id(chain: Chain) -> Result<i64, StorageError> {
+    Ok(i64::try_from(u64::from(chain))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_save_and_get() -> Result<(), StorageError> {
+        let dir = tempfile::tempdir()?;
+        let url = format!("sqlite://{}", dir.path().join("cove.db").display());
+        let store = SqliteStore::connect_lazy(&url)?;
+        let address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+
+        // Nothing is stored until a verification is saved.
+        assert_eq!(store.get(Chain::Mainnet, address).await?, None);
+        let verification = StoredVerification {
+            chain: Chain::Mainnet,
+            address,
+            repo_url: "https://github.com/ScopeLift/cove-test-repo".to_string(),
+            repo_commit: "188587df6652ca2b1b6a8e3e0ca8a9b7b2e5f7a3".to_string(),
+            verified_at: 1_700_000_000,
+            verification: json!({ "matched_chains": ["mainnet"] }),
+        };
+        store.save(&verification).await?;
+        assert_eq!(store.get(Chain::Mainnet, address).await?, Some(verification.clone()));
+        // Verifications are keyed by chain as well as address.
+        assert_eq!(store.get(Chain::Goerli, address).await?, None);
+
+        // Saving again for the same chain and address replaces the verification.
+        let reverified = StoredVerification { verified_at: 1_700_000_100, ..verification };
+        store.save(&reverified).await?;
+        assert_eq!(store.get(Chain::Mainnet, address).await?, Some(reverified));
+        Ok(())
+    }
+}
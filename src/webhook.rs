@@ -0,0 +1,290 @@
+use crate::routes::{verify::is_public_ip, JobStatus};
+use ethers::utils::hex;
+use hmac::{Hmac, Mac};
+use reqwest::{redirect::Policy, Url};
+use serde_json::json;
+use sha2::Sha256;
+use std::{net::SocketAddr, time::Duration};
+use uuid::Uuid;
+
+/// Environment variable holding the secret that callback payloads are signed with. Callbacks are
+/// disabled if it's unset or empty.
+const WEBHOOK_SECRET_ENV_VAR: &str = "WEBHOOK_SECRET";
+
+/// The header carrying the payload's signature, formatted as `sha256=<hex HMAC>`.
+pub const SIGNATURE_HEADER: &str = "x-cove-signature";
+
+/// The header carrying the ID of the job the payload is for.
+pub const JOB_ID_HEADER: &str = "x-cove-job-id";
+
+/// Default number of times a callback is attempted, including the first attempt.
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Default backoff before retrying a failed callback. This doubles after each retry.
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// How long each callback attempt may take before it's considered failed.
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns the signature of `payload` with `secret`, formatted as the `x-cove-signature` header
+/// value. Receivers recompute this over the raw request body to check the payload came from us.
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    // HMAC accepts keys of any length, so this can't fail.
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("Invalid HMAC key");
+    mac.update(payload);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Posts the final status of verification jobs to the callback URLs callers provided, so they
+/// don't need to poll for it.
+pub struct WebhookSender {
+    /// The secret payloads are signed with, or `None` if callbacks are disabled.
+    secret: Option<String>,
+    /// The maximum number of times a callback is attempted, including the first attempt.
+    max_attempts: u32,
+    /// The backoff before the first retry, which doubles after each retry.
+    initial_backoff: Duration,
+    /// Whether callbacks may be sent to hosts that resolve to private addresses, which is only
+    /// allowed in tests.
+    allow_private_addresses: bool,
+}
+
+impl Default for WebhookSender {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl WebhookSender {
+    /// Returns a sender that signs payloads with `secret`, or has callbacks disabled if it's
+    /// `None`.
+    pub fn new(secret: Option<String>) -> Self {
+        Self {
+            secret,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            allow_private_addresses: false,
+        }
+    }
+
+    /// Returns a sender that signs payloads with the secret in `WEBHOOK_SECRET`.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var(WEBHOOK_SECRET_ENV_VAR).ok().filter(|secret| !secret.is_empty()))
+    }
+
+    /// Sets how many times a callback is attempted and the backoff before the first retry.
+    pub fn retry_with(mut self, max_attempts: u32, initial_backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Allows callbacks to receivers on private addresses, such as those tests run on localhost.
+    #[cfg(test)]
+    fn allow_private_addresses(mut self) -> Self {
+        self.allow_private_addresses = true;
+        self
+    }
+
+    /// Returns true if a secret is configured, so callbacks can be signed.
+    pub fn is_enabled(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Posts the job's status to `callback_url`, retrying with exponential backoff if the request
+    /// fails or the receiver doesn't respond with a 2xx status. The body is the job status with
+    /// its `jobId`, in the same format `verify_status` returns it, and is signed in the
+    /// `x-cove-signature` header. Each attempt is sent with a client from `client_for`, so it's
+    /// never sent to a private address. Returns the last error if every attempt failed.
+    pub async fn deliver(
+        &self,
+        callback_url: &Url,
+        job_id: Uuid,
+        status: &JobStatus,
+    ) -> Result<(), String> {
+        let Some(secret) = &self.secret else { return Err("Callbacks are disabled".to_string()) };
+        let mut payload = serde_json::to_value(status).map_err(|e| e.to_string())?;
+        if let Some(payload) = payload.as_object_mut() {
+            payload.insert("jobId".to_string(), json!(job_id));
+        }
+        let body = payload.to_string();
+        let signature = sign(secret, body.as_bytes());
+
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let response = match self.client_for(callback_url).await {
+                Ok(client) => client
+                    .post(callback_url.clone())
+                    .timeout(CALLBACK_TIMEOUT)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(SIGNATURE_HEADER, &signature)
+                    .header(JOB_ID_HEADER, job_id.to_string())
+                    .body(body.clone())
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            let error = match response {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => format!("Receiver responded with {}", response.status()),
+                Err(e) => e,
+            };
+            if attempt >= self.max_attempts {
+                return Err(error)
+            }
+            tracing::warn!(%job_id, attempt, %error, "Callback failed, retrying");
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Returns a client to post to `callback_url` with. `validate_callback_url` only checks the
+    /// URL, so the host is resolved here and rejected if any of its addresses aren't public. The
+    /// client connects to the address that was checked rather than resolving the host again, so a
+    /// host whose DNS changes in between can't point it elsewhere. Redirects aren't followed,
+    /// since they could point at an internal service.
+    async fn client_for(&self, callback_url: &Url) -> Result<reqwest::Client, String> {
+        let host = callback_url.host_str().ok_or("The callback URL has no host")?;
+        // IPv6 hosts are wrapped in brackets.
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let port = callback_url.port_or_known_default().unwrap_or(443);
+        let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| format!("Failed to resolve host '{host}': {e}"))?
+            .collect();
+        if !self.allow_private_addresses && addresses.iter().any(|a| !is_public_ip(a.ip())) {
+            return Err(format!("The callback host '{host}' resolves to a private address"))
+        }
+
+        let mut builder = reqwest::Client::builder().redirect(Policy::none());
+        if let (Some(domain), Some(address)) = (callback_url.domain(), addresses.first()) {
+            builder = builder.resolve(domain, *address);
+        }
+        builder.build().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderMap, routing::post, Router};
+    use std::{
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    #[test]
+    fn test_sign() {
+        // Matches `echo -n '{"status":"pending"}' | openssl dgst -sha256 -hmac secret`.
+        assert_eq!(
+            sign("secret", br#"{"status":"pending"}"#),
+            "sha256=9dc597a10b4162575d7b2b48dd2028d35d592db75278699d99e5de3efadd6b45"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deliver() -> Result<(), Box<dyn std::error::Error>> {
+        // A receiver that fails the first request, then records the signature and body of each
+        // request it accepts.
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let handler_received = received.clone();
+        let handler = move |headers: HeaderMap, body: String| {
+            let received = handler_received.clone();
+            async move {
+                let mut received = received.lock().unwrap();
+                received.push((headers.get(SIGNATURE_HEADER).cloned(), body));
+                if received.len() == 1 {
+                    axum::http::StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    axum::http::StatusCode::OK
+                }
+            }
+        };
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let callback_url = Url::parse(&format!("http://{}/callback", listener.local_addr()?))?;
+        let app = Router::new().route("/callback", post(handler));
+        tokio::spawn(axum::Server::from_tcp(listener)?.serve(app.into_make_service()));
+
+        let sender = WebhookSender::new(Some("secret".to_string()))
+            .retry_with(3, Duration::from_millis(10))
+            .allow_private_addresses();
+        let job_id = Uuid::new_v4();
+        let status = JobStatus::Failed {
+            status_code: 409,
+            code: "NO_BYTECODE_MATCH".to_string(),
+            error: "No matching contract found".to_string(),
+        };
+        sender.deliver(&callback_url, job_id, &status).await?;
+
+        // The failed attempt was retried, and the payload is signed with the secret.
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        let (signature, body) = &received[1];
+        let signature = signature.as_ref().ok_or("Missing signature")?.to_str()?;
+        assert_eq!(signature, sign("secret", body.as_bytes()));
+        assert_ne!(signature, sign("other secret", body.as_bytes()));
+        let payload: serde_json::Value = serde_json::from_str(body)?;
+        assert_eq!(payload["jobId"], job_id.to_string());
+        assert_eq!(payload["status"], "failed");
+        assert_eq!(payload["code"], "NO_BYTECODE_MATCH");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deliver_gives_up() -> Result<(), Box<dyn std::error::Error>> {
+        // Nothing listens on this port once the listener is dropped.
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let callback_url = Url::parse(&format!("http://{}/callback", listener.local_addr()?))?;
+        drop(listener);
+
+        let status = JobStatus::Pending;
+        let sender = WebhookSender::new(Some("secret".to_string()))
+            .retry_with(2, Duration::from_millis(10))
+            .allow_private_addresses();
+        assert!(sender.deliver(&callback_url, Uuid::new_v4(), &status).await.is_err());
+
+        // Without a secret, callbacks are never sent.
+        let disabled = WebhookSender::default();
+        assert!(!disabled.is_enabled());
+        assert!(disabled.deliver(&callback_url, Uuid::new_v4(), &status).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deliver_only_to_public_addresses() -> Result<(), Box<dyn std::error::Error>> {
+        // A receiver that redirects callbacks to another path, counting the requests to each.
+        let redirected = Arc::new(Mutex::new(0));
+        let handler_redirected = redirected.clone();
+        let app = Router::new()
+            .route("/callback", post(|| async { axum::response::Redirect::temporary("/internal") }))
+            .route(
+                "/internal",
+                post(move || {
+                    *handler_redirected.lock().unwrap() += 1;
+                    async { axum::http::StatusCode::OK }
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        tokio::spawn(axum::Server::from_tcp(listener)?.serve(app.into_make_service()));
+        let status = JobStatus::Pending;
+
+        // Callbacks aren't sent to hosts that resolve to private addresses.
+        let callback_url = Url::parse(&format!("http://localhost:{}/callback", address.port()))?;
+        let sender =
+            WebhookSender::new(Some("secret".to_string())).retry_with(1, Duration::from_millis(10));
+        let error = sender.deliver(&callback_url, Uuid::new_v4(), &status).await.unwrap_err();
+        assert!(error.contains("private address"), "{error}");
+
+        // Redirects aren't followed.
+        let callback_url = Url::parse(&format!("http://{address}/callback"))?;
+        let sender = sender.allow_private_addresses();
+        let error = sender.deliver(&callback_url, Uuid::new_v4(), &status).await.unwrap_err();
+        assert!(error.contains("307"), "{error}");
+        assert_eq!(*redirected.lock().unwrap(), 0);
+        Ok(())
+    }
+}
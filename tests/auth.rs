@@ -0,0 +1,94 @@
+mod common;
+
+use cove::routes::ErrorResponse;
+use serde_json::{from_str, json};
+
+/// The API key the server under test accepts. Every test in this file sets the same keys, since
+/// the environment is shared by all tests in the process.
+const API_KEY: &str = "test-api-key";
+
+/// Spawns the app with API keys required.
+async fn spawn_app_with_api_keys() -> common::TestApp {
+    std::env::set_var("COVE_API_KEYS", format!("{API_KEY}:tests"));
+    common::spawn_app().await
+}
+
+/// A request body with an invalid address, which is rejected before any work is done, so the
+/// status code shows whether the request got past authentication.
+fn invalid_address_body() -> serde_json::Value {
+    json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x1234",
+        "buildConfig": { "framework": "foundry" },
+    })
+}
+
+#[tokio::test]
+async fn requests_without_an_api_key_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let app = spawn_app_with_api_keys().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(invalid_address_body().to_string())
+        .send()
+        .await?;
+    assert_eq!(401, response.status().as_u16());
+    let error: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(error.code, "UNAUTHORIZED");
+
+    // Routes that don't do expensive work stay open.
+    let response = client.get(&format!("{}/health", app.address)).send().await?;
+    assert_eq!(200, response.status().as_u16());
+    Ok(())
+}
+
+#[tokio::test]
+async fn requests_with_an_invalid_api_key_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let app = spawn_app_with_api_keys().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/verify", app.address))
+        .header("x-api-key", "not-the-key")
+        .header("Content-Type", "application/json")
+        .body(invalid_address_body().to_string())
+        .send()
+        .await?;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = client
+        .post(&format!("{}/verify", app.address))
+        .bearer_auth("not-the-key")
+        .header("Content-Type", "application/json")
+        .body(invalid_address_body().to_string())
+        .send()
+        .await?;
+    assert_eq!(401, response.status().as_u16());
+    Ok(())
+}
+
+#[tokio::test]
+async fn requests_with_a_valid_api_key_are_allowed() -> Result<(), Box<dyn std::error::Error>> {
+    let app = spawn_app_with_api_keys().await;
+    let client = reqwest::Client::new();
+
+    // The key can be sent in either header. The request then fails validation instead.
+    let requests = [
+        client.post(&format!("{}/verify", app.address)).header("x-api-key", API_KEY),
+        client.post(&format!("{}/verify", app.address)).bearer_auth(API_KEY),
+    ];
+    for request in requests {
+        let response = request
+            .header("Content-Type", "application/json")
+            .body(invalid_address_body().to_string())
+            .send()
+            .await?;
+        assert_eq!(400, response.status().as_u16());
+        let error: ErrorResponse = from_str(&response.text().await?)?;
+        assert_eq!(error.code, "INVALID_ADDRESS");
+    }
+    Ok(())
+}
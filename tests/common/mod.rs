@@ -1,6 +1,23 @@
-use cove::telemetry::{get_subscriber, init_subscriber};
+// Each test binary uses a different subset of these helpers.
+#![allow(dead_code)]
+
+use axum::{routing::post, Json, Router};
+use cove::{
+    cloner::{CloneRequest, RepoCloner},
+    provider::{ChainConfig, MultiChainProvider},
+    routes::VerifyError,
+    startup::AppState,
+    telemetry::{get_subscriber, init_subscriber},
+};
+use ethers::types::{Bytes, Chain};
+use futures::future::BoxFuture;
 use once_cell::sync::Lazy;
-use std::net::TcpListener;
+use serde_json::{json, Value};
+use std::{
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 // Ensure that the `tracing` stack is only initialized once.
 static TRACING: Lazy<()> = Lazy::new(|| {
@@ -47,3 +64,109 @@ pub async fn spawn_app() -> TestApp {
 
     TestApp { address }
 }
+
+// Like `spawn_app`, but serves the app with the given state, such as one with a mocked provider
+// and cloner so the test runs without network access.
+pub async fn spawn_app_with_state(state: AppState) -> TestApp {
+    Lazy::force(&TRACING);
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+    let port = listener.local_addr().unwrap().port();
+    let address = format!("http://127.0.0.1:{port}");
+    let server = cove::startup::run_with_state(listener, state).expect("Failed to bind address");
+    let _ = tokio::spawn(server);
+
+    TestApp { address }
+}
+
+// Spawns a JSON-RPC server standing in for a chain's RPC provider, which has `code` deployed at
+// every address from the first block on, and returns a provider for `chain` connected to it.
+// Calls the verification doesn't depend on, such as looking up transactions, return null.
+pub async fn mock_provider(chain: Chain, code: Bytes) -> MultiChainProvider {
+    let config = mock_chain_config(chain, code).await;
+    MultiChainProvider::from_config(vec![config]).expect("Invalid mock provider")
+}
+
+// Like `mock_provider`, but returns the config for `chain`, so tests can configure other chains
+// alongside it.
+pub async fn mock_chain_config(chain: Chain, code: Bytes) -> ChainConfig {
+    let chain_id = u64::from(chain);
+    let handler = move |Json(request): Json<Value>| {
+        let result = match request["method"].as_str().unwrap_or_default() {
+            "eth_chainId" => json!(format!("{chain_id:#x}")),
+            "eth_blockNumber" => json!("0x64"),
+            "eth_getCode" => json!(code),
+            "eth_getStorageAt" => json!(format!("0x{}", "0".repeat(64))),
+            _ => Value::Null,
+        };
+        async move { Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })) }
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+    let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+    let server = axum::Server::from_tcp(listener).expect("Failed to bind address");
+    tokio::spawn(server.serve(Router::new().route("/", post(handler)).into_make_service()));
+
+    ChainConfig {
+        chain,
+        rpc_url,
+        auth_header: None,
+        explorer: None,
+        trace_creations: false,
+        archive_rpc_url: None,
+    }
+}
+
+// Clones a local fixture repository, whatever repository URL is requested.
+pub struct FixtureCloner {
+    pub repo: PathBuf,
+}
+
+impl RepoCloner for FixtureCloner {
+    fn clone_repo<'a>(
+        &'a self,
+        request: CloneRequest<'a>,
+    ) -> BoxFuture<'a, Result<String, VerifyError>> {
+        Box::pin(async move {
+            let clone_failed = |e: String| VerifyError::CloneFailed(e);
+            let fixture = self.repo.to_str().ok_or_else(|| clone_failed("Invalid path".into()))?;
+            git(request.dest, &["clone", "--quiet", fixture, "."]).map_err(clone_failed)?;
+            git(request.dest, &["checkout", "--quiet", request.repo_commit])
+                .map_err(clone_failed)?;
+            git(request.dest, &["rev-parse", "HEAD"]).map_err(clone_failed)
+        })
+    }
+}
+
+// Runs git with `args` in `dir`, returning its trimmed output.
+pub fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Creates a git repository holding a Foundry project with a single `Counter` contract, returning
+// its directory and the hash of its commit.
+pub fn fixture_repo() -> Result<(tempfile::TempDir, String), Box<dyn std::error::Error>> {
+    let repo = tempfile::tempdir()?;
+    std::fs::write(repo.path().join("foundry.toml"), "[profile.default]\nsrc = 'src'\n")?;
+    std::fs::write(repo.path().join(".gitignore"), "out/\ncache/\n")?;
+    std::fs::create_dir(repo.path().join("src"))?;
+    std::fs::write(
+        repo.path().join("src").join("Counter.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity 0.8.19;\n\n\
+         contract Counter {\n    uint256 public number;\n\n\
+         function increment() public {\n        number++;\n    }\n}\n",
+    )?;
+    git(repo.path(), &["init", "--quiet"])?;
+    git(repo.path(), &["add", "."])?;
+    git(repo.path(), &["commit", "--quiet", "-m", "Add Counter"])?;
+    let commit = git(repo.path(), &["rev-parse", "HEAD"])?;
+    Ok((repo, commit))
+}
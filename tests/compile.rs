@@ -0,0 +1,56 @@
+use serde_json::json;
+mod common;
+use cove::routes::{compile::CompileResult, contract::ErrorResponse};
+use serde_json::from_str;
+
+#[tokio::test]
+async fn compile_returns_artifact_metadata() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractName": "CounterBasic",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    });
+    let response = client.post(&format!("{}/compile", app.address)).json(&body).send().await?;
+    assert_eq!(200, response.status().as_u16());
+
+    let result: CompileResult = from_str(&response.text().await?)?;
+    assert_eq!(result.resolved_commit, "b268862cf1ccf495d6dc20a86c41940dfb386d9b");
+    assert!(!result.artifacts.is_empty());
+    for artifact in &result.artifacts {
+        assert_eq!(artifact.name, "CounterBasic");
+        assert_eq!(artifact.profile.as_deref(), Some("default"));
+        assert!(artifact.path.is_relative(), "{:?}", artifact.path);
+        assert!(artifact.creation_code_length.unwrap_or_default() > 0);
+        assert!(artifact.compiler_version.as_deref().unwrap_or_default().starts_with("0.8."));
+    }
+
+    // Only the default profile was built, so there's a single attempt.
+    assert_eq!(result.build_attempts.len(), 1, "{:?}", result.build_attempts);
+    let attempt = &result.build_attempts[0];
+    assert!(attempt.command.starts_with("FOUNDRY_PROFILE=default forge build"), "{attempt:?}");
+    assert!(attempt.succeeded);
+    assert!(attempt.duration_ms > 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn compile_returns_a_404_for_unknown_contracts() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractName": "DoesNotExist",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    });
+    let response = client.post(&format!("{}/compile", app.address)).json(&body).send().await?;
+    assert_eq!(404, response.status().as_u16());
+    let response_body: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(response_body.code, "NOT_FOUND");
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use serde_json::json;
+mod common;
+use cove::{bytecode::BytecodeDiff, routes::contract::ErrorResponse};
+use serde_json::from_str;
+
+#[tokio::test]
+async fn diff_reports_offset_of_one_byte_difference() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // The same code with the byte at offset 5 changed from 0x34 to 0x35.
+    let body = json!({
+        "foundCode": "0x6080604052348015600f57600080fd5b50",
+        "expectedCode": "6080604052358015600F57600080FD5B50",
+    });
+    let response = client.post(&format!("{}/diff", app.address)).json(&body).send().await?;
+    assert_eq!(200, response.status().as_u16());
+    let diff: BytecodeDiff = from_str(&response.text().await?)?;
+    assert_eq!(diff.first_difference, Some(5));
+    assert_eq!(diff.common_prefix_length, 5);
+    assert_eq!(diff.window_start, 0);
+    assert_eq!(diff.found_window[5], 0x34);
+    assert_eq!(diff.expected_window[5], 0x35);
+    assert!(!diff.difference_in_metadata);
+    Ok(())
+}
+
+#[tokio::test]
+async fn diff_returns_a_400_for_invalid_hex() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let body = json!({ "foundCode": "0x6080", "expectedCode": "0x608" });
+    let response = client.post(&format!("{}/diff", app.address)).json(&body).send().await?;
+    assert_eq!(400, response.status().as_u16());
+    let response_body: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(response_body.code, "BAD_REQUEST");
+    Ok(())
+}
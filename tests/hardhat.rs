@@ -0,0 +1,77 @@
+mod common;
+use common::{git, mock_provider};
+use cove::{
+    cloner::GitCloner,
+    routes::verify::{JobCreated, JobStatus, MatchedCode},
+    startup::AppState,
+};
+use ethers::types::{Bytes, Chain};
+use serde_json::{from_str, json, Value};
+use std::{path::Path, process::Command, str::FromStr, sync::Arc, time::Duration};
+
+/// A small public Hardhat project, with a single `Token` contract.
+const REPO_URL: &str = "https://github.com/NomicFoundation/hardhat-boilerplate";
+
+// Installs the project's packages and compiles it to learn the code `Token` deploys, which the
+// mocked chain then serves.
+fn token_code(repo: &Path) -> Result<Bytes, Box<dyn std::error::Error>> {
+    let status = Command::new("npm")
+        .args(["ci", "--ignore-scripts", "--no-audit", "--no-fund"])
+        .current_dir(repo)
+        .status()?;
+    assert!(status.success(), "Failed to install the project's packages");
+    let status =
+        Command::new("npx").args(["--no", "hardhat", "compile"]).current_dir(repo).status()?;
+    assert!(status.success(), "Failed to compile the project");
+    let artifact = std::fs::read_to_string(repo.join("artifacts/contracts/Token.sol/Token.json"))?;
+    let artifact: Value = from_str(&artifact)?;
+    let code = artifact["deployedBytecode"].as_str().ok_or("No deployed bytecode")?;
+    Ok(Bytes::from_str(code)?)
+}
+
+#[tokio::test]
+async fn verify_hardhat_project() -> Result<(), Box<dyn std::error::Error>> {
+    // Hardhat projects don't commit their packages, so they must be installed.
+    std::env::set_var("INSTALL_DEPENDENCIES", "true");
+
+    let repo = tempfile::tempdir()?;
+    git(repo.path(), &["clone", "--quiet", REPO_URL, "."])?;
+    let commit = git(repo.path(), &["rev-parse", "HEAD"])?;
+    let provider = mock_provider(Chain::Goerli, token_code(repo.path())?).await;
+    let app = common::spawn_app_with_state(AppState::with(provider, Arc::new(GitCloner))).await;
+    let client = reqwest::Client::new();
+
+    let body = json!({
+        "repoUrl": REPO_URL,
+        "repoCommit": commit,
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "hardhat" },
+    });
+    let response = client.post(&format!("{}/verify", app.address)).json(&body).send().await?;
+    assert_eq!(response.status().as_u16(), 202);
+    let JobCreated { job_id } = from_str(&response.text().await?)?;
+
+    let status = loop {
+        let response = client.get(&format!("{}/verify/{job_id}", app.address)).send().await?;
+        match from_str(&response.text().await?)? {
+            JobStatus::Pending => tokio::time::sleep(Duration::from_secs(2)).await,
+            status => break status,
+        }
+    };
+    let result = match status {
+        JobStatus::Success { result } => *result,
+        JobStatus::Failed { status_code, code, error } => {
+            panic!("Failed with {status_code} {code}: {error}")
+        }
+        JobStatus::Pending => unreachable!("Pending jobs are polled again"),
+    };
+    assert_eq!(result.chain, Chain::Goerli);
+    assert_eq!(result.resolved_commit, commit);
+    assert_eq!(result.match_type, MatchedCode::Runtime);
+    assert!(
+        result.artifact_relative_path.ends_with("cove_artifacts/contracts/Token.sol/Token.json"),
+        "{:?}",
+        result.artifact_relative_path
+    );
+    Ok(())
+}
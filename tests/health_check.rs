@@ -19,3 +19,18 @@ async fn health_check_works() {
     assert_eq!(200, response.status().as_u16());
     assert_eq!(Some(0), response.content_length());
 }
+
+#[tokio::test]
+async fn health_works() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(&format!("{}/health", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(Some(0), response.content_length());
+}
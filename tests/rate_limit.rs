@@ -0,0 +1,38 @@
+mod common;
+
+use cove::routes::ErrorResponse;
+use serde_json::from_str;
+
+#[tokio::test]
+async fn requests_over_the_rate_limit_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var("RATE_LIMIT_PER_MINUTE", "2");
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // A request with an empty body is rejected before any work is done, but still counts towards
+    // the limit.
+    let verify = || {
+        client
+            .post(&format!("{}/verify", app.address))
+            .header("Content-Type", "application/json")
+            .body("{}")
+            .send()
+    };
+    for _ in 0..2 {
+        assert_eq!(400, verify().await?.status().as_u16());
+    }
+
+    let response = verify().await?;
+    assert_eq!(429, response.status().as_u16());
+    let retry_after: u64 = response.headers()["retry-after"].to_str()?.parse()?;
+    assert!((1..=60).contains(&retry_after));
+    let response_body: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(response_body.code, "RATE_LIMITED");
+
+    // Cheap routes aren't rate limited.
+    for _ in 0..5 {
+        let response = client.get(&format!("{}/health", app.address)).send().await?;
+        assert_eq!(200, response.status().as_u16());
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use cove::routes::contract::ErrorResponse;
+use reqwest::StatusCode;
+use serde_json::from_str;
+mod common;
+
+#[tokio::test]
+async fn request_id_is_echoed() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // The caller's request ID is returned in the response header.
+    let response = client
+        .get(&format!("{}/health_check", app.address))
+        .header("x-request-id", "trace-3f2b8c1e")
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], "trace-3f2b8c1e");
+
+    // And in the body of errors.
+    let response = client
+        .get(&format!("{}/verify/not-a-job-id", app.address))
+        .header("x-request-id", "trace-3f2b8c1e")
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()["x-request-id"], "trace-3f2b8c1e");
+    let error: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(error.request_id.as_deref(), Some("trace-3f2b8c1e"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn request_id_is_generated() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Without a request ID, or with one that's too long to log, a new one is generated and used
+    // for both the header and the error body.
+    for request_id in [None, Some("x".repeat(200))] {
+        let mut request = client.get(&format!("{}/verify/not-a-job-id", app.address));
+        if let Some(request_id) = &request_id {
+            request = request.header("x-request-id", request_id);
+        }
+        let response = request.send().await?;
+        let header = response.headers()["x-request-id"].to_str()?.to_string();
+        assert!(uuid::Uuid::parse_str(&header).is_ok(), "{header}");
+        let error: ErrorResponse = from_str(&response.text().await?)?;
+        assert_eq!(error.request_id, Some(header));
+    }
+    Ok(())
+}
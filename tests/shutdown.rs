@@ -0,0 +1,62 @@
+use cove::routes::verify::{JobCreated, JobStatus};
+use serde_json::{from_str, json};
+use std::net::TcpListener;
+use tokio::sync::oneshot;
+
+#[tokio::test]
+async fn shutdown_waits_for_running_verifications() -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = cove::startup::run_until(listener, async {
+        let _ = shutdown_rx.await;
+    })?;
+    let server = tokio::spawn(server);
+    let client = reqwest::Client::new();
+
+    // Start a verification, which takes a while since it clones and builds the repository, and
+    // stream its progress so the result can be read after shutdown starts.
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    });
+    let response = client.post(&format!("{address}/verify")).json(&body).send().await?;
+    assert_eq!(202, response.status().as_u16());
+    let JobCreated { job_id } = from_str(&response.text().await?)?;
+    let stream = client.get(&format!("{address}/verify/stream?job_id={job_id}")).send().await?;
+    assert_eq!(200, stream.status().as_u16());
+
+    // Once shutdown starts, new connections are refused.
+    shutdown_tx.send(()).map_err(|_| "Server stopped before shutdown")?;
+    let new_client = reqwest::Client::new();
+    let mut refused = false;
+    for _ in 0..50 {
+        if new_client.get(&format!("{address}/health_check")).send().await.is_err() {
+            refused = true;
+            break
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(refused, "Server accepted connections after shutdown started");
+
+    // The running verification still completes, and the server stops once it has.
+    let events = stream.text().await?;
+    let result = events
+        .lines()
+        .skip_while(|line| line.trim() != "event: result")
+        .find_map(|line| line.strip_prefix("data:"))
+        .ok_or("The stream ended without a result")?;
+    let status: serde_json::Value = from_str(result.trim())?;
+    let status: JobStatus = serde_json::from_value(status["status"].clone())?;
+    match status {
+        JobStatus::Success { .. } => {}
+        JobStatus::Failed { code, error, .. } => {
+            panic!("Verification failed during shutdown with {code}: {error}")
+        }
+        JobStatus::Pending => panic!("The result event had a pending status"),
+    }
+    server.await??;
+    Ok(())
+}
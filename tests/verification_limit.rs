@@ -0,0 +1,38 @@
+mod common;
+
+use cove::routes::ErrorResponse;
+use serde_json::{from_str, json};
+
+#[tokio::test]
+async fn verifications_over_the_limit_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var("MAX_CONCURRENT_VERIFICATIONS", "1");
+    std::env::set_var("MAX_QUEUED_VERIFICATIONS", "0");
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // The first verification takes a while, since it clones and builds the repository, so it's
+    // still running when the second is requested.
+    let verify = |contract_address: &str| {
+        let body = json!({
+            "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+            "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+            "contractAddress": contract_address,
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        });
+        client.post(&format!("{}/verify", app.address)).json(&body).send()
+    };
+    let response = verify("0x8d56e3e001132d84488DbacDbB01AfB8C3171242").await?;
+    assert_eq!(202, response.status().as_u16());
+
+    let response = verify("0x0000000000000000000000000000000000000001").await?;
+    assert_eq!(503, response.status().as_u16());
+    let retry_after: u64 = response.headers()["retry-after"].to_str()?.parse()?;
+    assert!(retry_after > 0);
+    let response_body: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(response_body.code, "SERVICE_UNAVAILABLE");
+
+    // Health checks aren't limited.
+    let response = client.get(&format!("{}/health", app.address)).send().await?;
+    assert_eq!(200, response.status().as_u16());
+    Ok(())
+}
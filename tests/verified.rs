@@ -0,0 +1,82 @@
+mod common;
+use cove::{
+    routes::contract::ErrorResponse,
+    storage::{sqlite::SqliteStore, StoredVerification, VerificationStore},
+};
+use ethers::types::{Address, Chain};
+use reqwest::{header, StatusCode};
+use serde_json::{from_str, json};
+use std::str::FromStr;
+
+/// The commit the saved verification is for.
+const COMMIT: &str = "b268862cf1ccf495d6dc20a86c41940dfb386d9b";
+
+#[tokio::test]
+async fn verified_returns_saved_verifications(
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Point the app at a fresh database, with one contract verified on mainnet.
+    let database_dir = tempfile::tempdir()?;
+    let database_url = format!("sqlite://{}", database_dir.path().join("cove.db").display());
+    std::env::set_var("DATABASE_URL", &database_url);
+    let address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let verification = StoredVerification {
+        chain: Chain::Mainnet,
+        address,
+        repo_url: "https://github.com/ScopeLift/cove-test-repo".to_string(),
+        repo_commit: COMMIT.to_string(),
+        verified_at: 1_700_000_000,
+        verification: json!({ "matched_chains": ["mainnet"] }),
+    };
+    SqliteStore::connect_lazy(&database_url)?.save(&verification).await?;
+
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // A saved verification is returned with the commit as its ETag, by chain name or ID.
+    for chain in ["mainnet", "1"] {
+        let url = format!("{}/verified/{chain}/{address:?}", app.address);
+        let response = client.get(&url).send().await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers().get(header::ETAG).and_then(|etag| etag.to_str().ok());
+        assert_eq!(etag, Some(format!("\"{COMMIT}\"").as_str()));
+        let saved = from_str::<StoredVerification>(&response.text().await?)?;
+        assert_eq!(saved, verification);
+    }
+
+    // Clients with the current version get a 304.
+    let url = format!("{}/verified/mainnet/{address:?}", app.address);
+    let response =
+        client.get(&url).header(header::IF_NONE_MATCH, format!("\"{COMMIT}\"")).send().await?;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    let response = client.get(&url).header(header::IF_NONE_MATCH, "\"stale\"").send().await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Contracts that weren't verified on the chain are a 404.
+    let response =
+        client.get(&format!("{}/verified/goerli/{address:?}", app.address)).send().await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let error = from_str::<ErrorResponse>(&response.text().await?)?;
+    assert_eq!(error.code, "NOT_FOUND");
+    Ok(())
+}
+
+#[tokio::test]
+async fn verified_rejects_invalid_chains_and_addresses() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(&format!(
+            "{}/verified/not-a-chain/0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+            app.address
+        ))
+        .send()
+        .await?;
+    let error = from_str::<ErrorResponse>(&response.text().await?)?;
+    assert_eq!(error.code, "UNSUPPORTED_CHAIN");
+
+    let response = client.get(&format!("{}/verified/mainnet/0x1234", app.address)).send().await?;
+    let error = from_str::<ErrorResponse>(&response.text().await?)?;
+    assert_eq!(error.code, "INVALID_ADDRESS");
+    Ok(())
+}
@@ -1,7 +1,19 @@
 use serde_json::json;
 mod common;
-use cove::routes::verify::SuccessfulVerification;
+use common::TestApp;
+use cove::routes::{
+    contract::ErrorResponse,
+    verify::{JobCreated, JobStatus, MatchedCode, SuccessfulVerification},
+};
+use ethers::types::Chain;
 use serde_json::from_str;
+use std::time::{Duration, Instant};
+
+/// How often to poll a verification job for its status.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait for a verification job to complete before failing the test.
+const JOB_TIMEOUT: Duration = Duration::from_secs(30 * 60);
 
 #[tokio::test]
 async fn verify_counters() -> Result<(), Box<dyn std::error::Error>> {
@@ -370,17 +382,9 @@ async fn verify_counters() -> Result<(), Box<dyn std::error::Error>> {
             "creationTxHashes": Some(test_case.creation_tx_hashes),
         });
 
-        let response = client
-            .post(&format!("{}/verify", app.address))
-            .header("Content-Type", "application/json")
-            .body(body.to_string())
-            .send()
-            .await?;
-
-        // Assertions.
-        // let response_body = response.text().await?;
-        // println!("response_body {:?}", response_body);
-        assert_eq!(200, response.status().as_u16(), "Test case {i} failed");
+        let status = verify_and_wait(&app, &client, &body).await?;
+        let JobStatus::Success { result } = status else { panic!("Test case {i} failed") };
+        assert_eq!(result.profile.as_deref(), Some(test_case.build_hint.as_str()), "Test case {i}");
     }
 
     Ok(())
@@ -405,12 +409,14 @@ async fn verify_seaport() -> Result<(), Box<dyn std::error::Error>> {
             "gnosis_chain": "0xfc189820c60536e2ce90443ac3d39633583cfed6653d5f7edd7c0e115fd2a18b",
         }),
     )
-    .await
+    .await?;
+    Ok(())
 }
 
 #[tokio::test]
 async fn verify_gitcoin_governor_alpha() -> Result<(), Box<dyn std::error::Error>> {
-    run_integration_test(
+    // This contract is only deployed on mainnet, so that must be the chain in the response.
+    let verification = run_integration_test(
         "https://github.com/gitcoinco/Alpha-Governor-Upgrade",
         "17f7717eec0604505da2faf3f65516a8619063a0",
         "0x1a84384e1f1b12D53E60C8C528178dC87767b488",
@@ -422,7 +428,16 @@ async fn verify_gitcoin_governor_alpha() -> Result<(), Box<dyn std::error::Error
             "mainnet": "0x61d669c6c0b976637b8f4528b99b170f060227b2bc20892743f22c6a34c84e23"
         }),
     )
-    .await
+    .await?;
+    assert_eq!(verification.chain, Chain::Mainnet);
+    assert_eq!(verification.chains, vec![Chain::Mainnet]);
+    assert_eq!(verification.chains_with_code, vec![Chain::Mainnet]);
+    assert_eq!(verification.matched_chains, vec![Chain::Mainnet]);
+    assert_eq!(verification.chain_verifications.len(), 1);
+    assert_eq!(verification.match_type, MatchedCode::Creation);
+    assert!(verification.chain_verifications[0].creation_code.is_some());
+    assert!(verification.chain_verifications[0].runtime_code.is_some());
+    Ok(())
 }
 
 #[tokio::test]
@@ -439,7 +454,127 @@ async fn verify_traderjoe_lbrouter_avalanche() -> Result<(), Box<dyn std::error:
             "avalanche": "0xfb3fba071ca9b76edf5a2bf3eddff271a1bbc765dfacd7b1b6c2be3e13bdb1ec"
         }),
     )
-    .await
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_with_profile() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // This contract was built with a non-default profile that disables the optimizer, so it only
+    // matches when that profile is built.
+    let mut body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x1F6891359Ac22adc47f8665F43dDcF4b6Ba107e9",
+        "buildConfig": { "framework": "foundry" },
+        "profile": "no_optimizer_no_metadata",
+        "creationTxHashes": {
+            "goerli": "0xca8b3d45156e889ae1bd8aafe7aed5f70a6669d36490ac8faf195e06d65ebe03",
+            "sepolia": "0xc43bb699bb31b7a5ff8e0af46a24999be30af10d45e21050649b847674d29b69",
+        },
+    });
+    let status = verify_and_wait(&app, &client, &body).await?;
+    let JobStatus::Success { result } = status else { panic!("Expected verification to succeed") };
+    assert_eq!(result.profile.as_deref(), Some("no_optimizer_no_metadata"));
+    assert!(result
+        .build_command
+        .starts_with("FOUNDRY_PROFILE=no_optimizer_no_metadata forge build"));
+    assert!(result.artifact_relative_path.ends_with(".json"));
+    assert!(!result.artifact_relative_path.starts_with('/'));
+    // Only the requested profile was built, so there's one attempt, for the matching command.
+    assert_eq!(result.build_attempts.len(), 1, "{:?}", result.build_attempts);
+    assert_eq!(result.build_attempts[0].command, result.build_command);
+    assert!(result.build_attempts[0].succeeded);
+    for source in &result.sources {
+        let hash = &result.source_hashes[&source.path.to_string_lossy().to_string()];
+        assert!(hash.starts_with("0x") && hash.len() == 66, "Invalid source hash {hash}");
+    }
+    // The repository imports forge-std through the remapping forge infers for it.
+    assert!(
+        result.remappings.iter().any(|remapping| remapping == "forge-std/=lib/forge-std/src/"),
+        "{:?}",
+        result.remappings
+    );
+
+    // Restricting the build to the default profile means nothing matches.
+    body["profile"] = json!("default");
+    let status = verify_and_wait(&app, &client, &body).await?;
+    let JobStatus::Failed { status_code, code, .. } = status else {
+        panic!("Expected verification to fail")
+    };
+    assert_eq!((status_code, code.as_str()), (409, "NO_BYTECODE_MATCH"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_with_runtime_code_only() -> Result<(), Box<dyn std::error::Error>> {
+    // Without creation transaction hashes the creation code can't be fetched, so verification
+    // falls back to matching the runtime code.
+    let verification = run_integration_test(
+        "https://github.com/ScopeLift/cove-test-repo",
+        "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        json!({
+            "framework": "foundry",
+            "buildHint": "default"
+        }),
+        json!({}),
+    )
+    .await?;
+    assert_eq!(verification.match_type, MatchedCode::Runtime);
+    assert!(verification.chains_with_code.is_empty());
+    for chain_verification in &verification.chain_verifications {
+        assert_eq!(chain_verification.match_type, MatchedCode::Runtime);
+        assert!(chain_verification.creation_code.is_none());
+        assert!(chain_verification.runtime_code.is_some());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_concurrent_different_repos() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Two verifications against different repos, fired at the same time. Each build must run in its
+    // own project directory, otherwise one request can build in the other's temp directory.
+    let counter_body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": {
+            "goerli": "0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6",
+            "sepolia": "0xf9899c9d982e7a7d074f6792c3689b1c0a25d14eaa9f065ce31bfa4ea59607b2",
+        },
+    });
+    let governor_body = json!({
+        "repoUrl": "https://github.com/gitcoinco/Alpha-Governor-Upgrade",
+        "repoCommit": "17f7717eec0604505da2faf3f65516a8619063a0",
+        "contractAddress": "0x1a84384e1f1b12D53E60C8C528178dC87767b488",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": {
+            "mainnet": "0x61d669c6c0b976637b8f4528b99b170f060227b2bc20892743f22c6a34c84e23"
+        },
+    });
+
+    let (counter_status, governor_status) = tokio::join!(
+        verify_and_wait(&app, &client, &counter_body),
+        verify_and_wait(&app, &client, &governor_body),
+    );
+
+    let JobStatus::Success { result: counter } = counter_status? else {
+        panic!("Counter verification failed")
+    };
+    let JobStatus::Success { result: governor } = governor_status? else {
+        panic!("Governor verification failed")
+    };
+    assert_eq!(counter.repo_url, "https://github.com/ScopeLift/cove-test-repo");
+    assert_eq!(governor.repo_url, "https://github.com/gitcoinco/Alpha-Governor-Upgrade");
+    Ok(())
 }
 
 async fn run_integration_test(
@@ -448,7 +583,7 @@ async fn run_integration_test(
     contract_address: &str,
     build_config: serde_json::Value,
     creation_tx_hashes: serde_json::Value,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<SuccessfulVerification, Box<dyn std::error::Error>> {
     let app = common::spawn_app().await;
     let client = reqwest::Client::new();
 
@@ -461,20 +596,426 @@ async fn run_integration_test(
         "creationTxHashes": Some(creation_tx_hashes),
     });
 
-    // Send request.
+    // Send request and wait for the job to complete.
+    let status = verify_and_wait(&app, &client, &body).await?;
+    let verification_result = match status {
+        JobStatus::Success { result } => *result,
+        JobStatus::Failed { status_code, code, error } => {
+            panic!("Failed with {status_code} {code}: {error}")
+        }
+        JobStatus::Pending => unreachable!("verify_and_wait only returns completed jobs"),
+    };
+    assert_eq!(repo_url, verification_result.repo_url);
+    assert_eq!(repo_commit, verification_result.repo_commit);
+    assert_eq!(repo_commit, verification_result.resolved_commit);
+    // Verified artifacts were compiled by the same solc as the on-chain code.
+    assert!(verification_result.warnings.is_empty(), "{:?}", verification_result.warnings);
+    Ok(verification_result)
+}
+
+/// Submits a verification job and polls it until it is no longer pending, returning the final
+/// status.
+async fn verify_and_wait(
+    app: &TestApp,
+    client: &reqwest::Client,
+    body: &serde_json::Value,
+) -> Result<JobStatus, Box<dyn std::error::Error>> {
+    let response = client.post(&format!("{}/verify", app.address)).json(body).send().await?;
+    assert_eq!(202, response.status().as_u16());
+    let JobCreated { job_id } = from_str(&response.text().await?)?;
+
+    let start = Instant::now();
+    loop {
+        let response = client.get(&format!("{}/verify/{job_id}", app.address)).send().await?;
+        assert_eq!(200, response.status().as_u16());
+        match from_str(&response.text().await?)? {
+            JobStatus::Pending => {}
+            status => return Ok(status),
+        }
+
+        if start.elapsed() > JOB_TIMEOUT {
+            return Err(format!("Verification job {job_id} did not complete in time").into())
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[tokio::test]
+async fn verify_job_polls_to_failure_for_nonexistent_repos(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/this-repo-does-not-exist",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    });
+
+    let status = verify_and_wait(&app, &client, &body).await?;
+    let JobStatus::Failed { status_code, code, .. } = status else {
+        panic!("Expected job to fail")
+    };
+    assert_eq!(400, status_code);
+    assert_eq!("CLONE_FAILED", code);
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_job_fails_early_for_nonexistent_commits() -> Result<(), Box<dyn std::error::Error>>
+{
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "0000000000000000000000000000000000000001",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    });
+
+    let status = verify_and_wait(&app, &client, &body).await?;
+    let JobStatus::Failed { status_code, code, .. } = status else {
+        panic!("Expected job to fail")
+    };
+    assert_eq!(400, status_code);
+    assert_eq!("COMMIT_NOT_FOUND", code);
+    Ok(())
+}
+
+#[tokio::test]
+async fn metrics_are_reported_after_a_verification() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "0000000000000000000000000000000000000002",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    });
+    verify_and_wait(&app, &client, &body).await?;
+
+    let response = client.get(&format!("{}/metrics", app.address)).send().await?;
+    assert_eq!(200, response.status().as_u16());
+    let content_type = response.headers()[reqwest::header::CONTENT_TYPE].to_str()?.to_string();
+    assert!(content_type.starts_with("text/plain; version=0.0.4"));
+
+    let metrics = response.text().await?;
+    for name in [
+        "cove_verifications_total{outcome=\"COMMIT_NOT_FOUND\"}",
+        "cove_verification_jobs_in_flight",
+        "cove_clone_duration_seconds_bucket",
+        "cove_build_duration_seconds_bucket",
+        "cove_rpc_duration_seconds_bucket",
+    ] {
+        assert!(metrics.contains(name), "Missing metric {name} in:\n{metrics}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_job_fails_with_a_404_for_addresses_without_code(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let test_cases = vec![
+        // The burn address, which holds funds on every chain but has no code, like an EOA.
+        ("0x000000000000000000000000000000000000dEaD", "EOA"),
+        // An address no contract has been deployed to.
+        ("0x1111111111111111111111111111111111111111", "nonexistent contract"),
+    ];
+
+    for (contract_address, description) in test_cases {
+        let body = json!({
+            "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+            "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+            "contractAddress": contract_address,
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        });
+
+        let status = verify_and_wait(&app, &client, &body).await?;
+        let JobStatus::Failed { status_code, code, .. } = status else {
+            panic!("Expected job to fail for {description}")
+        };
+        assert_eq!(404, status_code, "{description}");
+        assert_eq!("CONTRACT_NOT_FOUND", code, "{description}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_status_returns_a_404_for_unknown_jobs() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let job_id = uuid::Uuid::new_v4();
+    let response = client.get(&format!("{}/verify/{job_id}", app.address)).send().await?;
+    assert_eq!(404, response.status().as_u16());
+    let response_body: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(response_body.code, "NOT_FOUND");
+
+    // Malformed job IDs are rejected with a JSON error body too.
+    let response = client.get(&format!("{}/verify/not-a-uuid", app.address)).send().await?;
+    assert_eq!(400, response.status().as_u16());
+    let response_body: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(response_body.code, "BAD_REQUEST");
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_stream_reports_each_stage_in_order() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    });
+    let response = client.post(&format!("{}/verify", app.address)).json(&body).send().await?;
+    assert_eq!(202, response.status().as_u16());
+    let JobCreated { job_id } = from_str(&response.text().await?)?;
+
+    // The stream ends after the result, so the whole body can be read at once.
+    let response = client
+        .get(&format!("{}/verify/stream?job_id={job_id}", app.address))
+        .timeout(JOB_TIMEOUT)
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+    let body = response.text().await?;
+    let mut stages: Vec<&str> =
+        body.lines().filter_map(|line| line.strip_prefix("event:")).map(str::trim).collect();
+    // There's a `matched` event for each chain the contract matches on.
+    stages.dedup();
+    assert_eq!(stages, ["cloning", "building", "comparing", "matched", "result"]);
+    assert!(body.contains(r#""status":"success""#));
+
+    // Unknown jobs have no stream.
+    let job_id = uuid::Uuid::new_v4();
+    let url = format!("{}/verify/stream?job_id={job_id}", app.address);
+    let response = client.get(&url).send().await?;
+    assert_eq!(404, response.status().as_u16());
+    let response_body: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(response_body.code, "NOT_FOUND");
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_responses_are_compressed() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    });
+    let response = client.post(&format!("{}/verify", app.address)).json(&body).send().await?;
+    let JobCreated { job_id } = from_str(&response.text().await?)?;
+    let status_url = format!("{}/verify/{job_id}", app.address);
+
+    // Wait for the job to finish, without asking for compression.
+    let start = Instant::now();
+    let uncompressed = loop {
+        let response = client.get(&status_url).send().await?;
+        assert!(response.headers().get("content-encoding").is_none());
+        let text = response.text().await?;
+        if !matches!(from_str(&text)?, JobStatus::Pending) {
+            break text
+        }
+        if start.elapsed() > JOB_TIMEOUT {
+            return Err(format!("Verification job {job_id} did not complete in time").into())
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    };
+    assert!(matches!(from_str(&uncompressed)?, JobStatus::Success { .. }));
+
+    // The same result is compressed when the client accepts it.
+    let response = client.get(&status_url).header("Accept-Encoding", "gzip").send().await?;
+    assert_eq!(response.headers()["content-encoding"], "gzip");
+    assert!(response.bytes().await?.len() < uncompressed.len());
+
+    // So are errors.
+    let unknown_job = format!("{}/verify/{}", app.address, uuid::Uuid::new_v4());
+    let response = client.get(&unknown_job).header("Accept-Encoding", "gzip").send().await?;
+    assert_eq!(404, response.status().as_u16());
+    assert_eq!(response.headers()["content-encoding"], "gzip");
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_returns_a_json_error_for_malformed_bodies() -> Result<(), Box<dyn std::error::Error>>
+{
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
     let response = client
         .post(&format!("{}/verify", app.address))
         .header("Content-Type", "application/json")
-        .body(body.to_string())
+        .body(r#"{ "repoUrl": "https://github.com/ScopeLift/cove-test-repo" }"#)
         .send()
         .await?;
+    assert_eq!(400, response.status().as_u16());
+    let response_body: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(response_body.code, "BAD_REQUEST");
+    Ok(())
+}
 
-    assert_eq!(200, response.status().as_u16());
+#[tokio::test]
+async fn verify_returns_a_400_for_unsupported_chains() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Moonbeam isn't one of the configured chains, and Blast can't be configured at all.
+    for chain in [json!("moonbeam"), json!("blast"), json!(81457)] {
+        let body = json!({
+            "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+            "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+            "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+            "chain": chain,
+        });
+
+        let response = client
+            .post(&format!("{}/verify", app.address))
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await?;
+
+        assert_eq!(400, response.status().as_u16(), "{chain}");
+        let response_body: ErrorResponse = from_str(&response.text().await?)?;
+        assert_eq!(response_body.code, "UNSUPPORTED_CHAIN", "{chain}");
+        if chain != "moonbeam" {
+            assert!(response_body.message.contains("blast (81457)"), "{}", response_body.message);
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_returns_a_400_for_invalid_repo_urls() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let test_cases = vec![
+        ("file:///etc/passwd", "local file"),
+        ("https://127.0.0.1/repo.git", "loopback address"),
+        ("https://10.0.0.1/repo.git", "private address"),
+        ("git://github.com/ScopeLift/cove-test-repo", "non-https scheme"),
+    ];
+
+    for (repo_url, description) in test_cases {
+        let body = json!({
+            "repoUrl": repo_url,
+            "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+            "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        });
+
+        let response = client
+            .post(&format!("{}/verify", app.address))
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await?;
+
+        assert_eq!(400, response.status().as_u16(), "{description}");
+        let response_body: ErrorResponse = from_str(&response.text().await?)?;
+        assert_eq!(response_body.code, "INVALID_REPO_URL", "{description}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_returns_a_400_for_project_paths_outside_the_repo(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    for project_path in ["../contracts", "/etc", ""] {
+        let body = json!({
+            "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+            "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+            "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+            "projectPath": project_path,
+        });
+        let response = client.post(&format!("{}/verify", app.address)).json(&body).send().await?;
+
+        assert_eq!(400, response.status().as_u16(), "{project_path}");
+        let response_body: ErrorResponse = from_str(&response.text().await?)?;
+        assert_eq!(response_body.code, "BAD_REQUEST", "{project_path}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_returns_a_400_for_invalid_contract_addresses(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let test_cases = vec![
+        ("", "empty string"),
+        ("0x123", "odd-length hex"),
+        ("0x1234", "too-short hex"),
+        ("0x8d56e3e001132d84488DbacDbB01AfB8C317124200", "too-long hex"),
+        ("0xZZ56e3e001132d84488DbacDbB01AfB8C3171242", "non-hex characters"),
+    ];
+
+    for (contract_address, description) in test_cases {
+        let body = json!({
+            "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+            "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+            "contractAddress": contract_address,
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        });
+
+        let response = client
+            .post(&format!("{}/verify", app.address))
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await?;
+
+        assert_eq!(400, response.status().as_u16(), "{description}");
+        let response_body: ErrorResponse = from_str(&response.text().await?)?;
+        assert_eq!(response_body.code, "INVALID_ADDRESS", "{description}");
+        assert!(response_body.message.contains("invalid contract_address"), "{description}");
+    }
+
+    // Valid addresses are accepted in any of these forms, so the request fails later on the bad
+    // repo URL instead of on the address.
+    let test_cases = vec![
+        ("0x8d56e3e001132d84488DbacDbB01AfB8C3171242", "checksummed"),
+        ("0x8d56e3e001132d84488dbacdbb01afb8c3171242", "lowercase"),
+        ("0X8D56E3E001132D84488DBACDBB01AFB8C3171242", "uppercase"),
+        ("8d56e3e001132d84488DbacDbB01AfB8C3171242", "no 0x prefix"),
+        (" 0x8d56e3e001132d84488DbacDbB01AfB8C3171242\n", "surrounding whitespace"),
+    ];
+    for (contract_address, description) in test_cases {
+        let body = json!({
+            "repoUrl": "https://github.com/ScopeLift/this-repo-does-not-exist",
+            "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+            "contractAddress": contract_address,
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        });
+        let response = client
+            .post(&format!("{}/verify", app.address))
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await?;
+        let response_body = response.text().await?;
+        assert!(!response_body.contains("invalid contract_address"), "{description}");
+    }
 
-    let response_body = response.text().await?;
-    let verification_result: SuccessfulVerification =
-        from_str(&response_body).expect("Failed to deserialize SuccessfulVerification");
-    assert_eq!(repo_url, verification_result.repo_url);
-    assert_eq!(repo_commit, verification_result.repo_commit);
     Ok(())
 }
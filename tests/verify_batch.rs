@@ -0,0 +1,94 @@
+use serde_json::{from_str, json};
+mod common;
+use cove::routes::{
+    contract::ErrorResponse,
+    verify::JobStatus,
+    verify_batch::{BatchVerification, BatchVerificationResponse},
+};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn verify_batch_from_one_build() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Two contracts deployed from the same commit and profile of
+    // https://github.com/ScopeLift/cove-test-repo, so a single build verifies both.
+    let contracts = json!([
+        {
+            // CounterBasic, create.
+            "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+            "creationTxHashes": {
+                "goerli": "0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6",
+                "sepolia": "0xf9899c9d982e7a7d074f6792c3689b1c0a25d14eaa9f065ce31bfa4ea59607b2",
+            },
+        },
+        {
+            // CounterWithImmutables, create.
+            "contractAddress": "0xB264f440D77528320c74E215d5d18885060813fC",
+            "contractName": "CounterWithImmutables",
+            "creationTxHashes": {
+                "goerli": "0x99456f8b0f785dee828ca9c1ec76a25b758b728fd8b63d1e6cbf6329ed3faf2b",
+                "sepolia": "0x77f13d8f7df7da31fabc485c2a2c7a58e9c39636e5877cb2e18df38e5986e56e",
+            },
+        },
+    ]);
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "contracts": contracts,
+    });
+
+    let response = client.post(&format!("{}/verify-batch", app.address)).json(&body).send().await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let BatchVerificationResponse { results } = from_str(&response.text().await?)?;
+    assert_eq!(results.len(), 2);
+
+    // Results are in the order the contracts were requested, and both come from the one build.
+    let expected = [
+        ("0x8d56e3e001132d84488DbacDbB01AfB8C3171242", "CounterBasic"),
+        ("0xB264f440D77528320c74E215d5d18885060813fC", "CounterWithImmutables"),
+    ];
+    let mut attempts = Vec::new();
+    for (BatchVerification { contract_address, status }, (address, name)) in
+        results.into_iter().zip(expected)
+    {
+        assert_eq!(contract_address, address);
+        let JobStatus::Success { result } = status else { panic!("{address} failed to verify") };
+        assert!(result.artifact_relative_path.ends_with(&format!("{name}.json")), "{address}");
+        assert_eq!(result.profile.as_deref(), Some("default"));
+        attempts.push(result.build_attempts.len());
+    }
+    assert_eq!(attempts, vec![1, 1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_batch_rejects_invalid_batches() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // The contract address belongs in each entry of `contracts`, not the shared fields.
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "buildConfig": { "framework": "foundry" },
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "contracts": [{ "contractAddress": "0xB264f440D77528320c74E215d5d18885060813fC" }],
+    });
+    let response = client.post(&format!("{}/verify-batch", app.address)).json(&body).send().await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let error: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(error.code, "BAD_REQUEST");
+
+    // Each contract's address is validated before anything is cloned.
+    let mut body = body;
+    body.as_object_mut().ok_or("Not an object")?.remove("contractAddress");
+    body["contracts"] = json!([{ "contractAddress": "not an address" }]);
+    let response = client.post(&format!("{}/verify-batch", app.address)).json(&body).send().await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let error: ErrorResponse = from_str(&response.text().await?)?;
+    assert_eq!(error.code, "INVALID_ADDRESS");
+    Ok(())
+}
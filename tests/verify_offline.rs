@@ -0,0 +1,146 @@
+mod common;
+use common::{fixture_repo, mock_chain_config, mock_provider, FixtureCloner};
+use cove::{
+    provider::{ChainConfig, MultiChainProvider},
+    routes::verify::{JobCreated, JobStatus, SuccessfulVerification},
+    startup::AppState,
+};
+use ethers::types::{Bytes, Chain};
+use serde_json::{from_str, json, Value};
+use std::{
+    path::Path,
+    process::Command,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+// Builds the fixture to learn the code it deploys, which the mocked chain then serves. Only
+// installing solc, if it isn't installed already, needs the network.
+fn fixture_code(repo: &Path) -> Result<Bytes, Box<dyn std::error::Error>> {
+    let status = Command::new("forge").args(["build", "--quiet"]).current_dir(repo).status()?;
+    assert!(status.success(), "Failed to build the fixture");
+    let artifact = std::fs::read_to_string(repo.join("out/Counter.sol/Counter.json"))?;
+    let artifact: Value = from_str(&artifact)?;
+    let code = artifact["deployedBytecode"]["object"].as_str().ok_or("No deployed bytecode")?;
+    Ok(Bytes::from_str(code)?)
+}
+
+// Returns the request body that verifies the fixture at `commit`. The repository URL is never
+// cloned from, since the fixture is cloned instead.
+fn fixture_request(commit: &str) -> Value {
+    json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-fixture",
+        "repoCommit": commit,
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    })
+}
+
+// Verifies the fixture at `commit` against the given provider, returning the result once the job
+// finishes.
+async fn verify_fixture(
+    provider: MultiChainProvider,
+    repo: &Path,
+    commit: &str,
+) -> Result<SuccessfulVerification, Box<dyn std::error::Error>> {
+    let cloner = Arc::new(FixtureCloner { repo: repo.to_path_buf() });
+    let app = common::spawn_app_with_state(AppState::with(provider, cloner)).await;
+    let client = reqwest::Client::new();
+
+    let body = fixture_request(commit);
+    let response = client.post(&format!("{}/verify", app.address)).json(&body).send().await?;
+    assert_eq!(response.status().as_u16(), 202);
+    let JobCreated { job_id } = from_str(&response.text().await?)?;
+
+    let status = loop {
+        let response = client.get(&format!("{}/verify/{job_id}", app.address)).send().await?;
+        match from_str(&response.text().await?)? {
+            JobStatus::Pending => tokio::time::sleep(Duration::from_millis(200)).await,
+            status => break status,
+        }
+    };
+    match status {
+        JobStatus::Success { result } => Ok(*result),
+        JobStatus::Failed { status_code, code, error } => {
+            panic!("Failed with {status_code} {code}: {error}")
+        }
+        JobStatus::Pending => unreachable!("Pending jobs are polled again"),
+    }
+}
+
+#[tokio::test]
+async fn verify_fixture_repo_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, commit) = fixture_repo()?;
+    let provider = mock_provider(Chain::Goerli, fixture_code(repo.path())?).await;
+
+    let result = verify_fixture(provider, repo.path(), &commit).await?;
+    assert_eq!(result.chain, Chain::Goerli);
+    assert_eq!(result.resolved_commit, commit);
+    assert!(result.artifact_relative_path.ends_with("Counter.json"));
+    assert!(result.chain_errors.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_with_a_chain_down() -> Result<(), Box<dyn std::error::Error>> {
+    // Sepolia's RPC provider refuses connections, while Goerli has the fixture deployed.
+    let (repo, commit) = fixture_repo()?;
+    let goerli = mock_chain_config(Chain::Goerli, fixture_code(repo.path())?).await;
+    let sepolia = ChainConfig {
+        chain: Chain::Sepolia,
+        rpc_url: "http://127.0.0.1:1".to_string(),
+        auth_header: None,
+        explorer: None,
+        trace_creations: false,
+        archive_rpc_url: None,
+    };
+    let provider = MultiChainProvider::from_config(vec![goerli, sepolia])?;
+
+    // The contract is verified on Goerli, and Sepolia is reported as inconclusive rather than as
+    // not having the contract.
+    let result = verify_fixture(provider, repo.path(), &commit).await?;
+    assert_eq!(result.matched_chains, vec![Chain::Goerli]);
+    assert_eq!(result.chain_errors.keys().collect::<Vec<_>>(), vec![&Chain::Sepolia]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn finished_jobs_are_evicted_after_the_retention() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, commit) = fixture_repo()?;
+    let provider = mock_provider(Chain::Goerli, fixture_code(repo.path())?).await;
+    let cloner = Arc::new(FixtureCloner { repo: repo.path().to_path_buf() });
+    let mut state = AppState::with(provider, cloner);
+    state.job_retention = Duration::from_millis(500);
+    let app = common::spawn_app_with_state(state.clone()).await;
+    let client = reqwest::Client::new();
+
+    let body = fixture_request(&commit);
+    let response = client.post(&format!("{}/verify", app.address)).json(&body).send().await?;
+    assert_eq!(response.status().as_u16(), 202);
+    let JobCreated { job_id } = from_str(&response.text().await?)?;
+    let status_url = format!("{}/verify/{job_id}", app.address);
+
+    // The job is found until it's been finished for the retention period, and then it's gone.
+    loop {
+        let response = client.get(&status_url).send().await?;
+        assert_eq!(response.status().as_u16(), 200);
+        match from_str(&response.text().await?)? {
+            JobStatus::Pending => tokio::time::sleep(Duration::from_millis(200)).await,
+            JobStatus::Success { .. } => break,
+            JobStatus::Failed { error, .. } => panic!("Failed: {error}"),
+        }
+    }
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while client.get(&status_url).send().await?.status().as_u16() != 404 {
+        assert!(Instant::now() < deadline, "The finished job wasn't evicted");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    // Its progress is evicted along with its status, so it can't be streamed either.
+    assert!(state.jobs.lock().map_err(|e| e.to_string())?.is_empty());
+    assert!(state.progress.lock().map_err(|e| e.to_string())?.is_empty());
+    let stream_url = format!("{}/verify/stream?job_id={job_id}", app.address);
+    assert_eq!(client.get(&stream_url).send().await?.status().as_u16(), 404);
+    Ok(())
+}